@@ -0,0 +1,23 @@
+-- Standardize OHLCV and TECHNICAL_INDICATORS timestamps on RFC 3339 UTC text
+-- (e.g. 2024-01-02T02:00:00+00:00), the encoding sqlx uses for DateTime<Utc>.
+-- Rows written as integer epoch milliseconds are rewritten so that range filters and
+-- MAX(timestamp) compare like with like.
+
+-- Child and parent keys change together; check the foreign keys at commit
+PRAGMA defer_foreign_keys = ON;
+
+UPDATE OHLCV
+SET timestamp = CASE
+        WHEN timestamp % 1000 = 0
+            THEN strftime('%Y-%m-%dT%H:%M:%S', timestamp / 1000, 'unixepoch')
+        ELSE strftime('%Y-%m-%dT%H:%M:%f', timestamp / 1000.0, 'unixepoch')
+    END || '+00:00'
+WHERE typeof(timestamp) = 'integer';
+
+UPDATE TECHNICAL_INDICATORS
+SET timestamp = CASE
+        WHEN timestamp % 1000 = 0
+            THEN strftime('%Y-%m-%dT%H:%M:%S', timestamp / 1000, 'unixepoch')
+        ELSE strftime('%Y-%m-%dT%H:%M:%f', timestamp / 1000.0, 'unixepoch')
+    END || '+00:00'
+WHERE typeof(timestamp) = 'integer';
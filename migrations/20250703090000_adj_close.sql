@@ -0,0 +1,2 @@
+-- Close adjusted for splits and dividends. NULL until an adjustment has been computed.
+ALTER TABLE OHLCV ADD COLUMN adj_close REAL;
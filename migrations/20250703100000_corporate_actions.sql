@@ -0,0 +1,12 @@
+-- Splits and dividends recorded per ticker, used to compute adjusted prices on export
+CREATE TABLE IF NOT EXISTS CORPORATE_ACTIONS (
+    symbol VARCHAR(10) NOT NULL,
+    exchange VARCHAR(10) NOT NULL,
+    date DATE NOT NULL, -- Ex-date
+    kind VARCHAR(10) NOT NULL, -- 'split' or 'dividend'
+    ratio REAL NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (symbol, exchange, date, kind),
+    FOREIGN KEY (symbol, exchange) REFERENCES TICKERS(symbol, exchange) ON DELETE CASCADE
+);
@@ -0,0 +1,23 @@
+-- Precomputed ML feature rows, one per candle, so training sets can be reloaded by date range
+CREATE TABLE IF NOT EXISTS ML_FEATURES (
+    symbol VARCHAR(10) NOT NULL,
+    exchange VARCHAR(10) NOT NULL,
+    interval VARCHAR(10) NOT NULL,
+    timestamp DATETIME NOT NULL,
+    open REAL NOT NULL,
+    high REAL NOT NULL,
+    low REAL NOT NULL,
+    close REAL NOT NULL,
+    volume REAL NOT NULL,
+    -- Indicator columns are NULL until their window is full
+    rsi REAL,
+    mfi REAL,
+    sma_20 REAL,
+    ema_12 REAL,
+    price_change_pct REAL,
+    volatility_pct REAL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (symbol, exchange, interval, timestamp),
+    FOREIGN KEY (symbol, exchange) REFERENCES TICKERS(symbol, exchange) ON DELETE CASCADE
+) WITHOUT ROWID;
@@ -0,0 +1,36 @@
+-- Postgres port of the TICKERS and OHLCV tables from ../migrations
+CREATE TABLE IF NOT EXISTS TICKERS (
+    symbol VARCHAR(32) NOT NULL,
+    exchange VARCHAR(32) NOT NULL,
+    description TEXT DEFAULT 'No description available',
+    currency VARCHAR(3) DEFAULT 'N/A',
+    country VARCHAR(50) DEFAULT 'Unknown',
+    market_type VARCHAR(20) DEFAULT 'all',
+    industry VARCHAR(50) DEFAULT 'Unknown',
+    sector VARCHAR(50) DEFAULT 'Unknown',
+    founded BIGINT DEFAULT 0,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (symbol, exchange)
+);
+
+CREATE TABLE IF NOT EXISTS OHLCV (
+    symbol VARCHAR(32) NOT NULL,
+    exchange VARCHAR(32) NOT NULL,
+    interval VARCHAR(10) NOT NULL,
+    timestamp TIMESTAMPTZ NOT NULL,
+    open DOUBLE PRECISION NOT NULL,
+    high DOUBLE PRECISION NOT NULL,
+    low DOUBLE PRECISION NOT NULL,
+    close DOUBLE PRECISION NOT NULL,
+    volume DOUBLE PRECISION NOT NULL,
+    adj_close DOUBLE PRECISION,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (symbol, exchange, interval, timestamp),
+    FOREIGN KEY (symbol, exchange) REFERENCES TICKERS(symbol, exchange) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_ohlcv_symbol_interval_timestamp ON OHLCV(symbol, interval, timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_ohlcv_exchange_timestamp ON OHLCV(exchange, timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_ohlcv_timestamp_desc ON OHLCV(timestamp DESC);
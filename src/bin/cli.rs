@@ -1,13 +1,39 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand, ValueEnum};
-use tradingview::{Interval, MarketSymbol, UserCookies, get_quote_token};
+use clap::{Arg, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+use parquet::basic::Compression;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tradingview::{Interval, MarketSymbol, UserCookies};
 use vnquant_dataset::finance::{
+    calendar::calendar_for_exchange,
+    checkpoint::FetchCheckpoint,
     cmd::{
-        fetch_intraday_prices, fetch_intraday_prices_all, fetch_prices, fetch_prices_all,
-        fetch_tickers,
+        DEFAULT_CHUNK_BASE_BACKOFF, DEFAULT_CHUNK_MAX_BACKOFF, DEFAULT_INTER_CHUNK_DELAY,
+        build_features, configure_auth, configure_proxy, day_range, fetch_intraday_prices,
+        fetch_intraday_prices_all, fetch_prices, fetch_prices_all, fetch_prices_between,
+        fetch_prices_incremental,
     },
+    corporate::{CorporateAction, CorporateActionKind, adjust_for_splits},
     db::Database,
-    models::Ticker,
+    doctor::{CheckStatus, run_checks},
+    fetch_tickers,
+    models::{Ticker, interval_duration},
+    ta::compute_indicator,
+    token::{QuoteAuth, TOKEN_FILE, TokenStore, load_user_cookies},
+};
+use vnquant_dataset::utils::{
+    config::CliConfig,
+    dataset::{TemplateVars, export_partitioned_prices, render_file_template},
+    format::{
+        default_parquet_compression, save_batch_json, save_candles_csv, save_candles_json,
+        save_candles_parquet, save_csv, save_ml_features_parquet, save_parquet, to_batch,
+    },
+    output::{OutputFormat, OutputWriter, Tabular},
 };
 
 #[derive(Parser)]
@@ -15,6 +41,18 @@ use vnquant_dataset::finance::{
 #[command(about = "A CLI tool for managing financial data")]
 #[command(version = "1.0")]
 struct Cli {
+    /// Output format for read commands
+    #[arg(long, global = true, value_enum, default_value = "table")]
+    output_format: OutputFormat,
+
+    /// Proxy URL for TradingView requests (falls back to a direct connection when unset)
+    #[arg(long, global = true, env = "TV_PROXY")]
+    proxy: Option<String>,
+
+    /// Hide progress bars; they are only drawn when stderr is a terminal anyway
+    #[arg(long, global = true)]
+    no_progress: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -50,6 +88,91 @@ impl From<IntervalArg> for Interval {
     }
 }
 
+#[derive(Clone, ValueEnum, Debug, Copy)]
+enum ExportWhat {
+    Tickers,
+    Prices,
+}
+
+#[derive(Clone, ValueEnum, Debug, Copy)]
+enum ExportFormat {
+    Parquet,
+    Csv,
+    Json,
+}
+
+#[derive(Clone, ValueEnum, Debug, Copy)]
+enum CompressionArg {
+    None,
+    Snappy,
+    Zstd,
+}
+
+impl From<CompressionArg> for Compression {
+    fn from(compression: CompressionArg) -> Self {
+        match compression {
+            CompressionArg::None => Compression::UNCOMPRESSED,
+            CompressionArg::Snappy => Compression::SNAPPY,
+            CompressionArg::Zstd => default_parquet_compression(),
+        }
+    }
+}
+
+/// TradingView login shared by the fetch commands, for data that requires an account.
+#[derive(clap::Args, Debug, Clone)]
+struct AuthArgs {
+    /// Cookies JSON saved by `login`
+    #[arg(long, env = "TV_COOKIES", hide_env_values = true)]
+    cookies: Option<String>,
+
+    /// File with the cookies saved by `login` (takes precedence over --cookies)
+    #[arg(long)]
+    cookies_path: Option<String>,
+
+    /// File the quote token is cached in
+    #[arg(long, default_value = TOKEN_FILE)]
+    token_path: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct GapRow {
+    symbol: String,
+    exchange: String,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+}
+
+impl Tabular for GapRow {
+    fn headers() -> Vec<&'static str> {
+        vec!["symbol", "exchange", "start", "end"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.symbol.clone(),
+            self.exchange.clone(),
+            self.start.to_rfc3339(),
+            self.end.to_rfc3339(),
+        ]
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+struct ExchangeRow {
+    exchange: String,
+    tickers: i64,
+}
+
+impl Tabular for ExchangeRow {
+    fn headers() -> Vec<&'static str> {
+        vec!["exchange", "tickers"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.exchange.clone(), self.tickers.to_string()]
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Fetch intraday prices for tickers from a file
@@ -63,12 +186,21 @@ enum Commands {
         input_file: String,
 
         /// Time interval for price data
-        #[arg(short, long, value_enum, default_value = "one-day")]
+        #[arg(
+            short,
+            long,
+            value_enum,
+            default_value = "one-day",
+            env = "VNQUANT_INTERVAL"
+        )]
         interval: IntervalArg,
 
         /// Enable verbose logging
         #[arg(short, long)]
         verbose: bool,
+
+        #[command(flatten)]
+        auth: AuthArgs,
     },
     /// Fetch intraday prices for a batch of tickers from a file
     FetchIntradayPrices {
@@ -81,13 +213,23 @@ enum Commands {
         input_file: String,
 
         /// Time interval for price data
-        #[arg(short, long, value_enum, default_value = "one-hour")]
+        #[arg(
+            short,
+            long,
+            value_enum,
+            default_value = "one-hour",
+            env = "VNQUANT_INTRADAY_INTERVAL"
+        )]
         interval: IntervalArg,
 
         /// Number of concurrent requests
-        #[arg(short, long, default_value = "5")]
+        #[arg(short, long, default_value = "5", env = "VNQUANT_CONCURRENCY")]
         concurrency: usize,
 
+        /// Maximum requests per second across all concurrent tasks
+        #[arg(long, env = "VNQUANT_RATE_LIMIT")]
+        rate_limit: Option<u32>,
+
         #[arg(short, long, default_value = "false")]
         replay: bool,
 
@@ -97,14 +239,21 @@ enum Commands {
         /// Enable verbose logging
         #[arg(short, long)]
         verbose: bool,
+
+        #[command(flatten)]
+        auth: AuthArgs,
     },
-    /// Get TradingView token from cookies
+    /// Get TradingView token from cookies, reusing the cached one while it is valid
     GetToken {
         #[arg(env = "TV_COOKIES")]
         cookies: Option<String>,
 
         #[arg(short, long)]
         cookies_path: Option<String>,
+
+        /// File the token is cached in
+        #[arg(long, default_value = TOKEN_FILE)]
+        token_path: String,
     },
     /// Login to TradingView and save cookies
     Login {
@@ -134,9 +283,17 @@ enum Commands {
         #[arg(long, env = "DATABASE_URL")]
         database_url: String,
 
-        /// Path to file containing TradingView exchanges configuration
-        #[arg(short, long, default_value = "exchanges.json")]
-        path: String,
+        /// Path to a TradingView exchanges configuration, overriding the embedded default
+        #[arg(short, long, alias = "exchanges-config")]
+        path: Option<String>,
+
+        /// Keep only the first N symbols of each exchange (useful for smoke tests)
+        #[arg(long)]
+        limit_per_exchange: Option<usize>,
+
+        /// Log what would be fetched without touching the network or database
+        #[arg(long)]
+        dry_run: bool,
 
         /// Enable verbose logging
         #[arg(short, long)]
@@ -149,12 +306,49 @@ enum Commands {
         database_url: String,
 
         /// Time interval for price data
-        #[arg(short, long, value_enum, default_value = "one-day")]
+        #[arg(
+            short,
+            long,
+            value_enum,
+            default_value = "one-day",
+            env = "VNQUANT_INTERVAL"
+        )]
         interval: IntervalArg,
 
+        /// Only store bars from the last stored candle on, which is refreshed
+        #[arg(long)]
+        incremental: bool,
+
+        /// Delay in milliseconds before the first retry of a failed chunk, doubled per retry
+        #[arg(long, default_value_t = DEFAULT_CHUNK_BASE_BACKOFF.as_millis() as u64)]
+        base_backoff_ms: u64,
+
+        /// Upper bound in milliseconds on the delay between retries of a chunk
+        #[arg(long, default_value_t = DEFAULT_CHUNK_MAX_BACKOFF.as_millis() as u64)]
+        max_backoff_ms: u64,
+
+        /// Pause in milliseconds between consecutive chunks
+        #[arg(long, default_value_t = DEFAULT_INTER_CHUNK_DELAY.as_millis() as u64)]
+        inter_chunk_delay_ms: u64,
+
+        /// Log what would be fetched without touching the network or database
+        #[arg(long)]
+        dry_run: bool,
+
+        /// JSON file recording finished tickers, which are skipped when the run is resumed
+        #[arg(long)]
+        checkpoint: Option<String>,
+
+        /// Ignore an existing checkpoint and fetch every ticker again
+        #[arg(long, requires = "checkpoint")]
+        restart: bool,
+
         /// Enable verbose logging
         #[arg(short, long)]
         verbose: bool,
+
+        #[command(flatten)]
+        auth: AuthArgs,
     },
     /// Fetch intraday prices for all tickers in the database
     FetchIntradayPricesAll {
@@ -163,16 +357,41 @@ enum Commands {
         database_url: String,
 
         /// Time interval for price data
-        #[arg(short, long, value_enum, default_value = "one-hour")]
+        #[arg(
+            short,
+            long,
+            value_enum,
+            default_value = "one-hour",
+            env = "VNQUANT_INTRADAY_INTERVAL"
+        )]
         interval: IntervalArg,
 
         /// Number of concurrent requests
-        #[arg(short, long, default_value = "5")]
+        #[arg(short, long, default_value = "5", env = "VNQUANT_CONCURRENCY")]
         concurrency: usize,
 
+        /// Maximum requests per second across all concurrent tasks
+        #[arg(long, env = "VNQUANT_RATE_LIMIT")]
+        rate_limit: Option<u32>,
+
+        /// Log what would be fetched without touching the network or database
+        #[arg(long)]
+        dry_run: bool,
+
+        /// JSON file recording finished tickers, which are skipped when the run is resumed
+        #[arg(long)]
+        checkpoint: Option<String>,
+
+        /// Ignore an existing checkpoint and fetch every ticker again
+        #[arg(long, requires = "checkpoint")]
+        restart: bool,
+
         /// Enable verbose logging
         #[arg(short, long)]
         verbose: bool,
+
+        #[command(flatten)]
+        auth: AuthArgs,
     },
     /// Fetch prices for a specific ticker
     FetchPrices {
@@ -189,16 +408,42 @@ enum Commands {
         exchange: String,
 
         /// Time interval for price data
-        #[arg(short, long, value_enum, default_value = "one-day")]
+        #[arg(
+            short,
+            long,
+            value_enum,
+            default_value = "one-day",
+            env = "VNQUANT_INTERVAL"
+        )]
         interval: IntervalArg,
 
         /// Enable replay mode
         #[arg(short, long)]
         replay: bool,
 
+        /// Only store bars from the last stored candle on, which is refreshed
+        #[arg(long, conflicts_with_all = ["since", "until"])]
+        incremental: bool,
+
+        /// Only store bars on or after this date (YYYY-MM-DD, UTC)
+        #[arg(long)]
+        since: Option<chrono::NaiveDate>,
+
+        /// Only store bars on or before this date (YYYY-MM-DD, UTC)
+        #[arg(long)]
+        until: Option<chrono::NaiveDate>,
+
+        /// Overwrite the stored description, industry, sector and founding year with the
+        /// chart's symbol info (not with --incremental)
+        #[arg(long, conflicts_with = "incremental")]
+        refresh_metadata: bool,
+
         /// Enable verbose logging
         #[arg(short, long)]
         verbose: bool,
+
+        #[command(flatten)]
+        auth: AuthArgs,
     },
     /// List all tickers in the database
     ListTickers {
@@ -214,6 +459,12 @@ enum Commands {
         #[arg(short, long)]
         limit: Option<usize>,
     },
+    /// List exchanges with their ticker counts
+    ListExchanges {
+        /// Database URL (can also be set via DATABASE_URL environment variable)
+        #[arg(long, env = "DATABASE_URL")]
+        database_url: String,
+    },
     /// Get information about a specific ticker
     GetTicker {
         /// Database URL (can also be set via DATABASE_URL environment variable)
@@ -228,19 +479,320 @@ enum Commands {
         #[arg(short, long)]
         exchange: String,
     },
+    /// Full-text search over tickers, best match first
+    Search {
+        /// Database URL (can also be set via DATABASE_URL environment variable)
+        #[arg(long, env = "DATABASE_URL")]
+        database_url: String,
+
+        /// Free text to search for, e.g. "bank"
+        query: String,
+
+        /// Only return tickers listed on this exchange
+        #[arg(short, long)]
+        exchange: Option<String>,
+
+        /// Only search this field, e.g. description (repeat to search several)
+        #[arg(short, long)]
+        field: Vec<String>,
+
+        /// Let the last word match the start of a term, so "VC" finds VCB
+        #[arg(long, conflicts_with_all = ["exchange", "field"])]
+        prefix: bool,
+
+        /// Maximum number of results
+        #[arg(short, long, default_value_t = 20)]
+        limit: i64,
+    },
+    /// Import tickers from a Parquet or CSV file produced by `export`
+    ImportTickers {
+        /// Database URL (can also be set via DATABASE_URL environment variable)
+        #[arg(long, env = "DATABASE_URL")]
+        database_url: String,
+
+        /// File to import; `.csv` files are read as CSV, anything else as Parquet
+        #[arg(short, long)]
+        file: String,
+    },
+    /// Check the database, cookies and TradingView connectivity
+    Doctor {
+        /// Database URL (can also be set via DATABASE_URL environment variable)
+        #[arg(long, env = "DATABASE_URL")]
+        database_url: Option<String>,
+
+        /// Path to a cookies file saved by `login`
+        #[arg(short, long)]
+        cookies_path: Option<String>,
+    },
+    /// Record a split or dividend so exports can adjust prices for it
+    AddCorporateAction {
+        /// Database URL (can also be set via DATABASE_URL environment variable)
+        #[arg(long, env = "DATABASE_URL")]
+        database_url: String,
+
+        /// Ticker symbol
+        #[arg(short, long)]
+        symbol: String,
+
+        /// Exchange name
+        #[arg(short, long)]
+        exchange: String,
+
+        /// Ex-date (YYYY-MM-DD)
+        #[arg(short, long)]
+        date: chrono::NaiveDate,
+
+        /// Kind of action
+        #[arg(short, long, value_enum)]
+        kind: CorporateActionKind,
+
+        /// New shares per old share for splits, cash per share for dividends
+        #[arg(short, long)]
+        ratio: f64,
+    },
+    /// Summarize the stored prices of a ticker
+    Stats {
+        /// Database URL (can also be set via DATABASE_URL environment variable)
+        #[arg(long, env = "DATABASE_URL")]
+        database_url: String,
+
+        /// Ticker symbol
+        #[arg(short, long)]
+        symbol: String,
+
+        /// Exchange name
+        #[arg(short, long)]
+        exchange: String,
+
+        /// Time interval for price data
+        #[arg(
+            short,
+            long,
+            value_enum,
+            default_value = "one-day",
+            env = "VNQUANT_INTERVAL"
+        )]
+        interval: IntervalArg,
+    },
+    /// Compute technical indicators from stored prices and save them
+    ComputeIndicators {
+        /// Database URL (can also be set via DATABASE_URL environment variable)
+        #[arg(long, env = "DATABASE_URL")]
+        database_url: String,
+
+        /// Ticker symbol
+        #[arg(short, long)]
+        symbol: String,
+
+        /// Exchange name
+        #[arg(short, long)]
+        exchange: String,
+
+        /// Time interval for price data
+        #[arg(
+            short,
+            long,
+            value_enum,
+            default_value = "one-day",
+            env = "VNQUANT_INTERVAL"
+        )]
+        interval: IntervalArg,
+
+        /// Comma-separated indicator names, e.g. rsi_14,sma_20,macd_12_26_9
+        #[arg(long, value_delimiter = ',', required = true)]
+        indicators: Vec<String>,
+    },
+    /// Build ML features from stored prices and save them to Parquet or the database
+    BuildFeatures {
+        /// Database URL (can also be set via DATABASE_URL environment variable)
+        #[arg(long, env = "DATABASE_URL")]
+        database_url: String,
+
+        /// Ticker symbol
+        #[arg(short, long)]
+        symbol: String,
+
+        /// Exchange name
+        #[arg(short, long)]
+        exchange: String,
+
+        /// Time interval for price data
+        #[arg(
+            short,
+            long,
+            value_enum,
+            default_value = "one-day",
+            env = "VNQUANT_INTERVAL"
+        )]
+        interval: IntervalArg,
+
+        /// Output Parquet file path; the features are stored in the database when omitted
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Delete stored prices of a ticker within a date range
+    DeletePrices {
+        /// Database URL (can also be set via DATABASE_URL environment variable)
+        #[arg(long, env = "DATABASE_URL")]
+        database_url: String,
+
+        /// Ticker symbol
+        #[arg(short, long)]
+        symbol: String,
+
+        /// Exchange name
+        #[arg(short, long)]
+        exchange: String,
+
+        /// Time interval for price data
+        #[arg(
+            short,
+            long,
+            value_enum,
+            default_value = "one-day",
+            env = "VNQUANT_INTERVAL"
+        )]
+        interval: IntervalArg,
+
+        /// First timestamp to delete, inclusive (RFC 3339)
+        #[arg(long)]
+        start: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Last timestamp to delete, inclusive (RFC 3339)
+        #[arg(long)]
+        end: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Allow deleting the whole series when neither --start nor --end is given
+        #[arg(long)]
+        force: bool,
+    },
+    /// Check that the database answers queries and has every required table
+    Health {
+        /// Database URL (can also be set via DATABASE_URL environment variable)
+        #[arg(long, env = "DATABASE_URL")]
+        database_url: String,
+    },
+    /// Vacuum and analyze the database and rebuild the search index
+    Maintenance {
+        /// Database URL (can also be set via DATABASE_URL environment variable)
+        #[arg(long, env = "DATABASE_URL")]
+        database_url: String,
+    },
+    /// Report gaps in stored price data
+    CheckGaps {
+        /// Database URL (can also be set via DATABASE_URL environment variable)
+        #[arg(long, env = "DATABASE_URL")]
+        database_url: String,
+
+        /// Ticker symbol (checks every ticker when omitted)
+        #[arg(short, long)]
+        symbol: Option<String>,
+
+        /// Filter by exchange
+        #[arg(short, long)]
+        exchange: Option<String>,
+
+        /// Time interval for price data
+        #[arg(
+            short,
+            long,
+            value_enum,
+            default_value = "one-day",
+            env = "VNQUANT_INTERVAL"
+        )]
+        interval: IntervalArg,
+    },
+    /// Export tickers or prices from the database to Parquet/CSV
+    Export {
+        /// Database URL (can also be set via DATABASE_URL environment variable)
+        #[arg(long, env = "DATABASE_URL")]
+        database_url: String,
+
+        /// Path of the output file, or of the output directory with --partitioned
+        #[arg(
+            short,
+            long,
+            required_unless_present = "template",
+            conflicts_with = "template"
+        )]
+        output: Option<String>,
+
+        /// Name the output file from a template, e.g. {exchange}_{symbol}_{interval}.parquet;
+        /// placeholders are {symbol}, {exchange}, {interval} and {date}
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Directory the output path is relative to; missing directories are created
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+
+        /// Output file format
+        #[arg(short, long, value_enum, default_value = "parquet")]
+        format: ExportFormat,
+
+        /// Column compression for Parquet output
+        #[arg(long, value_enum, default_value = "zstd")]
+        compression: CompressionArg,
+
+        /// What to export
+        #[arg(short, long, value_enum, default_value = "tickers")]
+        what: ExportWhat,
+
+        /// Filter by exchange (required for prices)
+        #[arg(short, long)]
+        exchange: Option<String>,
+
+        /// Filter by ticker symbol (required for prices)
+        #[arg(short, long)]
+        symbol: Option<String>,
+
+        /// Time interval for price data (required for prices)
+        #[arg(short, long, value_enum, env = "VNQUANT_INTERVAL")]
+        interval: Option<IntervalArg>,
+
+        /// Fill `adj_close` from the recorded corporate actions (prices only)
+        #[arg(long)]
+        adjust: bool,
+
+        /// Render price timestamps in this time zone, e.g. Asia/Ho_Chi_Minh (CSV and JSON only)
+        #[arg(long)]
+        tz: Option<chrono_tz::Tz>,
+
+        /// Export prices as a Parquet dataset in the output directory, partitioned by
+        /// interval, year, exchange and symbol; --exchange and --symbol only filter tickers
+        #[arg(long, conflicts_with_all = ["adjust", "tz"])]
+        partitioned: bool,
+    },
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+fn main() -> Result<()> {
     // Load environment variables from .env file if it exists
     dotenvy::dotenv().ok();
 
-    let cli = Cli::parse();
+    let config = CliConfig::discover(config_path_arg(std::env::args_os()).as_deref())?;
+    validate_config(&config)?;
+    let env = std::env::vars().collect::<HashMap<_, _>>();
+    let matches = with_config_defaults(Cli::command(), &config, &env).get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    if let Some(proxy) = &cli.proxy {
+        // SAFETY: the runtime is built below, so no other thread exists yet
+        unsafe { configure_proxy(proxy)? };
+    }
 
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(cli))
+}
+
+async fn run(cli: Cli) -> Result<()> {
     match cli.command {
         Commands::FetchTickers {
             database_url,
             path,
+            limit_per_exchange,
+            dry_run,
             verbose,
         } => {
             // Initialize logging
@@ -253,17 +805,28 @@ async fn main() -> Result<()> {
             tracing_subscriber::fmt().with_max_level(log_level).init();
 
             println!("🔄 Connecting to database...");
-            let db = Database::new(&database_url).await?;
+            let db = open_database(&database_url, dry_run).await?;
 
             println!("📈 Fetching tickers from exchanges...");
-            fetch_tickers(db, &path).await?;
+            fetch_tickers(db, path.as_deref(), limit_per_exchange, dry_run).await?;
+            if dry_run {
+                return Ok(());
+            }
 
             println!("✅ Successfully fetched and stored tickers!");
         }
         Commands::FetchPricesAll {
             database_url,
             interval,
+            incremental,
+            base_backoff_ms,
+            max_backoff_ms,
+            inter_chunk_delay_ms,
+            dry_run,
+            checkpoint,
+            restart,
             verbose,
+            auth,
         } => {
             // Initialize logging
             let log_level = if verbose {
@@ -273,14 +836,54 @@ async fn main() -> Result<()> {
             };
 
             tracing_subscriber::fmt().with_max_level(log_level).init();
+            authenticate(&auth).await?;
 
             println!("🔄 Connecting to database...");
-            let db = Database::new(&database_url).await?;
+            let db = open_database(&database_url, dry_run).await?;
 
             println!("📊 Fetching prices for all tickers with interval {interval:?}...");
             let start = std::time::Instant::now();
 
-            fetch_prices_all(db, interval.into(), 100, 2).await?;
+            // Ctrl-C stops after the chunk in flight so its prices are kept
+            let cancel = CancellationToken::new();
+            tokio::spawn({
+                let cancel = cancel.clone();
+                async move {
+                    if tokio::signal::ctrl_c().await.is_ok() {
+                        eprintln!(
+                            "⏹️  Stopping after the current chunk (Ctrl-C again to abort)..."
+                        );
+                        cancel.cancel();
+                    }
+                    if tokio::signal::ctrl_c().await.is_ok() {
+                        std::process::exit(130);
+                    }
+                }
+            });
+
+            let mut checkpoint = load_checkpoint(checkpoint.as_deref(), interval.into(), restart)?;
+            let progress = progress_bar(!cli.no_progress && !dry_run);
+            let completed = fetch_prices_all()
+                .db(db)
+                .interval(interval.into())
+                .incremental(incremental)
+                .base_backoff(Duration::from_millis(base_backoff_ms))
+                .max_backoff(Duration::from_millis(max_backoff_ms))
+                .inter_chunk_delay(Duration::from_millis(inter_chunk_delay_ms))
+                .dry_run(dry_run)
+                .progress(&progress)
+                .cancel(&cancel)
+                .maybe_checkpoint(checkpoint.as_mut())
+                .call()
+                .await?;
+            progress.finish_and_clear();
+            if dry_run {
+                return Ok(());
+            }
+            if cancel.is_cancelled() {
+                println!("⏹️  Interrupted after {completed} completed chunks");
+                return Ok(());
+            }
 
             let duration = start.elapsed();
             println!(
@@ -295,7 +898,12 @@ async fn main() -> Result<()> {
             exchange,
             interval,
             replay,
+            incremental,
+            since,
+            until,
+            refresh_metadata,
             verbose,
+            auth,
         } => {
             // Initialize logging
             let log_level = if verbose {
@@ -305,6 +913,7 @@ async fn main() -> Result<()> {
             };
 
             tracing_subscriber::fmt().with_max_level(log_level).init();
+            authenticate(&auth).await?;
 
             println!("🔄 Connecting to database...");
             let db = Database::new(&database_url).await?;
@@ -314,7 +923,23 @@ async fn main() -> Result<()> {
             println!("📊 Fetching prices for {symbol}:{exchange} with interval {interval:?}...");
             let start = std::time::Instant::now();
 
-            fetch_prices(db, &ticker, interval.into(), replay).await?;
+            if incremental {
+                fetch_prices_incremental(db, &ticker, interval.into(), replay).await?;
+            } else if since.is_some() || until.is_some() {
+                let (start, end) = day_range(since, until)?;
+                fetch_prices_between(
+                    db,
+                    &ticker,
+                    interval.into(),
+                    replay,
+                    refresh_metadata,
+                    start,
+                    end,
+                )
+                .await?;
+            } else {
+                fetch_prices(db, &ticker, interval.into(), replay, refresh_metadata).await?;
+            }
 
             let duration = start.elapsed();
             println!(
@@ -344,26 +969,31 @@ async fn main() -> Result<()> {
                 tickers
             };
 
-            if display_tickers.is_empty() {
-                println!("No tickers found.");
-            } else {
-                println!("Found {} tickers:", display_tickers.len());
-                println!(
-                    "{:<15} {:<15} {:<30} {:<10}",
-                    "Symbol", "Exchange", "Description", "Currency"
-                );
-                println!("{}", "-".repeat(70));
-
-                for ticker in display_tickers {
-                    println!(
-                        "{:<15} {:<15} {:<30} {:<10}",
-                        ticker.symbol,
-                        ticker.exchange,
-                        ticker.description.as_deref().unwrap_or("N/A"),
-                        ticker.currency.as_deref().unwrap_or("N/A")
-                    );
+            let mut out = OutputWriter::stdout(cli.output_format);
+            if out.format() == OutputFormat::Table {
+                if display_tickers.is_empty() {
+                    println!("No tickers found.");
+                    return Ok(());
                 }
+                println!("Found {} tickers:", display_tickers.len());
+            }
+            out.write_list(&display_tickers)?;
+        }
+        Commands::ListExchanges { database_url } => {
+            let db = Database::new(&database_url).await?;
+
+            let rows = db
+                .get_exchanges()
+                .await?
+                .into_iter()
+                .map(|(exchange, tickers)| ExchangeRow { exchange, tickers })
+                .collect::<Vec<_>>();
+
+            let mut out = OutputWriter::stdout(cli.output_format);
+            if out.format() == OutputFormat::Table {
+                println!("Found {} exchanges:", rows.len());
             }
+            out.write_list(&rows)?;
         }
 
         Commands::GetTicker {
@@ -375,36 +1005,48 @@ async fn main() -> Result<()> {
 
             match db.get_ticker(&symbol, &exchange).await? {
                 Some(ticker) => {
-                    println!("Ticker Information:");
-                    println!("Symbol: {}", ticker.symbol);
-                    println!("Exchange: {}", ticker.exchange);
-                    println!(
-                        "Description: {}",
-                        ticker.description.as_deref().unwrap_or("N/A")
-                    );
-                    println!("Currency: {}", ticker.currency.as_deref().unwrap_or("N/A"));
-                    println!("Country: {}", ticker.country.as_deref().unwrap_or("N/A"));
-                    println!(
-                        "Market Type: {}",
-                        ticker.market_type.as_deref().unwrap_or("N/A")
-                    );
-                    println!("Industry: {}", ticker.industry.as_deref().unwrap_or("N/A"));
-                    println!("Sector: {}", ticker.sector.as_deref().unwrap_or("N/A"));
-                    if let Some(founded) = ticker.founded {
-                        println!("Founded: {founded}");
-                    }
+                    OutputWriter::stdout(cli.output_format).write_one(&ticker)?;
                 }
                 None => {
-                    println!("Ticker '{symbol}' not found on exchange '{exchange}'");
+                    eprintln!("Ticker '{symbol}' not found on exchange '{exchange}'");
+                }
+            }
+        }
+
+        Commands::Search {
+            database_url,
+            query,
+            exchange,
+            field,
+            prefix,
+            limit,
+        } => {
+            let db = Database::new(&database_url).await?;
+
+            let tickers =
+                search_tickers(&db, &query, exchange.as_deref(), &field, prefix, limit).await?;
+
+            let mut out = OutputWriter::stdout(cli.output_format);
+            if out.format() == OutputFormat::Table {
+                if tickers.is_empty() {
+                    println!("No tickers found.");
+                    return Ok(());
                 }
+                println!("Found {} tickers:", tickers.len());
             }
+            out.write_list(&tickers)?;
         }
 
         Commands::FetchIntradayPricesAll {
             database_url,
             interval,
             concurrency,
+            rate_limit,
+            dry_run,
+            checkpoint,
+            restart,
             verbose,
+            auth,
         } => {
             // Initialize logging
             let log_level = if verbose {
@@ -414,21 +1056,39 @@ async fn main() -> Result<()> {
             };
 
             tracing_subscriber::fmt().with_max_level(log_level).init();
+            authenticate(&auth).await?;
 
             println!("🔄 Connecting to database...");
-            let db = Database::new(&database_url).await?;
+            let db = open_database(&database_url, dry_run).await?;
 
             println!(
                 "📊 Fetching intraday prices for all tickers with interval {interval:?} (concurrency: {concurrency})..."
             );
             let start = std::time::Instant::now();
 
-            fetch_intraday_prices_all(&db, interval.into(), concurrency).await?;
+            let mut checkpoint = load_checkpoint(checkpoint.as_deref(), interval.into(), restart)?;
+            let progress = progress_bar(!cli.no_progress && !dry_run);
+            let report = fetch_intraday_prices_all(
+                &db,
+                interval.into(),
+                concurrency,
+                rate_limit,
+                dry_run,
+                &progress,
+                checkpoint.as_mut(),
+            )
+            .await?;
+            progress.finish_and_clear();
+            if dry_run {
+                return Ok(());
+            }
 
             let duration = start.elapsed();
             println!(
-                "✅ Successfully fetched intraday prices for all tickers in {:.2}s!",
-                duration.as_secs_f64()
+                "✅ Fetched intraday prices for {} tickers in {:.2}s ({} failed)",
+                report.successful.len(),
+                duration.as_secs_f64(),
+                report.failed.len()
             );
         }
         Commands::Login {
@@ -467,34 +1127,27 @@ async fn main() -> Result<()> {
         Commands::GetToken {
             cookies,
             cookies_path,
+            token_path,
         } => {
             // Load cookies from file or environment variable
-            let cookies = if let Some(path) = cookies_path {
-                std::fs::read_to_string(path)?
-            } else {
-                cookies.ok_or_else(|| {
+            let user = load_user_cookies(cookies_path.as_deref(), cookies.as_deref())?
+                .ok_or_else(|| {
                     anyhow::anyhow!("No cookies provided. Please set TV_COOKIES environment variable or use --cookies-path option.")
-                })?
-            };
-
-            // Parse cookies JSON
-            let user: UserCookies = serde_json::from_str(&cookies)?;
+                })?;
 
-            let token = get_quote_token(&user).await?;
+            let token = TokenStore::new(&token_path)
+                .get_or_refresh_token(&user)
+                .await?;
 
             // Print the auth token
             println!("{token}");
-
-            // set TV_AUTH_TOKEN environment variable
-            unsafe {
-                std::env::set_var("TV_AUTH_TOKEN", &token);
-            }
         }
         Commands::FetchPricesBatch {
             database_url,
             input_file,
             interval,
             verbose,
+            auth,
         } => {
             // Initialize logging
             let log_level = if verbose {
@@ -504,6 +1157,7 @@ async fn main() -> Result<()> {
             };
 
             tracing_subscriber::fmt().with_max_level(log_level).init();
+            authenticate(&auth).await?;
 
             println!("🔄 Connecting to database...");
             let db = Database::new(&database_url).await?;
@@ -516,7 +1170,7 @@ async fn main() -> Result<()> {
             let len = tickers.len();
 
             for ticker in tickers {
-                fetch_prices(db.clone(), &ticker, interval.into(), false).await?;
+                fetch_prices(db.clone(), &ticker, interval.into(), false, false).await?;
             }
 
             let duration = start.elapsed();
@@ -531,9 +1185,11 @@ async fn main() -> Result<()> {
             input_file,
             interval,
             concurrency,
+            rate_limit,
             replay,
             update_existing,
             verbose,
+            auth,
         } => {
             // Initialize logging
             let log_level = if verbose {
@@ -543,6 +1199,7 @@ async fn main() -> Result<()> {
             };
 
             tracing_subscriber::fmt().with_max_level(log_level).init();
+            authenticate(&auth).await?;
 
             println!("🔄 Connecting to database...");
             let db = Database::new(&database_url).await?;
@@ -554,24 +1211,825 @@ async fn main() -> Result<()> {
             let tickers: Vec<Ticker> = serde_json::from_str(&ticker_str)?;
             let len = tickers.len();
 
-            fetch_intraday_prices(
-                &db,
-                &tickers,
-                interval.into(),
-                concurrency,
-                replay,
-                update_existing,
-            )
-            .await?;
+            let progress = progress_bar(!cli.no_progress);
+            let report = fetch_intraday_prices()
+                .db(&db)
+                .tickers(&tickers)
+                .interval(interval.into())
+                .concurrency(concurrency)
+                .replay(replay)
+                .update_existing(update_existing)
+                .maybe_rate_limit(rate_limit)
+                .progress(&progress)
+                .call()
+                .await?;
+            progress.finish_and_clear();
 
             let duration = start.elapsed();
             println!(
-                "✅ Successfully fetched intraday prices for {} tickers in {:.2}s!",
+                "✅ Fetched intraday prices for {}/{} tickers in {:.2}s!",
+                report.successful.len(),
                 len,
                 duration.as_secs_f64()
             );
+            for (ticker, error) in &report.failed {
+                eprintln!("❌ {}:{} - {}", ticker.symbol, ticker.exchange, error);
+            }
+        }
+        Commands::ImportTickers { database_url, file } => {
+            let db = Database::new(&database_url).await?;
+
+            println!("📥 Importing tickers from {file}...");
+            let imported = if file.to_lowercase().ends_with(".csv") {
+                db.import_tickers_from_csv(&file).await?
+            } else {
+                db.import_tickers_from_parquet(&file).await?
+            };
+
+            println!("✅ Imported {imported} tickers");
+        }
+        Commands::Doctor {
+            database_url,
+            cookies_path,
+        } => {
+            let results = run_checks(database_url.as_deref(), cookies_path.as_deref()).await;
+
+            let mut out = OutputWriter::stdout(cli.output_format);
+            if out.format() == OutputFormat::Table {
+                for result in &results {
+                    let icon = match result.status {
+                        CheckStatus::Pass => "✅",
+                        CheckStatus::Fail => "❌",
+                        CheckStatus::Skip => "⏭️",
+                    };
+                    println!("{icon} {}: {}", result.name, result.detail);
+                }
+            } else {
+                out.write_list(&results)?;
+            }
+
+            let failed = results.iter().filter(|r| r.failed()).count();
+            if failed > 0 {
+                return Err(anyhow::anyhow!(
+                    "{} of {} checks failed",
+                    failed,
+                    results.len()
+                ));
+            }
+        }
+        Commands::AddCorporateAction {
+            database_url,
+            symbol,
+            exchange,
+            date,
+            kind,
+            ratio,
+        } => {
+            let db = Database::new(&database_url).await?;
+
+            let action = CorporateAction { date, ratio, kind };
+            db.upsert_corporate_action(&symbol, &exchange, &action)
+                .await?;
+            println!(
+                "✅ Recorded {} for {}:{} on {} (ratio {})",
+                kind.as_str(),
+                symbol,
+                exchange,
+                date,
+                ratio
+            );
+        }
+        Commands::Stats {
+            database_url,
+            symbol,
+            exchange,
+            interval,
+        } => {
+            let db = Database::new(&database_url).await?;
+
+            let ticker = Ticker::new(&symbol, &exchange);
+            let stats = db.get_price_stats(&ticker, interval.into()).await?;
+            OutputWriter::stdout(cli.output_format).write_one(&stats)?;
+        }
+        Commands::ComputeIndicators {
+            database_url,
+            symbol,
+            exchange,
+            interval,
+            indicators,
+        } => {
+            let db = Database::new(&database_url).await?;
+
+            let ticker = Ticker::new(&symbol, &exchange);
+            let candles = db
+                .get_prices()
+                .ticker(&ticker)
+                .interval(interval.into())
+                .call()
+                .await?;
+            if candles.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "No {symbol}:{exchange} prices stored for this interval"
+                ));
+            }
+
+            // Parse every name before writing anything
+            let calendar = calendar_for_exchange(&exchange);
+            let computed = indicators
+                .iter()
+                .map(|name| compute_indicator(name, &candles, calendar))
+                .collect::<Result<Vec<_>>>()?;
+            for (name, values) in indicators.iter().zip(computed) {
+                let written = db
+                    .upsert_indicators(&ticker, interval.into(), &values)
+                    .await?;
+                println!("✅ Saved {written} {name} values for {symbol}:{exchange}");
+            }
+        }
+        Commands::BuildFeatures {
+            database_url,
+            symbol,
+            exchange,
+            interval,
+            output,
+        } => {
+            let db = Database::new(&database_url).await?;
+
+            let ticker = Ticker::new(&symbol, &exchange);
+            let features = build_features(&db, &ticker, interval.into()).await?;
+            if features.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "No {symbol}:{exchange} prices stored for this interval"
+                ));
+            }
+
+            let rows = features.len();
+            match output {
+                Some(output) => {
+                    save_ml_features_parquet(features, &output)?;
+                    println!("✅ Wrote {rows} feature rows to {output}");
+                }
+                None => {
+                    db.upsert_ml_features(&ticker, interval.into(), &features)
+                        .await?;
+                    println!("✅ Stored {rows} feature rows for {symbol}:{exchange}");
+                }
+            }
+        }
+        Commands::DeletePrices {
+            database_url,
+            symbol,
+            exchange,
+            interval,
+            start,
+            end,
+            force,
+        } => {
+            if start.is_none() && end.is_none() && !force {
+                return Err(anyhow::anyhow!(
+                    "Refusing to delete every {symbol}:{exchange} candle without --start/--end; pass --force to confirm"
+                ));
+            }
+
+            let db = Database::new(&database_url).await?;
+
+            let ticker = Ticker::new(&symbol, &exchange);
+            let deleted = db
+                .delete_prices(&ticker, interval.into(), start, end)
+                .await?;
+            println!("🗑️ Deleted {deleted} candles for {symbol}:{exchange}");
+        }
+        Commands::Health { database_url } => {
+            // Don't migrate: the probe must not write and must report missing tables
+            let db = Database::builder()
+                .url(&database_url)
+                .migrate(false)
+                .connect()
+                .await?;
+
+            let status = db.health_check().await?;
+            OutputWriter::stdout(cli.output_format).write_one(&status)?;
+            if !status.is_healthy() {
+                return Err(anyhow::anyhow!(
+                    "Missing tables: {}",
+                    status.missing_tables.join(", ")
+                ));
+            }
+        }
+        Commands::Maintenance { database_url } => {
+            let db = Database::new(&database_url).await?;
+
+            println!("🧹 Vacuuming database...");
+            db.vacuum().await?;
+            println!("📊 Analyzing database...");
+            db.analyze().await?;
+            println!("🔍 Rebuilding search index...");
+            db.rebuild_search_index().await?;
+            println!("✅ Maintenance complete");
+        }
+        Commands::CheckGaps {
+            database_url,
+            symbol,
+            exchange,
+            interval,
+        } => {
+            let db = Database::new(&database_url).await?;
+
+            let tickers = match (symbol, exchange) {
+                (Some(symbol), Some(exchange)) => vec![Ticker::new(&symbol, &exchange)],
+                (symbol, Some(exchange)) => {
+                    let mut tickers = db.get_tickers_by_exchange(&exchange).await?;
+                    if let Some(symbol) = symbol {
+                        tickers.retain(|t| t.symbol == symbol);
+                    }
+                    tickers
+                }
+                (Some(symbol), None) => db
+                    .get_all_tickers()
+                    .await?
+                    .into_iter()
+                    .filter(|t| t.symbol == symbol)
+                    .collect(),
+                (None, None) => db.get_all_tickers().await?,
+            };
+
+            let mut rows = Vec::new();
+            for ticker in &tickers {
+                for (start, end) in db.find_price_gaps(ticker, interval.into()).await? {
+                    rows.push(GapRow {
+                        symbol: ticker.symbol.clone(),
+                        exchange: ticker.exchange.clone(),
+                        start,
+                        end,
+                    });
+                }
+            }
+
+            let mut out = OutputWriter::stdout(cli.output_format);
+            if out.format() == OutputFormat::Table {
+                println!(
+                    "Found {} gaps across {} tickers:",
+                    rows.len(),
+                    tickers.len()
+                );
+            }
+            out.write_list(&rows)?;
+        }
+        Commands::Export {
+            database_url,
+            output,
+            template,
+            output_dir,
+            format,
+            compression,
+            what,
+            exchange,
+            symbol,
+            interval,
+            adjust,
+            tz,
+            partitioned,
+        } => {
+            let db = Database::new(&database_url).await?;
+
+            let vars = TemplateVars {
+                symbol: symbol.as_deref(),
+                exchange: exchange.as_deref(),
+                interval: interval.map(Into::into),
+                date: Some(chrono::Utc::now().date_naive()),
+            };
+            let output = export_path(
+                output.as_deref(),
+                template.as_deref(),
+                output_dir.as_deref(),
+                &vars,
+            )?;
+
+            let rows = match what {
+                ExportWhat::Tickers => {
+                    let tickers =
+                        select_tickers(&db, exchange.as_deref(), symbol.as_deref()).await?;
+                    let len = tickers.len();
+                    match format {
+                        ExportFormat::Parquet => {
+                            save_parquet(tickers, &output, compression.into())?
+                        }
+                        ExportFormat::Csv => save_csv(tickers, &output)?,
+                        ExportFormat::Json => save_batch_json(&to_batch(tickers)?, &output)?,
+                    }
+                    len
+                }
+                ExportWhat::Prices if partitioned => {
+                    let Some(interval) = interval else {
+                        return Err(anyhow::anyhow!("A partitioned export requires --interval"));
+                    };
+                    if !matches!(format, ExportFormat::Parquet) {
+                        return Err(anyhow::anyhow!("A partitioned export is always Parquet"));
+                    }
+
+                    let tickers =
+                        select_tickers(&db, exchange.as_deref(), symbol.as_deref()).await?;
+                    let manifest =
+                        export_partitioned_prices(&db, &tickers, &[interval.into()], &output)
+                            .await?;
+                    manifest.files.iter().map(|f| f.rows).sum()
+                }
+                ExportWhat::Prices => {
+                    let (Some(symbol), Some(exchange), Some(interval)) =
+                        (&symbol, &exchange, interval)
+                    else {
+                        return Err(anyhow::anyhow!(
+                            "Exporting prices requires --symbol, --exchange and --interval"
+                        ));
+                    };
+
+                    let ticker = Ticker::new(symbol, exchange);
+                    let mut candles = db
+                        .get_prices()
+                        .ticker(&ticker)
+                        .interval(interval.into())
+                        .call()
+                        .await?;
+                    if adjust {
+                        let actions = db.get_corporate_actions(symbol, exchange).await?;
+                        adjust_for_splits(&mut candles, &actions);
+                    }
+
+                    match format {
+                        ExportFormat::Parquet => {
+                            save_candles_parquet(&candles, &output, compression.into())?
+                        }
+                        ExportFormat::Csv => save_candles_csv(&candles, &output, tz)?,
+                        ExportFormat::Json => save_candles_json(&candles, &output, tz)?,
+                    }
+                    candles.len()
+                }
+            };
+
+            println!("✅ Exported {rows} rows to {output}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Output path of an `export` command: `output`, or `template` rendered with `vars`, joined
+/// onto `output_dir` when given. Creates the parent directories of the returned path.
+/// Tickers of `exchange`, or of every exchange, optionally narrowed to `symbol`.
+async fn select_tickers(
+    db: &Database,
+    exchange: Option<&str>,
+    symbol: Option<&str>,
+) -> Result<Vec<Ticker>> {
+    let mut tickers = match exchange {
+        Some(exchange) => db.get_tickers_by_exchange(exchange).await?,
+        None => db.get_all_tickers().await?,
+    };
+    if let Some(symbol) = symbol {
+        tickers.retain(|t| t.symbol == symbol);
+    }
+    Ok(tickers)
+}
+
+fn export_path(
+    output: Option<&str>,
+    template: Option<&str>,
+    output_dir: Option<&Path>,
+    vars: &TemplateVars,
+) -> Result<String> {
+    let name = match (output, template) {
+        (_, Some(template)) => render_file_template(template, vars)?,
+        (Some(output), None) => output.to_string(),
+        (None, None) => return Err(anyhow::anyhow!("Either --output or --template is required")),
+    };
+    let path = match output_dir {
+        Some(dir) => dir.join(name),
+        None => PathBuf::from(name),
+    };
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    path.into_os_string()
+        .into_string()
+        .map_err(|path| anyhow::anyhow!("Output path is not valid UTF-8: {path:?}"))
+}
+
+/// Run a `search` command with the `search_tickers*` method that supports its filters.
+///
+/// Field searches go to `search_tickers_by_field`, exchange-only searches to
+/// `search_tickers_by_exchange` and the rest to `search_tickers`, the only one with prefix
+/// matching.
+async fn search_tickers(
+    db: &Database,
+    query: &str,
+    exchange: Option<&str>,
+    fields: &[String],
+    prefix: bool,
+    limit: i64,
+) -> Result<Vec<Ticker>> {
+    if !fields.is_empty() {
+        let fields = fields.iter().map(String::as_str).collect::<Vec<_>>();
+        db.search_tickers_by_field(&fields, query, exchange, Some(limit))
+            .await
+    } else if let Some(exchange) = exchange {
+        db.search_tickers_by_exchange(query, exchange, Some(limit))
+            .await
+    } else {
+        db.search_tickers(query, prefix, Some(limit), None).await
+    }
+}
+
+/// Connect to `database_url`. Dry runs skip the migrations, so they don't write to it.
+async fn open_database(database_url: &str, dry_run: bool) -> Result<Database> {
+    Database::builder()
+        .url(database_url)
+        .migrate(!dry_run)
+        .connect()
+        .await
+}
+
+/// Log the fetch requests in with the cookies in `auth`, if any were given.
+///
+/// The quote token comes from the `--token-path` cache while it is valid.
+async fn authenticate(auth: &AuthArgs) -> Result<()> {
+    let Some(user) = load_user_cookies(auth.cookies_path.as_deref(), auth.cookies.as_deref())?
+    else {
+        return Ok(());
+    };
+
+    let auth = QuoteAuth::new(TokenStore::new(&auth.token_path), user);
+    // Fail before fetching anything when the cookies can't get a token
+    auth.token().await?;
+    configure_auth(Some(auth));
+    Ok(())
+}
+
+/// Open the `--checkpoint` file of a bulk `interval` fetch, starting over when `restart` is
+/// set.
+fn load_checkpoint(
+    path: Option<&str>,
+    interval: Interval,
+    restart: bool,
+) -> Result<Option<FetchCheckpoint>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+
+    let checkpoint = if restart {
+        FetchCheckpoint::new(path, interval)
+    } else {
+        FetchCheckpoint::load(path, interval)
+            .map_err(|e| anyhow::anyhow!("{}; pass --restart to start over", e))?
+    };
+    if !checkpoint.is_empty() {
+        println!(
+            "⏭️  Resuming from {path}: skipping {} finished tickers",
+            checkpoint.len()
+        );
+    }
+    Ok(Some(checkpoint))
+}
+
+/// Progress bar for fetch commands, hidden when disabled or when stderr isn't a terminal.
+///
+/// Its length and position are set by the fetcher through `FetchProgress`.
+fn progress_bar(enabled: bool) -> ProgressBar {
+    if !enabled || !std::io::stderr().is_terminal() {
+        return ProgressBar::hidden();
+    }
+
+    let bar = ProgressBar::new(0);
+    bar.set_style(
+        ProgressStyle::with_template(
+            "{spinner} [{elapsed_precise}] {bar:40} {pos}/{len} tickers (eta {eta})",
+        )
+        .expect("progress template is valid"),
+    );
+    bar
+}
+
+/// Value of `--config` in `args`, read before clap parses them so the file can supply defaults.
+fn config_path_arg(args: impl IntoIterator<Item = OsString>) -> Option<PathBuf> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.to_str().and_then(|a| a.strip_prefix("--config=")) {
+            return Some(PathBuf::from(path));
         }
     }
+    None
+}
 
+/// Subcommands whose `interval` defaults come from `intraday_interval` and
+/// `VNQUANT_INTRADAY_INTERVAL` instead of `interval` and `VNQUANT_INTERVAL`.
+const INTRADAY_COMMANDS: [&str; 2] = ["fetch-intraday-prices", "fetch-intraday-prices-all"];
+
+/// Check the interval values in `config`, so a bad one is reported against the config file
+/// rather than as an invalid flag default.
+fn validate_config(config: &CliConfig) -> Result<()> {
+    for (key, value, intraday) in [
+        ("interval", &config.interval, false),
+        ("intraday_interval", &config.intraday_interval, true),
+    ] {
+        let Some(value) = value else { continue };
+        let interval = IntervalArg::from_str(value, false).map_err(|_| {
+            let expected = IntervalArg::value_variants()
+                .iter()
+                .filter_map(|v| v.to_possible_value())
+                .map(|v| v.get_name().to_string())
+                .collect::<Vec<_>>();
+            anyhow::anyhow!(
+                "Invalid {} '{}' in config, expected one of: {}",
+                key,
+                value,
+                expected.join(", ")
+            )
+        })?;
+        let is_intraday =
+            interval_duration(interval.into()).is_some_and(|d| d < chrono::Duration::days(1));
+        if intraday && !is_intraday {
+            anyhow::bail!(
+                "Invalid {} '{}' in config: not an intraday interval",
+                key,
+                value
+            );
+        }
+    }
     Ok(())
 }
+
+/// Add the global `--config` flag and resolve the defaults of the flags `config` covers.
+///
+/// An explicit flag wins, then the flag's environment variable looked up in `env`, then the
+/// value in `config`, then the built-in default. clap's own environment lookup is turned off
+/// for these flags so the whole precedence is decided from `env`.
+fn with_config_defaults(
+    cmd: clap::Command,
+    config: &CliConfig,
+    env: &HashMap<String, String>,
+) -> clap::Command {
+    let cmd = cmd.arg(
+        Arg::new("config")
+            .long("config")
+            .global(true)
+            .value_name("PATH")
+            .help("Config file with flag defaults (default: ./vnquant.toml if present)"),
+    );
+
+    let names = cmd
+        .get_subcommands()
+        .map(|sub| sub.get_name().to_string())
+        .collect::<Vec<_>>();
+    names.iter().fold(cmd, |cmd, name| {
+        let intraday = INTRADAY_COMMANDS.contains(&name.as_str());
+        cmd.mut_subcommand(name, |sub| {
+            sub.mut_args(|arg| {
+                let from_config = match arg.get_id().as_str() {
+                    "database_url" => config.database_url.clone(),
+                    "interval" if intraday => config.intraday_interval.clone(),
+                    "interval" => config.interval.clone(),
+                    "concurrency" => config.concurrency.map(|c| c.to_string()),
+                    "rate_limit" => config.rate_limit.map(|r| r.to_string()),
+                    _ => return arg,
+                };
+                let from_env = arg
+                    .get_env()
+                    .and_then(|var| env.get(var.to_str()?))
+                    .cloned();
+                // Don't print values from the environment, such as a database password, in --help
+                let hide = from_env.is_some();
+                match from_env.or(from_config) {
+                    Some(value) => arg
+                        .env(None)
+                        .default_value(value)
+                        .hide_default_value(hide)
+                        .required(false),
+                    None => arg.env(None),
+                }
+            })
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vnquant_dataset::finance::models::{interval_from_db_str, interval_to_db_str};
+
+    #[test]
+    fn test_interval_args_round_trip_through_db_codes() -> Result<()> {
+        for arg in IntervalArg::value_variants() {
+            let interval = Interval::from(*arg);
+            let code = interval_to_db_str(interval);
+            assert_eq!(interval_from_db_str(&code)?, interval, "{arg:?}");
+        }
+        Ok(())
+    }
+
+    fn parse_interval(
+        command: &str,
+        config: &CliConfig,
+        env: &[(&str, &str)],
+        extra: &[&str],
+    ) -> Result<Interval> {
+        let args = ["vnquant", command, "--database-url", "sqlite::memory:"];
+        let env = env
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>();
+        let matches = with_config_defaults(Cli::command(), config, &env)
+            .try_get_matches_from(args.iter().chain(extra))?;
+        match Cli::from_arg_matches(&matches)?.command {
+            Commands::FetchPricesAll { interval, .. }
+            | Commands::FetchIntradayPricesAll { interval, .. } => Ok(interval.into()),
+            _ => unreachable!("parsed a different subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_flag_precedence_over_env_and_config() -> Result<()> {
+        let config = CliConfig {
+            interval: Some("one-week".to_string()),
+            ..Default::default()
+        };
+        let env = [("VNQUANT_INTERVAL", "one-month")];
+        let daily = "fetch-prices-all";
+
+        assert_eq!(
+            parse_interval(daily, &CliConfig::default(), &[], &[])?,
+            Interval::OneDay
+        );
+        assert_eq!(parse_interval(daily, &config, &[], &[])?, Interval::OneWeek);
+        assert_eq!(
+            parse_interval(daily, &config, &env, &[])?,
+            Interval::OneMonth
+        );
+        assert_eq!(
+            parse_interval(daily, &config, &env, &["--interval", "one-hour"])?,
+            Interval::OneHour
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_intraday_commands_ignore_the_daily_interval() -> Result<()> {
+        let intraday = "fetch-intraday-prices-all";
+        let config = CliConfig {
+            interval: Some("one-day".to_string()),
+            ..Default::default()
+        };
+        let env = [("VNQUANT_INTERVAL", "one-week")];
+        assert_eq!(
+            parse_interval(intraday, &config, &env, &[])?,
+            Interval::OneHour
+        );
+
+        let config = CliConfig {
+            intraday_interval: Some("five-minutes".to_string()),
+            ..config
+        };
+        assert_eq!(
+            parse_interval(intraday, &config, &env, &[])?,
+            Interval::FiveMinutes
+        );
+        let env = [("VNQUANT_INTRADAY_INTERVAL", "fifteen-minutes")];
+        assert_eq!(
+            parse_interval(intraday, &config, &env, &[])?,
+            Interval::FifteenMinutes
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_config() {
+        let config = |interval: &str, intraday_interval: &str| CliConfig {
+            interval: Some(interval.to_string()),
+            intraday_interval: Some(intraday_interval.to_string()),
+            ..Default::default()
+        };
+
+        assert!(validate_config(&config("one-day", "one-hour")).is_ok());
+        let err = validate_config(&config("1D", "one-hour")).unwrap_err();
+        assert!(err.to_string().contains("Invalid interval '1D' in config"));
+        let err = validate_config(&config("one-day", "one-day")).unwrap_err();
+        assert!(err.to_string().contains("not an intraday interval"));
+    }
+
+    async fn run_search(db: &Database, args: &[&str]) -> Result<Vec<String>> {
+        let cli = Cli::try_parse_from(
+            ["vnquant", "search", "--database-url", "sqlite::memory:"]
+                .iter()
+                .chain(args),
+        )?;
+        let Commands::Search {
+            query,
+            exchange,
+            field,
+            prefix,
+            limit,
+            ..
+        } = cli.command
+        else {
+            unreachable!("parsed a different subcommand");
+        };
+        let found = search_tickers(db, &query, exchange.as_deref(), &field, prefix, limit).await?;
+        Ok(found.into_iter().map(|t| t.symbol).collect())
+    }
+
+    #[tokio::test]
+    async fn test_search_command_dispatch() -> Result<()> {
+        let db = Database::new("sqlite::memory:").await?;
+        let bank = |symbol: &str, exchange: &str| Ticker {
+            description: Some(format!("{symbol} Commercial Bank")),
+            ..Ticker::new(symbol, exchange)
+        };
+        db.upsert_tickers(&[
+            bank("VCB", "HOSE"),
+            bank("SHB", "HNX"),
+            Ticker {
+                sector: Some("Bank".to_string()),
+                ..Ticker::new("FPT", "HOSE")
+            },
+        ])
+        .await?;
+
+        let mut all = run_search(&db, &["bank"]).await?;
+        all.sort();
+        assert_eq!(all, ["FPT", "SHB", "VCB"]);
+        assert_eq!(run_search(&db, &["bank", "--limit", "1"]).await?.len(), 1);
+        assert_eq!(run_search(&db, &["bank", "-e", "HNX"]).await?, ["SHB"]);
+        assert_eq!(
+            run_search(&db, &["bank", "-e", "HOSE", "-f", "description"]).await?,
+            ["VCB"]
+        );
+        assert_eq!(run_search(&db, &["VC", "--prefix"]).await?, ["VCB"]);
+        assert!(
+            run_search(&db, &["bank", "--prefix", "-e", "HOSE"])
+                .await
+                .is_err()
+        );
+        assert!(run_search(&db, &["bank", "-f", "bogus"]).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_does_not_migrate() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("fresh.sqlite3");
+        let url = format!("sqlite://{}?mode=rwc", path.display());
+
+        let db = open_database(&url, true).await?;
+        let tables: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'")
+                .fetch_one(db.get_pool().await)
+                .await?;
+        assert_eq!(tables, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_path_from_template() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let dir = tmp.path().join("export_path");
+        let vars = TemplateVars {
+            symbol: Some("VCB"),
+            exchange: Some("HOSE"),
+            interval: Some(Interval::OneHour),
+            date: None,
+        };
+
+        let path = export_path(
+            None,
+            Some("{exchange}/{symbol}_{interval}.parquet"),
+            Some(&dir),
+            &vars,
+        )?;
+        assert_eq!(
+            PathBuf::from(&path),
+            dir.join("HOSE").join("VCB_60.parquet")
+        );
+        assert!(dir.join("HOSE").is_dir());
+        assert!(export_path(None, Some("{date}.csv"), Some(&dir), &vars).is_err());
+        assert_eq!(export_path(Some("out.csv"), None, None, &vars)?, "out.csv");
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_path_arg() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            config_path_arg(args(&["vnquant", "--config", "a.toml", "list-tickers"])),
+            Some(PathBuf::from("a.toml"))
+        );
+        assert_eq!(
+            config_path_arg(args(&["vnquant", "list-tickers", "--config=b.toml"])),
+            Some(PathBuf::from("b.toml"))
+        );
+        assert_eq!(config_path_arg(args(&["vnquant", "list-tickers"])), None);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::finance::{features::simple_returns, models::Candle};
+use chrono::Datelike;
+use std::collections::HashMap;
+
+/// Pair up the candles of two series that share a timestamp, in timestamp order.
+///
+/// Timestamps present in only one series are dropped, so missing bars on either side never
+/// shift the pairing. Neither input has to be sorted.
+pub fn align_by_timestamp(a: &[Candle], b: &[Candle]) -> Vec<(Candle, Candle)> {
+    let by_timestamp = b
+        .iter()
+        .map(|candle| (candle.timestamp, candle))
+        .collect::<HashMap<_, _>>();
+    let mut pairs = a
+        .iter()
+        .filter_map(|left| {
+            by_timestamp
+                .get(&left.timestamp)
+                .map(|right| (left.clone(), (*right).clone()))
+        })
+        .collect::<Vec<_>>();
+    pairs.sort_by_key(|(left, _)| left.timestamp);
+    pairs
+}
+
+/// Rolling Pearson correlation of the close-to-close returns of two series.
+///
+/// The series are first intersected with [`align_by_timestamp`] and the result has one value
+/// per common timestamp, in the same order. Returns are taken between consecutive common
+/// timestamps, so each value correlates the last `window` returns. A value is `None` until
+/// the window is full, when the window contains a missing return (see
+/// [`simple_returns`]) and when either side has no variance over the window.
+pub fn rolling_correlation(a: &[Candle], b: &[Candle], window: usize) -> Vec<Option<f64>> {
+    let (left, right): (Vec<_>, Vec<_>) = align_by_timestamp(a, b).into_iter().unzip();
+    let left = simple_returns(&left);
+    let right = simple_returns(&right);
+
+    let mut out = vec![None; left.len()];
+    if window < 2 {
+        return out;
+    }
+    for end in window..=left.len() {
+        let pairs = left[end - window..end]
+            .iter()
+            .zip(&right[end - window..end])
+            .map(|(x, y)| x.zip(*y))
+            .collect::<Option<Vec<_>>>();
+        out[end - 1] = pairs.and_then(|pairs| pearson(&pairs));
+    }
+    out
+}
+
+/// Average close-to-close return by day of week, indexed from Monday (0) to Sunday (6).
+///
+/// Each return is attributed to the weekday of its bar in `tz`, so a HOSE bar stamped
+/// shortly after local midnight counts for the local day, not the UTC one. Days without
+/// any return are `None`. `candles` must be sorted by timestamp.
+pub fn returns_by_weekday(candles: &[Candle], tz: chrono_tz::Tz) -> [Option<f64>; 7] {
+    let mut sums = [0.0; 7];
+    let mut counts = [0usize; 7];
+    for (candle, ret) in candles.iter().zip(simple_returns(candles)) {
+        if let Some(ret) = ret {
+            let day = candle
+                .timestamp
+                .with_timezone(&tz)
+                .weekday()
+                .num_days_from_monday() as usize;
+            sums[day] += ret;
+            counts[day] += 1;
+        }
+    }
+    std::array::from_fn(|day| (counts[day] > 0).then(|| sums[day] / counts[day] as f64))
+}
+
+fn pearson(pairs: &[(f64, f64)]) -> Option<f64> {
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in pairs {
+        let (dx, dy) = (x - mean_x, y - mean_y);
+        cov += dx * dy;
+        var_x += dx * dx;
+        var_y += dy * dy;
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(cov / (var_x * var_y).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{daily_series, flat_candle};
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn test_rolling_correlation() {
+        let closes = [100.0, 102.0, 101.0, 104.0, 103.0, 107.0, 106.0, 110.0];
+        let a = daily_series(&closes);
+        // Twice the moves of `a`, and missing the third bar
+        let mut b = daily_series(&closes.map(|c| 2.0 * c - 100.0));
+        b.remove(2);
+
+        let correlation = rolling_correlation(&a, &b, 3);
+        assert_eq!(correlation.len(), 7);
+        assert!(correlation[..3].iter().all(Option::is_none));
+        for value in &correlation[3..] {
+            assert!(value.unwrap() > 0.99);
+        }
+
+        let inverse = daily_series(&closes.map(|c| 300.0 - c));
+        let correlation = rolling_correlation(&a, &inverse, 4);
+        assert!(correlation[7].unwrap() < -0.99);
+    }
+
+    #[test]
+    fn test_returns_by_weekday() {
+        // 00:30 in Ho Chi Minh City, still the previous day in UTC. 2024-01-01 is a Monday.
+        let first = Utc.with_ymd_and_hms(2023, 12, 31, 17, 30, 0).unwrap();
+        let mut close = 100.0;
+        let candles = (0..28)
+            .map(|day| first + Duration::days(day))
+            .filter(|timestamp| day_index(*timestamp + Duration::hours(7)) < 5)
+            .map(|timestamp| {
+                close *= if day_index(timestamp + Duration::hours(7)) == 0 {
+                    1.02
+                } else {
+                    0.995
+                };
+                flat_candle(timestamp, close)
+            })
+            .collect::<Vec<_>>();
+
+        let local = returns_by_weekday(&candles, chrono_tz::Asia::Ho_Chi_Minh);
+        assert!((local[0].unwrap() - 0.02).abs() < 1e-12);
+        assert!(local[1..5].iter().all(|avg| avg.unwrap() < 0.0));
+        assert_eq!(local[5..], [None, None]);
+
+        // In UTC the Monday bars fall on Sunday
+        let utc = returns_by_weekday(&candles, chrono_tz::UTC);
+        assert!(utc[6].unwrap() > 0.0);
+        assert!(utc[0].unwrap() < 0.0);
+    }
+
+    fn day_index(timestamp: chrono::DateTime<Utc>) -> u32 {
+        timestamp.weekday().num_days_from_monday()
+    }
+
+    #[test]
+    fn test_align_by_timestamp_intersects() {
+        let a = daily_series(&[1.0, 2.0, 3.0, 4.0]);
+        let mut b = daily_series(&[10.0, 20.0, 30.0]);
+        b.reverse();
+        b.remove(1);
+
+        let closes = align_by_timestamp(&a, &b)
+            .into_iter()
+            .map(|(left, right)| (left.close, right.close))
+            .collect::<Vec<_>>();
+        assert_eq!(closes, vec![(1.0, 10.0), (3.0, 30.0)]);
+    }
+}
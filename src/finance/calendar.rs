@@ -0,0 +1,155 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+
+/// Offset of Vietnam time (ICT) from UTC.
+const VN_UTC_OFFSET_HOURS: i64 = 7;
+
+/// Lunch break of the Vietnamese exchanges in local time, from 11:30 until 13:00.
+const VN_LUNCH_BREAK: (NaiveTime, NaiveTime) = (
+    NaiveTime::from_hms_opt(11, 30, 0).unwrap(),
+    NaiveTime::from_hms_opt(13, 0, 0).unwrap(),
+);
+
+/// Decides which dates an exchange is open on.
+pub trait ExchangeCalendar: Send + Sync {
+    /// Whether the exchange holds a trading session on `date`.
+    fn is_trading_day(&self, date: NaiveDate) -> bool;
+
+    /// Local date of the session a bar stamped `timestamp` belongs to.
+    fn session_date(&self, timestamp: DateTime<Utc>) -> NaiveDate {
+        timestamp.date_naive()
+    }
+
+    /// Whether `timestamp` falls in a break inside a session, such as lunch, when no bars
+    /// are traded.
+    fn is_break(&self, _timestamp: DateTime<Utc>) -> bool {
+        false
+    }
+}
+
+/// Calendar that only closes on weekends, used for exchanges without a holiday list.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WeekdayCalendar;
+
+impl ExchangeCalendar for WeekdayCalendar {
+    fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !is_weekend(date)
+    }
+}
+
+/// Ho Chi Minh City Stock Exchange.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HoseCalendar;
+
+impl ExchangeCalendar for HoseCalendar {
+    fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !is_weekend(date) && !is_vn_holiday(date)
+    }
+
+    fn session_date(&self, timestamp: DateTime<Utc>) -> NaiveDate {
+        vn_session_date(timestamp)
+    }
+
+    fn is_break(&self, timestamp: DateTime<Utc>) -> bool {
+        is_vn_lunch_break(timestamp)
+    }
+}
+
+/// Hanoi Stock Exchange, which also runs UPCOM. It follows the same holidays as HOSE.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HnxCalendar;
+
+impl ExchangeCalendar for HnxCalendar {
+    fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !is_weekend(date) && !is_vn_holiday(date)
+    }
+
+    fn session_date(&self, timestamp: DateTime<Utc>) -> NaiveDate {
+        vn_session_date(timestamp)
+    }
+
+    fn is_break(&self, timestamp: DateTime<Utc>) -> bool {
+        is_vn_lunch_break(timestamp)
+    }
+}
+
+/// Calendar for an exchange code as stored in `TICKERS.exchange`.
+pub fn calendar_for_exchange(exchange: &str) -> &'static dyn ExchangeCalendar {
+    match exchange.to_ascii_uppercase().as_str() {
+        "HOSE" | "HSX" => &HoseCalendar,
+        "HNX" | "UPCOM" => &HnxCalendar,
+        _ => &WeekdayCalendar,
+    }
+}
+
+/// Solar holidays observed every year: New Year, Reunification Day, Labour Day and
+/// National Day.
+const FIXED_VN_HOLIDAYS: [(u32, u32); 4] = [(1, 1), (4, 30), (5, 1), (9, 2)];
+
+/// Market closures that move from year to year: Tet and Hung Kings (lunar calendar)
+/// plus the compensation and bridge days announced by the government.
+///
+/// To extend, append the closure dates published by HOSE/SSC for the new year. Fixed
+/// holidays from [`FIXED_VN_HOLIDAYS`] don't need to be repeated here.
+const MOVABLE_VN_HOLIDAYS: &[(i32, u32, u32)] = &[
+    // 2023
+    (2023, 1, 2),
+    (2023, 1, 20),
+    (2023, 1, 23),
+    (2023, 1, 24),
+    (2023, 1, 25),
+    (2023, 1, 26),
+    (2023, 5, 2),
+    (2023, 5, 3),
+    (2023, 9, 1),
+    (2023, 9, 4),
+    // 2024
+    (2024, 2, 8),
+    (2024, 2, 9),
+    (2024, 2, 12),
+    (2024, 2, 13),
+    (2024, 2, 14),
+    (2024, 4, 18),
+    (2024, 9, 3),
+    // 2025
+    (2025, 1, 27),
+    (2025, 1, 28),
+    (2025, 1, 29),
+    (2025, 1, 30),
+    (2025, 1, 31),
+    (2025, 4, 7),
+    (2025, 9, 1),
+];
+
+fn vn_session_date(timestamp: DateTime<Utc>) -> NaiveDate {
+    (timestamp + Duration::hours(VN_UTC_OFFSET_HOURS)).date_naive()
+}
+
+fn is_vn_lunch_break(timestamp: DateTime<Utc>) -> bool {
+    let local = (timestamp + Duration::hours(VN_UTC_OFFSET_HOURS)).time();
+    (VN_LUNCH_BREAK.0..VN_LUNCH_BREAK.1).contains(&local)
+}
+
+fn is_weekend(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+fn is_vn_holiday(date: NaiveDate) -> bool {
+    FIXED_VN_HOLIDAYS.contains(&(date.month(), date.day()))
+        || MOVABLE_VN_HOLIDAYS.contains(&(date.year(), date.month(), date.day()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tet_is_not_a_trading_day() {
+        let tet = NaiveDate::from_ymd_opt(2024, 2, 12).unwrap();
+        let after_tet = NaiveDate::from_ymd_opt(2024, 2, 15).unwrap();
+
+        assert!(!HoseCalendar.is_trading_day(tet));
+        assert!(!calendar_for_exchange("HNX").is_trading_day(tet));
+        assert!(HoseCalendar.is_trading_day(after_tet));
+        assert!(WeekdayCalendar.is_trading_day(tet));
+    }
+}
@@ -0,0 +1,119 @@
+use crate::finance::models::{Ticker, interval_to_db_str};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use tradingview::Interval;
+
+/// Tickers finished by a bulk fetch of one interval, persisted so an interrupted run can
+/// resume.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FetchCheckpoint {
+    #[serde(skip)]
+    path: PathBuf,
+    /// Interval of the fetch, as stored in the database (e.g. `1D`)
+    interval: String,
+    /// `(symbol, exchange)` pairs whose prices are stored
+    completed: BTreeSet<(String, String)>,
+}
+
+impl FetchCheckpoint {
+    /// Empty checkpoint of an `interval` fetch that will be written to `path`, replacing any
+    /// earlier run.
+    pub fn new(path: impl AsRef<Path>, interval: Interval) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            interval: interval_to_db_str(interval).into_owned(),
+            completed: BTreeSet::new(),
+        }
+    }
+
+    /// Load the checkpoint of an `interval` fetch at `path`, or start an empty one if the
+    /// file doesn't exist yet. Fails when the file was written by a fetch of another
+    /// interval, whose finished tickers say nothing about this one.
+    pub fn load(path: impl AsRef<Path>, interval: Interval) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::new(path, interval));
+        }
+
+        let mut checkpoint: Self = serde_json::from_reader(std::fs::File::open(path)?)
+            .map_err(|e| anyhow::anyhow!("Invalid checkpoint {}: {}", path.display(), e))?;
+        checkpoint.path = path.to_path_buf();
+        checkpoint.ensure_interval(interval)?;
+        Ok(checkpoint)
+    }
+
+    /// Fail unless this checkpoint belongs to a fetch of `interval`.
+    pub fn ensure_interval(&self, interval: Interval) -> anyhow::Result<()> {
+        let interval = interval_to_db_str(interval);
+        if self.interval != interval {
+            return Err(anyhow::anyhow!(
+                "Checkpoint {} belongs to a {} fetch, not {}",
+                self.path.display(),
+                self.interval,
+                interval
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether `ticker` finished in an earlier chunk or run.
+    pub fn contains(&self, ticker: &Ticker) -> bool {
+        self.completed
+            .contains(&(ticker.symbol.clone(), ticker.exchange.clone()))
+    }
+
+    /// Number of finished tickers.
+    pub fn len(&self) -> usize {
+        self.completed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.completed.is_empty()
+    }
+
+    /// Record `tickers` as finished and write the checkpoint.
+    ///
+    /// The file is replaced through a rename, so a crash mid-write keeps the previous state.
+    pub fn mark_done<'a>(
+        &mut self,
+        tickers: impl IntoIterator<Item = &'a Ticker>,
+    ) -> anyhow::Result<()> {
+        self.completed.extend(
+            tickers
+                .into_iter()
+                .map(|t| (t.symbol.clone(), t.exchange.clone())),
+        );
+
+        let tmp = self.path.with_extension("tmp");
+        serde_json::to_writer(std::fs::File::create(&tmp)?, self)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tradingview::MarketSymbol;
+
+    #[test]
+    fn test_checkpoint_round_trip() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("checkpoint.json");
+        let vcb = Ticker::new("VCB", "HOSE");
+
+        let mut checkpoint = FetchCheckpoint::new(&path, Interval::OneDay);
+        checkpoint.mark_done([&vcb])?;
+        let loaded = FetchCheckpoint::load(&path, Interval::OneDay)?;
+        let hourly = FetchCheckpoint::load(&path, Interval::OneHour);
+
+        assert_eq!(loaded, checkpoint);
+        assert!(loaded.contains(&vcb));
+        assert!(!loaded.contains(&Ticker::new("FPT", "HOSE")));
+        // A daily checkpoint must not skip tickers of an hourly run
+        let err = hourly.unwrap_err().to_string();
+        assert!(err.contains("belongs to a 1D fetch"), "{err}");
+        Ok(())
+    }
+}
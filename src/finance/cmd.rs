@@ -1,10 +1,40 @@
-use crate::finance::{db::Database, models::Ticker};
+use crate::finance::{
+    checkpoint::FetchCheckpoint,
+    db::{Database, UpsertReport},
+    features::build_ml_features,
+    metrics::metrics,
+    models::{Candle, FetchReport, MlFeatures, Ticker},
+    progress::{ChunkProgress, FetchProgress},
+    rate_limit::RateLimiter,
+    retry::{capped_backoff_delay, retry_async},
+    token::QuoteAuth,
+};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use futures::{
     TryStreamExt,
     stream::{self, StreamExt},
 };
 use std::str::FromStr;
-use tradingview::{Country, Interval, history, list_symbols};
+use std::sync::{PoisonError, RwLock};
+use tokio_util::sync::CancellationToken;
+use tradingview::{Country, Interval, MarketSymbol, OHLCV, SymbolInfo, history, list_symbols};
+
+/// Retries for a single TradingView history request before giving up.
+const FETCH_MAX_RETRIES: usize = 3;
+const FETCH_RETRY_BASE_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Default delay before the first retry of a failed chunk in `fetch_prices_all`.
+pub const DEFAULT_CHUNK_BASE_BACKOFF: std::time::Duration = std::time::Duration::from_secs(2);
+/// Default cap on the delay between retries of a chunk.
+pub const DEFAULT_CHUNK_MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(60);
+/// Default pause between consecutive chunks.
+pub const DEFAULT_INTER_CHUNK_DELAY: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Tickers handed to `fetch_intraday_prices` at a time by `fetch_intraday_prices_all`.
+const INTRADAY_CHUNK_SIZE: usize = 1000;
+
+/// Default exchanges configuration, used when no config file is given.
+const DEFAULT_EXCHANGES_CONFIG: &str = include_str!("../../config/exchanges.json");
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ExchangeConfig {
@@ -17,10 +47,144 @@ pub struct TVConfigMap {
     pub exchanges: Vec<ExchangeConfig>,
 }
 
-pub async fn fetch_tickers(db: Database, path: &str) -> anyhow::Result<()> {
-    let exchanges_str = std::fs::read_to_string(path)?;
+impl TVConfigMap {
+    /// Parse an exchanges configuration from JSON.
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Load an exchanges configuration from a JSON file on disk.
+    pub fn from_path(path: &str) -> anyhow::Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read exchanges config {}: {}", path, e))?;
+        Self::from_json(&json)
+    }
+
+    /// The configuration compiled into the binary from `config/exchanges.json`.
+    pub fn embedded() -> anyhow::Result<Self> {
+        Self::from_json(DEFAULT_EXCHANGES_CONFIG)
+    }
+
+    /// Load the configuration at `path` if given, otherwise the embedded default, and
+    /// validate it.
+    pub fn load(path: Option<&str>) -> anyhow::Result<Self> {
+        let config = match path {
+            Some(path) => Self::from_path(path)?,
+            None => Self::embedded()?,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Reject empty configurations and exchanges with a blank name.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.exchanges.is_empty() {
+            return Err(anyhow::anyhow!("Exchanges config lists no exchanges"));
+        }
+        if let Some(index) = self
+            .exchanges
+            .iter()
+            .position(|e| e.exchange.trim().is_empty())
+        {
+            return Err(anyhow::anyhow!(
+                "Exchanges config entry {} has an empty exchange name",
+                index
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Route TradingView HTTP traffic through `proxy_url`.
+///
+/// The `tradingview` client has no base-URL setting, but its HTTP client honours the
+/// standard `HTTPS_PROXY`/`HTTP_PROXY` variables, so the proxy is applied through them.
+///
+/// # Safety
+///
+/// This sets process environment variables, so it must be called before any other thread
+/// is started, in particular before the tokio runtime is built.
+pub unsafe fn configure_proxy(proxy_url: &str) -> anyhow::Result<()> {
+    let proxy_url = proxy_url.trim();
+    if !["http://", "https://", "socks5://", "socks5h://"]
+        .iter()
+        .any(|scheme| proxy_url.starts_with(scheme))
+    {
+        return Err(anyhow::anyhow!(
+            "Invalid proxy URL '{}': expected an http://, https:// or socks5:// URL",
+            proxy_url
+        ));
+    }
+
+    // SAFETY: the caller guarantees no other thread is running
+    unsafe {
+        std::env::set_var("HTTPS_PROXY", proxy_url);
+        std::env::set_var("HTTP_PROXY", proxy_url);
+    }
+    tracing::debug!("Routing TradingView traffic through proxy {}", proxy_url);
+
+    Ok(())
+}
+
+static AUTH: RwLock<Option<QuoteAuth>> = RwLock::new(None);
+
+/// Send a quote token for `auth` with every TradingView price request, for data that
+/// requires a logged-in account. `None` stops sending one.
+///
+/// The token is looked up for each request, so runs that outlive it switch to a fresh one.
+pub fn configure_auth(auth: Option<QuoteAuth>) {
+    *AUTH.write().unwrap_or_else(PoisonError::into_inner) = auth;
+}
+
+/// A valid token for the session set by [`configure_auth`], if any.
+async fn auth_token() -> anyhow::Result<Option<String>> {
+    let auth = AUTH.read().unwrap_or_else(PoisonError::into_inner).clone();
+    match auth {
+        Some(auth) => Ok(Some(auth.token().await?)),
+        None => Ok(None),
+    }
+}
 
-    let config: TVConfigMap = serde_json::from_str(&exchanges_str)?;
+/// Fetch the symbol lists of every configured exchange and upsert them as tickers.
+///
+/// Exchanges are read from `config_path` when given and from the embedded default
+/// otherwise. With `limit_per_exchange`, only the first `n` symbols returned for each
+/// exchange are kept, which is handy for quick end-to-end smoke tests. With `dry_run`, the
+/// planned requests are logged and nothing is fetched or stored.
+///
+/// This is the only ticker fetcher; it is re-exported as `finance::fetch_tickers`.
+///
+/// ```no_run
+/// use vnquant_dataset::finance::{db::Database, fetch_tickers};
+///
+/// # async fn run() -> anyhow::Result<()> {
+/// let db = Database::new("sqlite://data.sqlite3").await?;
+/// // Embedded exchange list, first 10 symbols of each exchange
+/// fetch_tickers(db, None, Some(10), false).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn fetch_tickers(
+    db: Database,
+    config_path: Option<&str>,
+    limit_per_exchange: Option<usize>,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let config = TVConfigMap::load(config_path)?;
+    if dry_run {
+        let exchanges = config
+            .exchanges
+            .iter()
+            .map(|e| e.exchange.as_str())
+            .collect::<Vec<_>>();
+        tracing::info!(
+            "Dry run: would list symbols of {} exchanges ({} requests): {}",
+            exchanges.len(),
+            exchanges.len(),
+            exchanges.join(", ")
+        );
+        return Ok(());
+    }
     let mut tickers = Vec::new();
 
     for exchange_config in config.exchanges {
@@ -34,7 +198,10 @@ pub async fn fetch_tickers(db: Database, path: &str) -> anyhow::Result<()> {
             .exchange(&exchange_config.exchange)
             .maybe_country(country_opt);
 
-        let symbols = query.call().await?;
+        let mut symbols = query.call().await?;
+        if let Some(limit) = limit_per_exchange {
+            symbols.truncate(limit);
+        }
         tracing::info!(
             "Fetched {} symbols from exchange: {} (country: {})",
             symbols.len(),
@@ -49,16 +216,47 @@ pub async fn fetch_tickers(db: Database, path: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Fetch the full history of `ticker` and store it.
+///
+/// Tickers that are not stored yet are inserted first rather than rejected, so prices
+/// can be fetched for any symbol TradingView knows about. The batch fetchers follow the
+/// same auto-insert rule.
+///
+/// With `refresh_metadata`, the description, industry, sector and founding year from the
+/// chart's symbol info overwrite the stored ticker, which the symbol lists leave empty.
 pub async fn fetch_prices(
     db: Database,
     ticker: &Ticker,
     interval: Interval,
     replay: bool,
+    refresh_metadata: bool,
 ) -> anyhow::Result<()> {
-    // validate ticker
-    if ticker.symbol.is_empty() || ticker.exchange.is_empty() {
-        return Err(anyhow::anyhow!("Ticker symbol or exchange is empty"));
-    }
+    fetch_and_store(
+        &db,
+        ticker,
+        interval,
+        replay,
+        refresh_metadata,
+        (None, None),
+        None,
+    )
+    .await
+}
+
+/// Shared body of [`fetch_prices`] and [`fetch_prices_between`]: insert the ticker when it
+/// is new, fetch its history with retries and store the bars with `start <= timestamp < end`.
+///
+/// With a `limiter`, every request waits for a permit, retries included.
+async fn fetch_and_store(
+    db: &Database,
+    ticker: &Ticker,
+    interval: Interval,
+    replay: bool,
+    refresh_metadata: bool,
+    (start, end): (Option<DateTime<Utc>>, Option<DateTime<Utc>>),
+    limiter: Option<&RateLimiter>,
+) -> anyhow::Result<()> {
+    ensure_valid(ticker)?;
     // Check if ticker already exists
     let existing_ticker = db.get_ticker(&ticker.symbol, &ticker.exchange).await?;
     if existing_ticker.is_none() {
@@ -70,45 +268,211 @@ pub async fn fetch_prices(
         );
     }
 
-    // Fetch historical prices
-    let query = history::single::retrieve()
-        .symbol(&ticker.symbol)
-        .exchange(&ticker.exchange)
-        .interval(interval)
-        .with_replay(replay);
-
-    let chart_data = query.call().await?;
-    // db.update_ticker(&chart_data.symbol_info).await?;
-    db.upsert_prices(ticker, interval, &chart_data.data).await?;
+    // Fetch historical prices, retrying transient network failures
+    let result = retry_async(
+        move || async move {
+            if let Some(limiter) = limiter {
+                limiter.acquire().await;
+            }
+            let token = auth_token().await?;
+            Ok(history::single::retrieve()
+                .maybe_auth_token(token.as_deref())
+                .symbol(&ticker.symbol)
+                .exchange(&ticker.exchange)
+                .interval(interval)
+                .with_replay(replay)
+                .call()
+                .await?)
+        },
+        FETCH_MAX_RETRIES,
+        FETCH_RETRY_BASE_DELAY,
+    )
+    .await;
+    metrics().record_fetch(&ticker.symbol, &ticker.exchange, result.is_ok());
+    let chart_data = result?;
+    store_fetched(
+        db,
+        ticker,
+        interval,
+        &chart_data.symbol_info,
+        &chart_data.data,
+        refresh_metadata,
+        (start, end),
+    )
+    .await?;
 
     Ok(())
 }
 
+/// Store what a single-ticker fetch returned: the ticker's metadata from `symbol_info` when
+/// `refresh_metadata` is set, and the bars with `start <= timestamp < end`.
+async fn store_fetched(
+    db: &Database,
+    ticker: &Ticker,
+    interval: Interval,
+    symbol_info: &SymbolInfo,
+    bars: &[impl OHLCV],
+    refresh_metadata: bool,
+    (start, end): (Option<DateTime<Utc>>, Option<DateTime<Utc>>),
+) -> anyhow::Result<UpsertReport> {
+    if refresh_metadata {
+        db.upsert_ticker(symbol_info).await?;
+    }
+
+    let candles = within_range(bars, start, end);
+    if start.is_some() || end.is_some() {
+        tracing::info!(
+            "Keeping {} of {} fetched bars for {}:{} in range",
+            candles.len(),
+            bars.len(),
+            ticker.symbol,
+            ticker.exchange
+        );
+    }
+    let report = db.upsert_prices(ticker, interval, &candles).await?;
+    metrics().record_rows(&ticker.symbol, &ticker.exchange, report.inserted);
+
+    Ok(report)
+}
+
+/// Fetch the bars from the last stored candle on and store them.
+///
+/// TradingView's history request has no start date, so the default window (or the full
+/// history with `replay`) is fetched and the bars before the last stored one are dropped.
+/// The last stored bar is stored again, as it may have been saved while its session was
+/// still trading. Falls back to a full fetch when nothing is stored yet for the ticker and
+/// interval.
+pub async fn fetch_prices_incremental(
+    db: Database,
+    ticker: &Ticker,
+    interval: Interval,
+    replay: bool,
+) -> anyhow::Result<()> {
+    ensure_valid(ticker)?;
+    let last = db
+        .get_last_candle_timestamp(&ticker.symbol, &ticker.exchange, interval)
+        .await?;
+    if last.is_none() {
+        tracing::info!(
+            "No stored prices for {}:{}, running a full fetch",
+            ticker.symbol,
+            ticker.exchange
+        );
+    }
+
+    fetch_and_store(&db, ticker, interval, replay, false, (last, None), None).await
+}
+
+/// [`Ticker::validate`] with the ticker named in the error.
+fn ensure_valid(ticker: &Ticker) -> anyhow::Result<()> {
+    ticker.validate().map_err(|e| {
+        anyhow::anyhow!(
+            "Invalid ticker '{}:{}': {}",
+            ticker.symbol,
+            ticker.exchange,
+            e
+        )
+    })
+}
+
+/// UTC bounds covering the whole days from `since` through `until`.
+///
+/// The start is midnight of `since` and the end is midnight after `until`, for use as
+/// `start <= timestamp < end`. Errors when `since` is after `until`.
+pub fn day_range(
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+) -> anyhow::Result<(Option<DateTime<Utc>>, Option<DateTime<Utc>>)> {
+    if let (Some(since), Some(until)) = (since, until)
+        && since > until
+    {
+        return Err(anyhow::anyhow!(
+            "--since {} is after --until {}",
+            since,
+            until
+        ));
+    }
+
+    let start = since.map(|date| date.and_time(NaiveTime::MIN).and_utc());
+    let end = until
+        .and_then(|date| date.succ_opt())
+        .map(|date| date.and_time(NaiveTime::MIN).and_utc());
+    Ok((start, end))
+}
+
+/// Bars with `start <= timestamp < end`; a missing bound leaves that side open.
+fn within_range(
+    bars: &[impl OHLCV],
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Vec<Candle> {
+    bars.iter()
+        .filter(|bar| {
+            start.is_none_or(|start| bar.datetime() >= start)
+                && end.is_none_or(|end| bar.datetime() < end)
+        })
+        .map(Candle::from_ohlcv)
+        .collect()
+}
+
+/// Fetch prices and store only the bars with `start <= timestamp < end`.
+///
+/// TradingView's history request has no date bounds, so the default window (or the full
+/// history with `replay`) is fetched and filtered before it is stored. See [`day_range`]
+/// for turning dates into bounds. Otherwise the same as [`fetch_prices`].
+pub async fn fetch_prices_between(
+    db: Database,
+    ticker: &Ticker,
+    interval: Interval,
+    replay: bool,
+    refresh_metadata: bool,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> anyhow::Result<()> {
+    fetch_and_store(
+        &db,
+        ticker,
+        interval,
+        replay,
+        refresh_metadata,
+        (start, end),
+        None,
+    )
+    .await
+}
+
 pub async fn fetch_prices_batch(
     db: &Database,
     tickers: &[Ticker],
     interval: Interval,
+    incremental: bool,
 ) -> anyhow::Result<()> {
     // Validate tickers
     if tickers.is_empty() {
         return Err(anyhow::anyhow!("No tickers provided for batch processing"));
     }
     for ticker in tickers {
-        if ticker.symbol.is_empty() || ticker.exchange.is_empty() {
-            return Err(anyhow::anyhow!(
-                "Ticker symbol or exchange is empty for ticker: {:?}",
-                ticker
-            ));
-        }
+        ensure_valid(ticker)?;
     }
 
     db.upsert_tickers(tickers).await?;
 
-    let data = history::batch::retrieve()
+    let token = auth_token().await?;
+    let data = match history::batch::retrieve()
+        .maybe_auth_token(token.as_deref())
         .symbols(tickers)
         .interval(interval)
         .call()
-        .await?;
+        .await
+    {
+        Ok(data) => data,
+        Err(e) => {
+            for ticker in tickers {
+                metrics().record_fetch(&ticker.symbol, &ticker.exchange, false);
+            }
+            return Err(e.into());
+        }
+    };
 
     // Process chart data as a stream with controlled concurrency
     stream::iter(data.values())
@@ -118,10 +482,33 @@ pub async fn fetch_prices_batch(
             let data_clone = chart_data.data.clone();
 
             async move {
+                metrics().record_fetch(symbol_info.symbol(), symbol_info.exchange(), true);
                 db_clone.upsert_ticker(&symbol_info).await?;
-                db_clone
-                    .upsert_prices(&symbol_info, interval, &data_clone)
-                    .await
+                let report = if incremental
+                    && let Some(last) = db_clone
+                        .get_last_candle_timestamp(
+                            symbol_info.symbol(),
+                            symbol_info.exchange(),
+                            interval,
+                        )
+                        .await?
+                {
+                    // The last stored bar may have changed since it was stored
+                    let new_candles = within_range(&data_clone, Some(last), None);
+                    db_clone
+                        .upsert_prices(&symbol_info, interval, &new_candles)
+                        .await?
+                } else {
+                    db_clone
+                        .upsert_prices(&symbol_info, interval, &data_clone)
+                        .await?
+                };
+                metrics().record_rows(
+                    symbol_info.symbol(),
+                    symbol_info.exchange(),
+                    report.inserted,
+                );
+                Ok::<_, anyhow::Error>(report.inserted)
             }
         })
         .buffer_unordered(10) // Process up to 10 upserts concurrently
@@ -131,31 +518,105 @@ pub async fn fetch_prices_batch(
     Ok(())
 }
 
+/// Fetch prices for every stored ticker in batch requests of `chunk_size` tickers.
+///
+/// `progress` hears about every ticker of a chunk once that chunk succeeds or gives up.
+/// Cancelling `cancel` stops the run after the chunk in flight, whose prices are already
+/// committed. Tickers in `checkpoint` are skipped, and each successful chunk is added to
+/// it. Returns the number of chunks that completed successfully, or an error when any chunk
+/// failed. A cancelled run only logs its failed chunks, so the caller can report the
+/// interruption.
+#[bon::builder]
 pub async fn fetch_prices_all(
     db: Database,
     interval: Interval,
+    /// Tickers per batch request
+    #[builder(default = 100)]
     chunk_size: usize,
+    /// Retries of a failed chunk before giving up on it
+    #[builder(default = 2)]
     max_retries: usize,
-) -> anyhow::Result<()> {
-    let tickers = db.get_all_tickers().await?;
-    if tickers.is_empty() {
+    /// Delay before the first retry of a chunk, doubled (with jitter) on each further retry
+    #[builder(default = DEFAULT_CHUNK_BASE_BACKOFF)]
+    base_backoff: std::time::Duration,
+    /// Upper bound on the delay between retries of a chunk
+    #[builder(default = DEFAULT_CHUNK_MAX_BACKOFF)]
+    max_backoff: std::time::Duration,
+    /// Pause between consecutive chunks
+    #[builder(default = DEFAULT_INTER_CHUNK_DELAY)]
+    inter_chunk_delay: std::time::Duration,
+    /// Only store bars from the last stored candle on, which is refreshed
+    #[builder(default)]
+    incremental: bool,
+    /// Log what would be fetched without making any request
+    #[builder(default)]
+    dry_run: bool,
+    progress: Option<&dyn FetchProgress>,
+    cancel: Option<&CancellationToken>,
+    mut checkpoint: Option<&mut FetchCheckpoint>,
+) -> anyhow::Result<usize> {
+    if let Some(checkpoint) = checkpoint.as_deref() {
+        checkpoint.ensure_interval(interval)?;
+    }
+    let total_tickers = db.get_ticker_count().await? as usize;
+    if total_tickers == 0 {
         tracing::warn!("No tickers found in the database");
-        return Ok(());
+        return Ok(0);
     }
 
-    let total_chunks = tickers.len().div_ceil(chunk_size);
+    let total_chunks = total_tickers.div_ceil(chunk_size);
+    if dry_run {
+        tracing::info!(
+            "Dry run: would fetch {} prices for {} tickers in {} chunks of {} ({} batch requests, up to {} with retries)",
+            interval,
+            total_tickers,
+            total_chunks,
+            chunk_size,
+            total_chunks,
+            total_chunks * (max_retries + 1)
+        );
+        return Ok(0);
+    }
     let mut successful_chunks = 0;
     let mut failed_chunks = 0;
+    let mut done = 0;
 
     tracing::info!(
         "Processing {} tickers in {} chunks of {}",
-        tickers.len(),
+        total_tickers,
         total_chunks,
         chunk_size
     );
 
-    for (chunk_idx, chunk) in tickers.chunks(chunk_size).enumerate() {
+    // Tickers are streamed so only one chunk is held in memory
+    let mut chunks = std::pin::pin!(db.stream_all_tickers().chunks(chunk_size).enumerate());
+    while let Some((chunk_idx, chunk)) = chunks.next().await {
+        let mut chunk = chunk.into_iter().collect::<anyhow::Result<Vec<_>>>()?;
+        if let Some(checkpoint) = checkpoint.as_deref() {
+            let before = chunk.len();
+            chunk.retain(|ticker| !checkpoint.contains(ticker));
+            let skipped = before - chunk.len();
+            if skipped > 0 {
+                tracing::info!(
+                    "Skipping {} tickers of chunk {}/{} already in the checkpoint",
+                    skipped,
+                    chunk_idx + 1,
+                    total_chunks
+                );
+                if let Some(progress) = progress {
+                    for _ in 0..skipped {
+                        done += 1;
+                        progress.on_ticker_done(done, total_tickers, true);
+                    }
+                }
+            }
+            if chunk.is_empty() {
+                continue;
+            }
+        }
+
         let mut attempts = 0;
+        let mut chunk_ok = false;
         let mut last_error = None;
         if let Some(last_error) = last_error {
             tracing::warn!("Last error: {}", last_error);
@@ -173,7 +634,7 @@ pub async fn fetch_prices_all(
 
             let start = std::time::Instant::now();
 
-            match fetch_prices_batch(&db, chunk, interval).await {
+            match fetch_prices_batch(&db, &chunk, interval, incremental).await {
                 Ok(_) => {
                     let duration = start.elapsed();
                     tracing::info!(
@@ -183,6 +644,7 @@ pub async fn fetch_prices_all(
                         duration.as_secs_f64()
                     );
                     successful_chunks += 1;
+                    chunk_ok = true;
                     break;
                 }
                 Err(e) => {
@@ -191,14 +653,16 @@ pub async fn fetch_prices_all(
                     attempts += 1;
 
                     if attempts <= max_retries {
-                        let delay = std::time::Duration::from_secs(2u64.pow(attempts as u32)); // Exponential backoff
+                        metrics().record_retry("chunk");
+                        let delay =
+                            capped_backoff_delay(attempts as u32, base_backoff, max_backoff);
                         tracing::warn!(
-                            "Chunk {}/{} failed after {:.2}s (attempt {}), retrying in {}s: {}",
+                            "Chunk {}/{} failed after {:.2}s (attempt {}), retrying in {:.2}s: {}",
                             chunk_idx + 1,
                             total_chunks,
                             duration.as_secs_f64(),
                             attempts,
-                            delay.as_secs(),
+                            delay.as_secs_f64(),
                             last_error.as_ref().unwrap()
                         );
                         tokio::time::sleep(delay).await;
@@ -217,8 +681,28 @@ pub async fn fetch_prices_all(
             }
         }
 
-        // Optional: Add delay between chunks
-        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        if chunk_ok && let Some(checkpoint) = checkpoint.as_deref_mut() {
+            checkpoint.mark_done(&chunk)?;
+        }
+        if let Some(progress) = progress {
+            for _ in &chunk {
+                done += 1;
+                progress.on_ticker_done(done, total_tickers, chunk_ok);
+            }
+        }
+
+        if cancel.is_some_and(CancellationToken::is_cancelled) {
+            tracing::warn!(
+                "Cancelled after chunk {}/{}: {} successful, {} failed",
+                chunk_idx + 1,
+                total_chunks,
+                successful_chunks,
+                failed_chunks
+            );
+            break;
+        }
+
+        tokio::time::sleep(inter_chunk_delay).await;
     }
 
     tracing::info!(
@@ -228,24 +712,36 @@ pub async fn fetch_prices_all(
         failed_chunks
     );
 
-    if failed_chunks > 0 {
+    if failed_chunks > 0 && !cancel.is_some_and(CancellationToken::is_cancelled) {
         return Err(anyhow::anyhow!(
             "{} chunks failed to process",
             failed_chunks
         ));
     }
 
-    Ok(())
+    Ok(successful_chunks)
 }
 
+/// Fetch prices for each ticker concurrently, returning which tickers succeeded and why
+/// the others failed. Individual failures don't abort the run.
+///
+/// `progress` is told about each ticker as it finishes, successful or not.
+#[bon::builder]
 pub async fn fetch_intraday_prices(
     db: &Database,
     tickers: &[Ticker],
     interval: Interval,
     concurrency: usize,
+    /// Fetch the full history in replay mode
+    #[builder(default)]
     replay: bool,
+    /// Upsert the tickers before fetching
+    #[builder(default)]
     update_existing: bool,
-) -> anyhow::Result<()> {
+    /// Maximum requests per second, retries included
+    rate_limit: Option<u32>,
+    progress: Option<&dyn FetchProgress>,
+) -> anyhow::Result<FetchReport> {
     if update_existing {
         // Update existing tickers in the database
         db.upsert_tickers(tickers).await?;
@@ -261,19 +757,26 @@ pub async fn fetch_intraday_prices(
     );
 
     let mut processed = 0;
-    let mut successful = 0;
-    let mut failed_tickers = Vec::new();
+    let mut done = 0;
+    let mut report = FetchReport::default();
+
+    let limiter = rate_limit.map(RateLimiter::new);
+    let limiter = limiter.as_ref();
 
     let results = stream::iter(tickers)
         .enumerate()
-        .map(|(idx, ticker)| {
-            let db_clone = db.clone();
-            async move {
-                let result = fetch_prices(db_clone, &ticker, interval, replay).await;
-                (idx, ticker, result)
-            }
+        .map(|(idx, ticker)| async move {
+            let result =
+                fetch_and_store(db, ticker, interval, replay, false, (None, None), limiter).await;
+            (idx, ticker, result)
         })
         .buffer_unordered(concurrency)
+        .inspect(|(_, _, result)| {
+            if let Some(progress) = progress {
+                done += 1;
+                progress.on_ticker_done(done, total_tickers, result.is_ok());
+            }
+        })
         .collect::<Vec<_>>()
         .await;
 
@@ -282,80 +785,443 @@ pub async fn fetch_intraday_prices(
 
         match result {
             Ok(_) => {
-                successful += 1;
+                report.successful.push(ticker.clone());
                 if processed % progress_interval == 0 || processed == total_tickers {
                     tracing::info!(
                         "Progress: {}/{} processed ({:.1}%), {} successful",
                         processed,
                         total_tickers,
                         (processed as f64 / total_tickers as f64) * 100.0,
-                        successful
+                        report.successful.len()
                     );
                 }
             }
             Err(e) => {
-                failed_tickers.push(format!("{}:{} - {}", ticker.symbol, ticker.exchange, e));
                 tracing::warn!(
                     "Failed to fetch prices for {}:{}: {}",
                     ticker.symbol,
                     ticker.exchange,
                     e
                 );
+                report.failed.push((ticker.clone(), e.to_string()));
             }
         }
     }
 
-    let failed_count = failed_tickers.len();
+    let failed_count = report.failed.len();
     tracing::info!(
         "Intraday processing completed: {}/{} successful ({:.1}% success rate)",
-        successful,
+        report.successful.len(),
         total_tickers,
-        (successful as f64 / total_tickers as f64) * 100.0
+        (report.successful.len() as f64 / total_tickers as f64) * 100.0
     );
 
     if failed_count > 0 {
         tracing::warn!("Failed {} tickers:", failed_count);
-        for failure in failed_tickers.iter().take(10) {
+        for (ticker, error) in report.failed.iter().take(10) {
             // Show first 10 failures
-            tracing::warn!("  {}", failure);
+            tracing::warn!("  {}:{} - {}", ticker.symbol, ticker.exchange, error);
         }
         if failed_count > 10 {
             tracing::warn!("  ... and {} more", failed_count - 10);
         }
     }
-    Ok(())
+    Ok(report)
 }
 
+/// Fetch intraday prices for every stored ticker, `INTRADAY_CHUNK_SIZE` tickers at a time.
+///
+/// `progress` is told about each ticker as it finishes, counted across the whole run.
+/// Tickers in `checkpoint` are skipped, and successful tickers are added to it after each
+/// chunk.
 pub async fn fetch_intraday_prices_all(
     db: &Database,
     interval: Interval,
     concurrency: usize,
-) -> anyhow::Result<()> {
-    let tickers = db.get_all_tickers().await?;
-    if tickers.is_empty() {
+    rate_limit: Option<u32>,
+    dry_run: bool,
+    progress: &dyn FetchProgress,
+    mut checkpoint: Option<&mut FetchCheckpoint>,
+) -> anyhow::Result<FetchReport> {
+    if let Some(checkpoint) = checkpoint.as_deref() {
+        checkpoint.ensure_interval(interval)?;
+    }
+    let total_tickers = db.get_ticker_count().await? as usize;
+    if total_tickers == 0 {
         tracing::warn!("No tickers found in the database");
-        return Ok(());
+        return Ok(FetchReport::default());
     }
 
-    fetch_intraday_prices(db, &tickers, interval, concurrency, true, true)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch intraday prices: {}", e);
-            e
-        })?;
+    if dry_run {
+        tracing::info!(
+            "Dry run: would fetch {} prices for {} tickers ({} requests, concurrency {})",
+            interval,
+            total_tickers,
+            total_tickers,
+            concurrency
+        );
+        return Ok(FetchReport::default());
+    }
 
-    Ok(())
+    // Tickers are streamed so only one chunk is held in memory
+    let mut report = FetchReport::default();
+    let mut chunks = std::pin::pin!(db.stream_all_tickers().chunks(INTRADAY_CHUNK_SIZE));
+    let mut skipped = 0;
+    while let Some(chunk) = chunks.next().await {
+        let mut chunk = chunk.into_iter().collect::<anyhow::Result<Vec<_>>>()?;
+        if let Some(checkpoint) = checkpoint.as_deref() {
+            let before = chunk.len();
+            chunk.retain(|ticker| !checkpoint.contains(ticker));
+            for _ in chunk.len()..before {
+                skipped += 1;
+                progress.on_ticker_done(
+                    skipped + report.successful.len() + report.failed.len(),
+                    total_tickers,
+                    true,
+                );
+            }
+            if chunk.is_empty() {
+                continue;
+            }
+        }
+
+        let chunk_progress = ChunkProgress {
+            inner: progress,
+            offset: skipped + report.successful.len() + report.failed.len(),
+            total: total_tickers,
+        };
+        let chunk_report = fetch_intraday_prices()
+            .db(db)
+            .tickers(&chunk)
+            .interval(interval)
+            .concurrency(concurrency)
+            .replay(true)
+            .update_existing(true)
+            .maybe_rate_limit(rate_limit)
+            .progress(&chunk_progress)
+            .call()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch intraday prices: {}", e);
+                e
+            })?;
+        if let Some(checkpoint) = checkpoint.as_deref_mut() {
+            checkpoint.mark_done(&chunk_report.successful)?;
+        }
+        report.successful.extend(chunk_report.successful);
+        report.failed.extend(chunk_report.failed);
+    }
+    Ok(report)
+}
+
+/// Build ML features from every stored candle of a ticker.
+///
+/// Returns an empty set when no candles are stored for the interval.
+pub async fn build_features(
+    db: &Database,
+    ticker: &Ticker,
+    interval: Interval,
+) -> anyhow::Result<Vec<MlFeatures>> {
+    let candles = db
+        .get_prices()
+        .ticker(ticker)
+        .interval(interval)
+        .call()
+        .await?;
+    Ok(build_ml_features(&candles))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::finance::db::Database;
+    use super::{
+        TVConfigMap, build_features, day_range, fetch_intraday_prices, fetch_intraday_prices_all,
+        fetch_prices, fetch_prices_all, fetch_tickers, store_fetched, within_range,
+    };
+    use crate::finance::{
+        checkpoint::FetchCheckpoint,
+        db::Database,
+        models::{Candle, Ticker},
+        progress::FetchProgress,
+    };
+    use crate::test_support::flat_candle;
+    use chrono::{Datelike, NaiveDate, TimeZone, Utc};
+    use tokio_util::sync::CancellationToken;
+    use tradingview::{Interval, MarketSymbol, SymbolInfo};
+
+    #[test]
+    fn test_config_loaders() -> anyhow::Result<()> {
+        let json = r#"{"exchanges": [{"exchange": "HOSE", "country": "VN"}, {"exchange": "OKX"}]}"#;
+        let parsed = TVConfigMap::from_json(json)?;
+        assert_eq!(parsed.exchanges.len(), 2);
+        assert_eq!(parsed.exchanges[1].country, None);
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("exchanges.json");
+        std::fs::write(&path, json)?;
+        let loaded = TVConfigMap::from_path(path.to_str().unwrap());
+        assert_eq!(loaded?.exchanges[0].exchange, "HOSE");
+
+        assert!(TVConfigMap::from_path("does/not/exist.json").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_default_and_override() -> anyhow::Result<()> {
+        let default = TVConfigMap::load(None)?;
+        assert!(default.exchanges.iter().any(|e| e.exchange == "HOSE"));
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("exchanges_override.json");
+        std::fs::write(&path, r#"{"exchanges": [{"exchange": "HNX"}]}"#)?;
+        let overridden = TVConfigMap::load(path.to_str());
+        std::fs::write(&path, r#"{"exchanges": [{"exchange": " "}]}"#)?;
+        let invalid = TVConfigMap::load(path.to_str());
+
+        assert_eq!(overridden?.exchanges[0].exchange, "HNX");
+        assert!(invalid.is_err());
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct CountingProgress {
+        calls: std::sync::Mutex<Vec<(usize, usize, bool)>>,
+    }
+
+    impl FetchProgress for CountingProgress {
+        fn on_ticker_done(&self, done: usize, total: usize, ok: bool) {
+            self.calls.lock().unwrap().push((done, total, ok));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_report_lists_failures() -> anyhow::Result<()> {
+        let db = Database::new("sqlite::memory:").await?;
+        // An empty symbol is rejected before any network request is made
+        let invalid = Ticker::default();
+
+        let progress = CountingProgress::default();
+
+        let report = fetch_intraday_prices()
+            .db(&db)
+            .tickers(&[invalid.clone(), invalid])
+            .interval(Interval::OneHour)
+            .concurrency(1)
+            .progress(&progress)
+            .call()
+            .await?;
+
+        assert!(report.successful.is_empty());
+        assert_eq!(report.failed.len(), 2);
+        assert!(report.failed[0].1.contains("empty"));
+        // Failed tickers still count as finished
+        assert_eq!(
+            *progress.calls.lock().unwrap(),
+            vec![(1, 2, false), (2, 2, false)]
+        );
+        Ok(())
+    }
+
+    /// Cancels its token as soon as the first chunk reports back.
+    struct CancelAfterFirstChunk(CancellationToken);
+
+    impl FetchProgress for CancelAfterFirstChunk {
+        fn on_ticker_done(&self, _done: usize, _total: usize, _ok: bool) {
+            self.0.cancel();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_prices_all_stops_when_cancelled() -> anyhow::Result<()> {
+        let db = Database::new("sqlite::memory:").await?;
+        // Empty symbols fail their chunk before any network request is made
+        let tickers = ["HOSE", "HNX", "UPCOM"].map(|exchange| Ticker::new("", exchange));
+        db.upsert_tickers(&tickers).await?;
+        let cancel = CancellationToken::new();
+
+        let completed = fetch_prices_all()
+            .db(db)
+            .interval(Interval::OneDay)
+            .chunk_size(1)
+            .max_retries(0)
+            .progress(&CancelAfterFirstChunk(cancel.clone()))
+            .cancel(&cancel)
+            .call()
+            .await?;
+
+        assert!(cancel.is_cancelled());
+        // Only the first of the three chunks ran, and its failure doesn't hide the cancel
+        assert_eq!(completed, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_checkpointed_tickers_are_skipped() -> anyhow::Result<()> {
+        let db = Database::new("sqlite::memory:").await?;
+        // Both tickers would fail if fetched, since their symbols are empty
+        let tickers = ["HOSE", "HNX"].map(|exchange| Ticker::new("", exchange));
+        db.upsert_tickers(&tickers).await?;
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("fetch_checkpoint.json");
+        FetchCheckpoint::new(&path, Interval::OneDay).mark_done(&tickers)?;
+
+        let mut checkpoint = FetchCheckpoint::load(&path, Interval::OneDay)?;
+        let daily = fetch_prices_all()
+            .db(db.clone())
+            .interval(Interval::OneDay)
+            .max_retries(0)
+            .checkpoint(&mut checkpoint)
+            .call()
+            .await;
+        // The daily checkpoint is refused for an hourly run
+        let mismatched = fetch_intraday_prices_all(
+            &db,
+            Interval::OneHour,
+            1,
+            None,
+            false,
+            &(),
+            Some(&mut checkpoint),
+        )
+        .await;
+
+        FetchCheckpoint::new(&path, Interval::OneHour).mark_done(&tickers)?;
+        let mut checkpoint = FetchCheckpoint::load(&path, Interval::OneHour)?;
+        let intraday = fetch_intraday_prices_all(
+            &db,
+            Interval::OneHour,
+            1,
+            None,
+            false,
+            &(),
+            Some(&mut checkpoint),
+        )
+        .await;
+
+        assert_eq!(daily?, 0);
+        assert!(mismatched.is_err());
+        let intraday = intraday?;
+        assert!(intraday.successful.is_empty() && intraday.failed.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_day_range_drops_bars_outside() -> anyhow::Result<()> {
+        let since = "2024-01-02".parse::<NaiveDate>()?;
+        let until = "2024-01-03".parse::<NaiveDate>()?;
+        let (start, end) = day_range(Some(since), Some(until))?;
+
+        assert_eq!(
+            start,
+            Some(Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            end,
+            Some(Utc.with_ymd_and_hms(2024, 1, 4, 0, 0, 0).unwrap())
+        );
+        assert!(day_range(Some(until), Some(since)).is_err());
+        assert!("2024-13-01".parse::<NaiveDate>().is_err());
+
+        let bars = (1..=5)
+            .map(|day| Candle {
+                timestamp: Utc.with_ymd_and_hms(2024, 1, day, 2, 0, 0).unwrap(),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let kept = within_range(&bars, start, end)
+            .iter()
+            .map(|c| c.timestamp.day())
+            .collect::<Vec<_>>();
+        assert_eq!(kept, [2, 3]);
+        assert_eq!(within_range(&bars, None, end).len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_leaves_database_unchanged() -> anyhow::Result<()> {
+        let db = Database::new("sqlite::memory:").await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        db.upsert_tickers(std::slice::from_ref(&ticker)).await?;
+
+        fetch_tickers(db.clone(), None, None, true).await?;
+        fetch_prices_all()
+            .db(db.clone())
+            .interval(Interval::OneDay)
+            .dry_run(true)
+            .call()
+            .await?;
+        let report =
+            fetch_intraday_prices_all(&db, Interval::OneHour, 5, None, true, &(), None).await?;
+
+        assert!(report.successful.is_empty() && report.failed.is_empty());
+        assert_eq!(db.get_ticker_count().await?, 1);
+        let last = db
+            .get_last_candle_timestamp("VCB", "HOSE", Interval::OneDay)
+            .await?;
+        assert!(last.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_prices_rejects_empty_symbol() -> anyhow::Result<()> {
+        let db = Database::new("sqlite::memory:").await?;
+        let error = fetch_prices(
+            db.clone(),
+            &Ticker::new("", "HOSE"),
+            Interval::OneDay,
+            false,
+            false,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(error.to_string(), "Invalid ticker ':HOSE': symbol is empty");
+        assert_eq!(db.get_ticker_count().await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_build_features_end_to_end() -> anyhow::Result<()> {
+        let db = Database::new("sqlite::memory:").await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        db.upsert_tickers(std::slice::from_ref(&ticker)).await?;
+        let start = chrono::Utc::now() - chrono::Duration::days(40);
+        let candles = (0..30)
+            .map(|i| {
+                let close = 100.0 + (i % 5) as f64;
+                Candle {
+                    high: close + 1.0,
+                    low: close - 1.0,
+                    ..flat_candle(start + chrono::Duration::days(i), close)
+                }
+            })
+            .collect::<Vec<_>>();
+        db.upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+
+        let features = build_features(&db, &ticker, Interval::OneDay).await?;
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("build_features.parquet");
+        let path = path.to_str().unwrap();
+        crate::utils::format::save_ml_features_parquet(features.clone(), path)?;
+        let loaded = crate::utils::format::load_ml_features_parquet(path)?;
+
+        assert_eq!(features.len(), 30);
+        assert!(features.last().unwrap().rsi.is_some());
+        assert_eq!(loaded.len(), features.len());
+        assert!(
+            build_features(&db, &Ticker::new("FPT", "HOSE"), Interval::OneDay)
+                .await?
+                .is_empty()
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test() -> anyhow::Result<()> {
         let url = std::env::var("DATABASE_URL").unwrap_or("sqlite::memory:".to_string());
         let db = Database::new(&url).await?;
         let res = db
-            .search_tickers_by_field("market_type", "forex", None)
+            .search_tickers_by_field(&["market_type"], "forex", None, None)
             .await?;
         println!("Found {} tickers", res.len());
         for ticker in res {
@@ -367,4 +1233,112 @@ mod tests {
 
         Ok(())
     }
+
+    /// Chart symbol info for `ticker`, built through serde so only the fields under test
+    /// need setting.
+    fn symbol_info(ticker: &Ticker, industry: &str, sector: &str) -> anyhow::Result<SymbolInfo> {
+        let mut json = serde_json::to_value(SymbolInfo::default())?;
+        json["name"] = ticker.symbol.clone().into();
+        json["exchange"] = ticker.exchange.clone().into();
+        json["industry"] = industry.into();
+        json["sector"] = sector.into();
+        Ok(serde_json::from_value(json)?)
+    }
+
+    #[tokio::test]
+    async fn test_store_fetched_refreshes_metadata_only_when_asked() -> anyhow::Result<()> {
+        let db = Database::new("sqlite::memory:").await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        db.upsert_tickers(std::slice::from_ref(&ticker)).await?;
+        let info = symbol_info(&ticker, "Banks", "Financials")?;
+        let bars = [Candle {
+            open: 90.0,
+            high: 92.0,
+            low: 89.0,
+            ..flat_candle(Utc.with_ymd_and_hms(2024, 1, 2, 2, 0, 0).unwrap(), 91.0)
+        }];
+
+        let report = store_fetched(
+            &db,
+            &ticker,
+            Interval::OneDay,
+            &info,
+            &bars,
+            false,
+            (None, None),
+        )
+        .await?;
+        assert_eq!(report.inserted, 1);
+        let stored = db.get_ticker("VCB", "HOSE").await?.unwrap();
+        assert_eq!(stored.industry, None);
+        assert_eq!(stored.sector, None);
+
+        store_fetched(
+            &db,
+            &ticker,
+            Interval::OneDay,
+            &info,
+            &bars,
+            true,
+            (None, None),
+        )
+        .await?;
+        let stored = db.get_ticker("VCB", "HOSE").await?.unwrap();
+        assert_eq!(stored.industry.as_deref(), Some("Banks"));
+        assert_eq!(stored.sector.as_deref(), Some("Financials"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_incremental_store_refreshes_the_last_bar() -> anyhow::Result<()> {
+        let db = Database::new("sqlite::memory:").await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        db.upsert_tickers(std::slice::from_ref(&ticker)).await?;
+        let info = symbol_info(&ticker, "Banks", "Financials")?;
+        let bar = |day, close| Candle {
+            high: close + 1.0,
+            low: close - 1.0,
+            ..flat_candle(Utc.with_ymd_and_hms(2024, 1, day, 2, 0, 0).unwrap(), close)
+        };
+        let stored = [bar(2, 90.0), bar(3, 91.0)];
+        store_fetched(
+            &db,
+            &ticker,
+            Interval::OneDay,
+            &info,
+            &stored,
+            false,
+            (None, None),
+        )
+        .await?;
+
+        // The 3rd was stored mid-session and has closed higher since
+        let last = db
+            .get_last_candle_timestamp("VCB", "HOSE", Interval::OneDay)
+            .await?;
+        let fetched = [bar(2, 80.0), bar(3, 95.0), bar(4, 96.0)];
+        let report = store_fetched(
+            &db,
+            &ticker,
+            Interval::OneDay,
+            &info,
+            &fetched,
+            false,
+            (last, None),
+        )
+        .await?;
+        assert_eq!(report.inserted, 2);
+
+        let closes = db
+            .get_prices()
+            .ticker(&ticker)
+            .interval(Interval::OneDay)
+            .call()
+            .await?
+            .iter()
+            .map(|c| c.close)
+            .collect::<Vec<_>>();
+        assert_eq!(closes, [90.0, 95.0, 96.0]);
+        Ok(())
+    }
 }
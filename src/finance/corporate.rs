@@ -0,0 +1,122 @@
+use crate::finance::models::Candle;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Kind of corporate action affecting historical prices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum CorporateActionKind {
+    /// Share split or stock dividend; `ratio` is new shares per old share (2.0 for 2:1)
+    Split,
+    /// Cash dividend; `ratio` is the amount paid per share, in the quote currency
+    Dividend,
+}
+
+impl CorporateActionKind {
+    /// Name stored in the `kind` column of `CORPORATE_ACTIONS`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CorporateActionKind::Split => "split",
+            CorporateActionKind::Dividend => "dividend",
+        }
+    }
+}
+
+impl std::str::FromStr for CorporateActionKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "split" => Ok(CorporateActionKind::Split),
+            "dividend" => Ok(CorporateActionKind::Dividend),
+            _ => Err(anyhow::anyhow!("Unknown corporate action kind '{}'", s)),
+        }
+    }
+}
+
+/// A split or dividend taking effect on its ex-date.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CorporateAction {
+    /// Ex-date: the first trading day on the new basis
+    pub date: NaiveDate,
+    pub ratio: f64,
+    pub kind: CorporateActionKind,
+}
+
+impl CorporateAction {
+    /// Multiplier applied to prices before the ex-date, or `None` if the action can't be
+    /// applied (non-positive ratio, or a dividend with no bar before its ex-date).
+    fn price_factor(&self, candles: &[Candle]) -> Option<f64> {
+        if self.ratio <= 0.0 {
+            return None;
+        }
+
+        match self.kind {
+            CorporateActionKind::Split => Some(1.0 / self.ratio),
+            CorporateActionKind::Dividend => {
+                let prev_close = candles
+                    .iter()
+                    .filter(|c| c.timestamp.date_naive() < self.date)
+                    .max_by_key(|c| c.timestamp)?
+                    .close;
+                (prev_close > self.ratio).then(|| 1.0 - self.ratio / prev_close)
+            }
+        }
+    }
+}
+
+/// Fill `adj_close` on every candle from its raw close and `events`.
+///
+/// Each action is back-propagated to all bars dated before its ex-date, so a 2:1 split
+/// halves every earlier adjusted close while later bars keep `adj_close == close`. Factors
+/// of multiple actions compound. Raw OHLC values are left untouched and `candles` may be
+/// in any order.
+pub fn adjust_for_splits(candles: &mut [Candle], events: &[CorporateAction]) {
+    let factors = events
+        .iter()
+        .filter_map(|event| Some((event.date, event.price_factor(candles)?)))
+        .collect::<Vec<_>>();
+
+    for candle in candles.iter_mut() {
+        let date = candle.timestamp.date_naive();
+        let factor = factors
+            .iter()
+            .filter(|(ex_date, _)| date < *ex_date)
+            .map(|(_, factor)| factor)
+            .product::<f64>();
+        candle.adj_close = Some(candle.close * factor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn test_two_for_one_split_halves_earlier_closes() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 2, 0, 0).unwrap();
+        let mut candles = (0..6)
+            .map(|i| Candle {
+                timestamp: start + Duration::days(i),
+                close: if i < 3 { 100.0 } else { 50.0 },
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let split = CorporateAction {
+            date: NaiveDate::from_ymd_opt(2024, 1, 4).unwrap(),
+            ratio: 2.0,
+            kind: CorporateActionKind::Split,
+        };
+
+        adjust_for_splits(&mut candles, &[split]);
+
+        for candle in &candles[..3] {
+            assert_eq!(candle.close, 100.0);
+            assert_eq!(candle.adj_close, Some(50.0));
+        }
+        for candle in &candles[3..] {
+            assert_eq!(candle.adj_close, Some(candle.close));
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::finance::{db::Database, models::Ticker};
+use crate::utils::format::{candle_schema, candles_to_batch};
+use ::datafusion::{datasource::MemTable, prelude::SessionContext};
+use std::sync::Arc;
+use tradingview::Interval;
+
+/// Name of the table registered by [`register_candles`].
+pub const CANDLES_TABLE: &str = "candles";
+
+/// Load the stored candles of `ticker` and register them in `ctx` as the `candles` table.
+///
+/// Columns follow [`candle_schema`]. Registering again replaces the previous table, so one
+/// context can be pointed at different tickers in turn.
+pub async fn register_candles(
+    ctx: &SessionContext,
+    db: &Database,
+    ticker: &Ticker,
+    interval: Interval,
+) -> anyhow::Result<()> {
+    let candles = db
+        .get_prices()
+        .ticker(ticker)
+        .interval(interval)
+        .call()
+        .await?;
+
+    let table = MemTable::try_new(candle_schema(), vec![vec![candles_to_batch(&candles)?]])?;
+    ctx.deregister_table(CANDLES_TABLE)?;
+    ctx.register_table(CANDLES_TABLE, Arc::new(table))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::daily_candles;
+    use arrow::array::Float64Array;
+    use tradingview::MarketSymbol;
+
+    #[tokio::test]
+    async fn test_average_close_query() -> anyhow::Result<()> {
+        let db = Database::new("sqlite::memory:").await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        db.upsert_tickers(std::slice::from_ref(&ticker)).await?;
+
+        let candles = daily_candles(4);
+        db.upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+
+        let ctx = SessionContext::new();
+        register_candles(&ctx, &db, &ticker, Interval::OneDay).await?;
+        let batches = ctx
+            .sql("SELECT AVG(close) FROM candles")
+            .await?
+            .collect()
+            .await?;
+
+        let avg = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap()
+            .value(0);
+        assert_eq!(avg, 11.5);
+        Ok(())
+    }
+}
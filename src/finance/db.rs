@@ -1,23 +1,251 @@
+use crate::finance::calendar::calendar_for_exchange;
+use crate::finance::corporate::CorporateAction;
 use crate::finance::models::*;
+use crate::finance::quality::{QualityReport, find_gaps_in};
+use crate::utils::format::{candle_schema, load_tickers_csv, load_tickers_parquet};
 use anyhow::Result;
-use chrono::{DateTime, Utc};
-use sqlx::SqlitePool;
+use arrow::array::{ArrayRef, Float64Builder, RecordBatch, TimestampMillisecondBuilder};
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::{Stream, TryStreamExt};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{FromRow, Row, SqlitePool};
+use std::str::FromStr;
+use std::sync::Arc;
 use tradingview::{Interval, MarketSymbol, OHLCV, SymbolInfo};
 
+/// What `upsert_prices` does when candle timestamps don't fit the declared interval.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpacingCheck {
+    /// Skip the check entirely
+    Off,
+    /// Log a warning and store the candles anyway
+    #[default]
+    Warn,
+    /// Refuse to store the candles
+    Error,
+}
+
+/// How `upsert_prices` treats candles that are already stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InsertMode {
+    /// Overwrite the prices of existing bars with the incoming values
+    /// (`ON CONFLICT DO UPDATE`), keeping their `adj_close`
+    #[default]
+    Replace,
+    /// Append-only: keep existing bars untouched and only add new ones (`ON CONFLICT DO NOTHING`)
+    Ignore,
+}
+
+/// Options controlling how `upsert_prices_with` stores candles.
+#[derive(Debug, Clone, Default)]
+pub struct UpsertOptions {
+    /// Median spacing of the candles against the interval length
+    pub spacing_check: SpacingCheck,
+    /// Every intraday candle starting on an interval boundary, e.g. no 1h bar at 10:37
+    pub alignment_check: SpacingCheck,
+    pub mode: InsertMode,
+    /// Which bars count as invalid and are rejected
+    pub validation: ValidationPolicy,
+}
+
+impl UpsertOptions {
+    /// Options that refuse to store candles failing the spacing or alignment checks.
+    pub fn strict() -> Self {
+        Self {
+            spacing_check: SpacingCheck::Error,
+            alignment_check: SpacingCheck::Error,
+            ..Default::default()
+        }
+    }
+}
+
+/// Outcome of an `upsert_prices` call.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UpsertReport {
+    /// Rows written (for `InsertMode::Ignore`, only the new ones)
+    pub inserted: u64,
+    /// Bars that failed validation, as (epoch milliseconds, reason), in input order
+    pub rejected: Vec<(i64, OhlcvError)>,
+}
+
+impl UpsertReport {
+    /// Number of rejected bars for each kind of problem.
+    pub fn rejected_by_kind(&self) -> std::collections::HashMap<OhlcvError, usize> {
+        let mut counts = std::collections::HashMap::new();
+        for (_, error) in &self.rejected {
+            *counts.entry(*error).or_default() += 1;
+        }
+        counts
+    }
+}
+
+/// Position in a paginated price query: the timestamp of the last candle returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor(pub DateTime<Utc>);
+
+/// Turn free text into an FTS5 `MATCH` expression that can't fail to parse.
+///
+/// Every whitespace-separated term is wrapped in double quotes, with embedded quotes
+/// doubled, so FTS5 reads it as a plain string: operators (`AND`, `OR`, `NOT`, `NEAR`),
+/// column filters (`:`), `*`, `^`, `+`, `-` and parentheses lose their special meaning.
+/// Characters FTS5 treats as separators are then dropped by its tokenizer as usual, and
+/// terms made only of such characters are skipped. Terms are implicitly ANDed.
+///
+/// With `prefix`, the last term also matches any token it starts, so `VC` finds `VCB`.
+/// Misspelled terms still don't match, as FTS5 has no fuzzy matching. Returns `None` when
+/// the query has no terms.
+pub fn sanitize_fts_query(query: &str, prefix: bool) -> Option<String> {
+    let terms = query
+        .split_whitespace()
+        // Terms without letters or digits tokenize to nothing and would leave an empty phrase
+        .filter(|term| term.chars().any(char::is_alphanumeric))
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+    if terms.is_empty() {
+        return None;
+    }
+
+    let mut expression = terms.join(" ");
+    if prefix {
+        expression.push('*');
+    }
+    Some(expression)
+}
+
+/// SQL string literal for `value`, with embedded single quotes doubled.
+fn sql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// [`sql_string`] for an optional value, `NULL` when absent.
+fn sql_opt_string(value: Option<&str>) -> String {
+    value.map_or_else(|| "NULL".to_string(), sql_string)
+}
+
+/// bm25 weight of each `tickers_fts` column, in column order, for weighted search. Columns
+/// with weight 0 don't contribute to the score.
+const SEARCH_COLUMN_WEIGHTS: [(&str, f64); 8] = [
+    ("symbol", 10.0),
+    ("exchange", 0.0),
+    ("description", 2.0),
+    ("currency", 0.0),
+    ("country", 0.0),
+    ("market_type", 0.0),
+    ("industry", 1.0),
+    ("sector", 1.0),
+];
+
+/// Tables `health_check` expects the migrations to have created.
+const REQUIRED_TABLES: [&str; 6] = [
+    "TICKERS",
+    "OHLCV",
+    "TECHNICAL_INDICATORS",
+    "CORPORATE_ACTIONS",
+    "ML_FEATURES",
+    "tickers_fts",
+];
+
+/// Number of tickers read per query by `stream_all_tickers`.
+const TICKER_PAGE_SIZE: i64 = 1000;
+
+/// SQLite-backed store. Clones share the pool and the write queue.
+///
+/// Reads run concurrently on the pool. Writes are queued on a FIFO lock so only one write
+/// transaction is open at a time: SQLite allows a single writer, and concurrent transactions
+/// that upgrade from read to write fail with `database is locked` instead of waiting.
+///
+/// Several queries only work on SQLite, which is why Postgres has its own, smaller
+/// `PgDatabase` behind the `postgres` feature:
+/// - ticker search uses the `tickers_fts` fts5 table, `MATCH` and `bm25()`
+/// - `health_check` lists tables from `sqlite_master`
+/// - the migrations use `WITHOUT ROWID` tables and triggers written for SQLite
+/// - the `query!` / `query_as!` macros are checked against the SQLite schema and use `?`
+///   placeholders
 #[derive(Debug, Clone)]
 pub struct Database {
     pool: SqlitePool,
+    writer: Arc<tokio::sync::Mutex<()>>,
 }
 
 #[bon::bon]
 impl Database {
+    /// Connect with sqlx's default pool settings and run migrations.
     pub async fn new(database_url: &str) -> Result<Self> {
-        let pool = SqlitePool::connect(database_url).await?;
+        Self::builder().url(database_url).connect().await
+    }
+
+    /// Connect with explicit pool settings and, unless `migrate` is false, run migrations.
+    ///
+    /// Under concurrent writers, enable `wal` and set a `busy_timeout` so that writes wait
+    /// for the lock instead of failing with `database is locked`.
+    #[builder(start_fn = builder, finish_fn = connect)]
+    pub async fn connect_with(
+        url: &str,
+        /// Maximum number of pooled connections (sqlx defaults to 10)
+        max_connections: Option<u32>,
+        /// How long a connection waits on a locked database (sqlx defaults to 5s)
+        busy_timeout: Option<std::time::Duration>,
+        /// Use write-ahead logging so readers don't block the writer
+        #[builder(default)]
+        wal: bool,
+        /// Run the migrations after connecting. Disable it to inspect a database without
+        /// writing to it, e.g. for `health_check`
+        #[builder(default = true)]
+        migrate: bool,
+    ) -> Result<Self> {
+        let mut connect_options = SqliteConnectOptions::from_str(url)?;
+        if let Some(timeout) = busy_timeout {
+            connect_options = connect_options.busy_timeout(timeout);
+        }
+        if wal {
+            connect_options = connect_options.journal_mode(SqliteJournalMode::Wal);
+        }
+
+        let mut pool_options = SqlitePoolOptions::new();
+        if let Some(max) = max_connections {
+            pool_options = pool_options.max_connections(max);
+        }
+        let pool = pool_options.connect_with(connect_options).await?;
+
+        if migrate {
+            sqlx::migrate!("./migrations").run(&pool).await?;
+        }
+
+        Ok(Self {
+            pool,
+            writer: Arc::default(),
+        })
+    }
 
-        // Run migrations
-        sqlx::migrate!("./migrations").run(&pool).await?;
+    /// Wait for this database's turn to write. Hold the guard until the write commits.
+    async fn write_lock(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.writer.lock().await
+    }
 
-        Ok(Self { pool })
+    /// Run `f` in one write transaction, committing when it returns `Ok` and rolling back
+    /// when it returns `Err`, so several writes can be made atomic together.
+    ///
+    /// The write queue is held while `f` runs, so write through the given transaction, e.g.
+    /// with [`Self::upsert_tickers_in`] and [`Self::upsert_prices_in`], rather than calling
+    /// the other writing methods of this `Database`, which would wait forever.
+    pub async fn with_transaction<F, T>(&self, f: F) -> Result<T>
+    where
+        F: for<'c> FnOnce(
+            &'c mut sqlx::Transaction<'static, sqlx::Sqlite>,
+        ) -> futures::future::BoxFuture<'c, Result<T>>,
+    {
+        let _writer = self.write_lock().await;
+        let mut tx = self.pool.begin().await?;
+        match f(&mut tx).await {
+            Ok(value) => {
+                tx.commit().await?;
+                Ok(value)
+            }
+            Err(e) => {
+                tx.rollback().await?;
+                Err(e)
+            }
+        }
     }
 
     pub async fn get_pool(&self) -> &SqlitePool {
@@ -29,23 +257,189 @@ impl Database {
         Ok(())
     }
 
+    /// Run raw SQL, queued behind the other writes like every writing method.
     pub async fn execute(&self, query: &str) -> Result<()> {
+        let _writer = self.write_lock().await;
         sqlx::query(query).execute(&self.pool).await?;
         Ok(())
     }
 
+    /// Rebuild the database file to reclaim space left by replaced and deleted rows.
+    ///
+    /// `VACUUM` can't run inside a transaction, so it runs on a dedicated connection while
+    /// holding the write queue.
+    pub async fn vacuum(&self) -> Result<()> {
+        let _writer = self.write_lock().await;
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("VACUUM").execute(&mut *conn).await?;
+        Ok(())
+    }
+
+    /// Refresh the query planner statistics.
+    pub async fn analyze(&self) -> Result<()> {
+        let _writer = self.write_lock().await;
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("ANALYZE").execute(&mut *conn).await?;
+        Ok(())
+    }
+
+    /// Write every ticker and candle as `INSERT` statements to a `.sql` file, returning the
+    /// number of rows written.
+    ///
+    /// The dump is plain text, so unlike the database file it can be replayed with
+    /// [`Self::execute`] on a freshly migrated database of any SQLite version. Tickers come
+    /// first so the OHLCV foreign keys resolve. Floats are written with enough digits to
+    /// round-trip exactly and timestamps as stored.
+    pub async fn export_sql_dump(&self, path: &str) -> Result<u64> {
+        use std::io::Write;
+
+        let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut rows = 0u64;
+
+        let mut tickers = std::pin::pin!(self.stream_all_tickers());
+        while let Some(ticker) = tickers.try_next().await? {
+            writeln!(
+                out,
+                "INSERT INTO TICKERS (symbol, exchange, description, currency, country, market_type, industry, sector, founded) VALUES ({}, {}, {}, {}, {}, {}, {}, {}, {});",
+                sql_string(&ticker.symbol),
+                sql_string(&ticker.exchange),
+                sql_opt_string(ticker.description.as_deref()),
+                sql_opt_string(ticker.currency.as_deref()),
+                sql_opt_string(ticker.country.as_deref()),
+                sql_opt_string(ticker.market_type.as_deref()),
+                sql_opt_string(ticker.industry.as_deref()),
+                sql_opt_string(ticker.sector.as_deref()),
+                ticker
+                    .founded
+                    .map_or_else(|| "NULL".to_string(), |year| year.to_string()),
+            )?;
+            rows += 1;
+        }
+
+        let mut prices = sqlx::query(
+            "SELECT symbol, exchange, interval, timestamp, open, high, low, close, volume, adj_close \
+             FROM OHLCV ORDER BY symbol, exchange, interval, timestamp",
+        )
+        .fetch(&self.pool);
+        while let Some(row) = prices.try_next().await? {
+            writeln!(
+                out,
+                "INSERT INTO OHLCV (symbol, exchange, interval, timestamp, open, high, low, close, volume, adj_close) VALUES ({}, {}, {}, {}, {:?}, {:?}, {:?}, {:?}, {:?}, {});",
+                sql_string(row.try_get("symbol")?),
+                sql_string(row.try_get("exchange")?),
+                sql_string(row.try_get("interval")?),
+                sql_string(row.try_get("timestamp")?),
+                row.try_get::<f64, _>("open")?,
+                row.try_get::<f64, _>("high")?,
+                row.try_get::<f64, _>("low")?,
+                row.try_get::<f64, _>("close")?,
+                row.try_get::<f64, _>("volume")?,
+                row.try_get::<Option<f64>, _>("adj_close")?
+                    .map_or_else(|| "NULL".to_string(), |value| format!("{value:?}")),
+            )?;
+            rows += 1;
+        }
+
+        out.flush()?;
+        Ok(rows)
+    }
+
+    /// Check that the database answers queries and has every required table.
+    ///
+    /// Fails when `SELECT 1` fails. Missing tables are reported in the status rather than
+    /// as an error, so a readiness probe can tell an unmigrated database from a dead one.
+    /// Connect with `migrate(false)` for that, since migrating creates the missing tables.
+    pub async fn health_check(&self) -> Result<HealthStatus> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+
+        let tables: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table'")
+                .fetch_all(&self.pool)
+                .await?;
+        let missing_tables = REQUIRED_TABLES
+            .iter()
+            .filter(|required| !tables.iter().any(|t| t.eq_ignore_ascii_case(required)))
+            .map(|required| required.to_string())
+            .collect();
+
+        Ok(HealthStatus {
+            pool_size: self.pool.size(),
+            idle_connections: self.pool.num_idle(),
+            missing_tables,
+        })
+    }
+
+    /// Version of the latest migration applied to this database, e.g. `20250704090000`.
+    ///
+    /// Exports record it so files can be matched to the schema they were written from.
+    /// Returns 0 when no migration has been applied, including on a database connected with
+    /// `migrate(false)` that has no `_sqlx_migrations` table.
+    pub async fn schema_version(&self) -> Result<i64> {
+        let tracked: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if !tracked {
+            return Ok(0);
+        }
+
+        let version: Option<i64> =
+            sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1")
+                .fetch_one(&self.pool)
+                .await?;
+        Ok(version.unwrap_or(0))
+    }
+
+    /// Look up a ticker by symbol alone.
+    ///
+    /// When the symbol is listed on several exchanges the alphabetically first exchange
+    /// wins; use [`Self::get_ticker_by_symbol_preferring`] to choose the venue.
     pub async fn get_ticker_by_symbol(&self, symbol: &str) -> Result<Option<Ticker>> {
-        let row = sqlx::query_as!(
-            Ticker,
-            "SELECT symbol, exchange, description, currency, country, market_type, industry, sector, founded FROM TICKERS WHERE symbol = ?",
-            symbol
+        Ok(self.get_tickers_by_symbol(symbol).await?.into_iter().next())
+    }
+
+    /// Like [`Self::get_ticker_by_symbol`], but ignores ASCII case, so `vcb` finds `VCB`.
+    pub async fn get_ticker_by_symbol_ci(&self, symbol: &str) -> Result<Option<Ticker>> {
+        let row = sqlx::query_as::<_, Ticker>(
+            "SELECT symbol, exchange, description, currency, country, market_type, industry, sector, founded FROM TICKERS WHERE symbol = ? COLLATE NOCASE ORDER BY exchange LIMIT 1",
         )
+        .bind(symbol)
         .fetch_optional(&self.pool)
         .await?;
 
         Ok(row)
     }
 
+    /// All listings of `symbol`, ordered by exchange.
+    pub async fn get_tickers_by_symbol(&self, symbol: &str) -> Result<Vec<Ticker>> {
+        let rows = sqlx::query_as!(
+            Ticker,
+            "SELECT symbol, exchange, description, currency, country, market_type, industry, sector, founded FROM TICKERS WHERE symbol = ? ORDER BY exchange",
+            symbol
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Look up a ticker by symbol, picking the listing whose exchange comes first in
+    /// `preferred_exchanges`. Falls back to the alphabetically first exchange.
+    pub async fn get_ticker_by_symbol_preferring(
+        &self,
+        symbol: &str,
+        preferred_exchanges: &[&str],
+    ) -> Result<Option<Ticker>> {
+        let tickers = self.get_tickers_by_symbol(symbol).await?;
+        let preferred = preferred_exchanges
+            .iter()
+            .find_map(|exchange| tickers.iter().find(|t| t.exchange == *exchange))
+            .cloned();
+
+        Ok(preferred.or_else(|| tickers.into_iter().next()))
+    }
+
     pub async fn get_ticker(&self, symbol: &str, exchange: &str) -> Result<Option<Ticker>> {
         let row = sqlx::query_as!(
             Ticker,
@@ -59,6 +453,35 @@ impl Database {
         Ok(row)
     }
 
+    /// Look up many `(symbol, exchange)` pairs at once, ordered by symbol and exchange.
+    ///
+    /// Pairs are matched with a single `IN (VALUES ...)` query per 1000 pairs instead of one
+    /// query each. Unknown pairs are skipped and duplicates return the ticker once.
+    pub async fn get_tickers_by_symbols(&self, pairs: &[(String, String)]) -> Result<Vec<Ticker>> {
+        const BATCH_SIZE: usize = 1000;
+        let mut tickers = Vec::new();
+
+        for chunk in pairs.chunks(BATCH_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                "SELECT symbol, exchange, description, currency, country, market_type, industry, sector, founded FROM TICKERS WHERE (symbol, exchange) IN (",
+            );
+            query_builder.push_values(chunk, |mut b, (symbol, exchange)| {
+                b.push_bind(symbol).push_bind(exchange);
+            });
+            query_builder.push(")");
+
+            let rows = query_builder
+                .build_query_as::<Ticker>()
+                .fetch_all(&self.pool)
+                .await?;
+            tickers.extend(rows);
+        }
+
+        tickers.sort_by(|a, b| (&a.symbol, &a.exchange).cmp(&(&b.symbol, &b.exchange)));
+        tickers.dedup_by(|a, b| a.symbol == b.symbol && a.exchange == b.exchange);
+        Ok(tickers)
+    }
+
     pub async fn get_all_tickers(&self) -> Result<Vec<Ticker>> {
         let rows = sqlx::query!(
             "SELECT symbol, exchange, description, currency, country, market_type, industry, sector, founded FROM tickers ORDER BY symbol"
@@ -84,6 +507,42 @@ impl Database {
         Ok(tickers)
     }
 
+    /// Stream every ticker ordered by symbol and exchange without loading them all at once.
+    ///
+    /// Tickers are read in pages of `TICKER_PAGE_SIZE` using keyset pagination rather than
+    /// one long-lived cursor: an open cursor holds SQLite's read lock, which would block the
+    /// writes callers typically make between items.
+    pub fn stream_all_tickers(&self) -> impl Stream<Item = Result<Ticker>> + '_ {
+        futures::stream::try_unfold(
+            Some((String::new(), String::new())),
+            move |after| async move {
+                let Some((symbol, exchange)) = after else {
+                    return Ok::<_, anyhow::Error>(None);
+                };
+
+                let page = sqlx::query_as::<_, Ticker>(
+                    "SELECT symbol, exchange, description, currency, country, market_type, industry, sector, founded \
+                     FROM TICKERS WHERE (symbol, exchange) > (?, ?) ORDER BY symbol, exchange LIMIT ?",
+                )
+                .bind(symbol)
+                .bind(exchange)
+                .bind(TICKER_PAGE_SIZE)
+                .fetch_all(&self.pool)
+                .await?;
+
+                let next = match page.last() {
+                    Some(last) if page.len() as i64 == TICKER_PAGE_SIZE => {
+                        Some((last.symbol.clone(), last.exchange.clone()))
+                    }
+                    _ => None,
+                };
+                let page = futures::stream::iter(page.into_iter().map(Ok));
+                Ok(Some((page, next)))
+            },
+        )
+        .try_flatten()
+    }
+
     pub async fn get_tickers_by_exchange(&self, exchange: &str) -> Result<Vec<Ticker>> {
         let tickers = sqlx::query_as!(
             Ticker,
@@ -110,6 +569,7 @@ impl Database {
 
     // Improved INSERT with upsert capability
     pub async fn upsert_ticker(&self, ticker: &SymbolInfo) -> Result<()> {
+        let _writer = self.write_lock().await;
         let mut tx = self.pool.begin().await?;
         let result = sqlx::query!(
             "INSERT INTO TICKERS (symbol, exchange, description, currency, country, market_type, industry, sector, founded) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?) ON CONFLICT(symbol, exchange) DO UPDATE SET description = excluded.description, currency = excluded.currency, country = excluded.country, market_type = excluded.market_type, industry = excluded.industry, sector = excluded.sector, founded = excluded.founded",
@@ -117,11 +577,12 @@ impl Database {
             ticker.exchange,
             ticker.description,
             ticker.currency_code,
-            None::<String>,
+            // SymbolInfo carries no country, so derive it from the exchange
+            exchange_to_country(&ticker.exchange),
             ticker.market_type,
             ticker.industry,
             ticker.sector,
-            ticker.founded
+            normalize_founded(ticker.founded.into())
         )
         .execute(&mut *tx)
         .await?;
@@ -146,9 +607,26 @@ impl Database {
         const BATCH_SIZE: usize = 1000;
         let mut total_affected = 0u64;
 
+        let _writer = self.write_lock().await;
         for chunk in tickers.chunks(BATCH_SIZE) {
             let mut tx = self.pool.begin().await?;
+            total_affected += Self::upsert_tickers_in(&mut tx, chunk).await?;
+            tx.commit().await?;
+        }
+
+        Ok(total_affected)
+    }
+
+    /// Same as `upsert_tickers`, but writes through `tx` so it can be combined with other
+    /// writes in [`Self::with_transaction`].
+    pub async fn upsert_tickers_in(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        tickers: &[Ticker],
+    ) -> Result<u64> {
+        const BATCH_SIZE: usize = 1000;
+        let mut total_affected = 0u64;
 
+        for chunk in tickers.chunks(BATCH_SIZE) {
             let mut query_builder = sqlx::QueryBuilder::new(
                 "INSERT INTO tickers (symbol, exchange, description, currency, country, market_type, industry, sector, founded) ",
             );
@@ -158,11 +636,16 @@ impl Database {
                     .push_bind(&ticker.exchange)
                     .push_bind(&ticker.description)
                     .push_bind(&ticker.currency)
-                    .push_bind(&ticker.country)
+                    .push_bind(
+                        ticker
+                            .country
+                            .as_deref()
+                            .or_else(|| exchange_to_country(&ticker.exchange)),
+                    )
                     .push_bind(&ticker.market_type)
                     .push_bind(&ticker.industry)
                     .push_bind(&ticker.sector)
-                    .push_bind(ticker.founded);
+                    .push_bind(ticker.founded.and_then(normalize_founded));
             });
 
             query_builder.push(" ON CONFLICT(symbol, exchange) DO UPDATE SET ");
@@ -175,17 +658,30 @@ impl Database {
             query_builder.push("founded = excluded.founded");
 
             let query = query_builder.build();
-            let result = query.execute(&mut *tx).await?;
+            let result = query.execute(&mut **tx).await?;
             total_affected += result.rows_affected();
-
-            tx.commit().await?;
         }
 
         Ok(total_affected)
     }
 
+    /// Upsert every ticker stored in a Parquet file exported with `save_parquet`.
+    pub async fn import_tickers_from_parquet(&self, path: &str) -> Result<u64> {
+        let tickers = load_tickers_parquet(path)?;
+        tracing::info!("Importing {} tickers from {}", tickers.len(), path);
+        self.upsert_tickers(&tickers).await
+    }
+
+    /// Upsert every ticker stored in a CSV file exported with `save_csv`.
+    pub async fn import_tickers_from_csv(&self, path: &str) -> Result<u64> {
+        let tickers = load_tickers_csv(path)?;
+        tracing::info!("Importing {} tickers from {}", tickers.len(), path);
+        self.upsert_tickers(&tickers).await
+    }
+
     // DELETE operations
     pub async fn delete_ticker(&self, symbol: &str, exchange: &str) -> Result<bool> {
+        let _writer = self.write_lock().await;
         let result = sqlx::query!(
             "DELETE FROM TICKERS WHERE symbol = ? AND exchange = ?",
             symbol,
@@ -198,6 +694,7 @@ impl Database {
     }
 
     pub async fn delete_tickers_by_exchange(&self, exchange: &str) -> Result<u64> {
+        let _writer = self.write_lock().await;
         let result = sqlx::query!("DELETE FROM tickers WHERE exchange = ?", exchange)
             .execute(&self.pool)
             .await?;
@@ -205,6 +702,17 @@ impl Database {
         Ok(result.rows_affected())
     }
 
+    /// Distinct exchanges in `TICKERS` with their ticker counts, largest first.
+    pub async fn get_exchanges(&self) -> Result<Vec<(String, i64)>> {
+        let rows = sqlx::query_as::<_, (String, i64)>(
+            "SELECT exchange, COUNT(*) AS count FROM TICKERS GROUP BY exchange ORDER BY count DESC, exchange",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
     pub async fn get_ticker_count(&self) -> Result<i64> {
         let count = sqlx::query!("SELECT COUNT(*) as count FROM TICKERS")
             .fetch_one(&self.pool)
@@ -218,77 +726,96 @@ impl Database {
         ticker: &impl MarketSymbol,
         interval: Interval,
         prices: &[impl OHLCV],
-    ) -> Result<u64> {
+    ) -> Result<UpsertReport> {
+        self.upsert_prices_with(ticker, interval, prices, &UpsertOptions::default())
+            .await
+    }
+
+    /// Same as `upsert_prices`, but fails without storing anything when the candles are
+    /// spaced or aligned inconsistently with `interval`. Catches corrupted upstream data.
+    pub async fn upsert_prices_strict(
+        &self,
+        ticker: &impl MarketSymbol,
+        interval: Interval,
+        prices: &[impl OHLCV],
+    ) -> Result<UpsertReport> {
+        self.upsert_prices_with(ticker, interval, prices, &UpsertOptions::strict())
+            .await
+    }
+
+    /// Insert candles for `ticker`, applying `options`.
+    ///
+    /// Bars rejected by `options.validation` are skipped and listed in the returned report.
+    /// When several of the remaining bars share a timestamp only the last one is kept, and
+    /// the spacing and alignment checks only see what is left.
+    /// The write is atomic: either every valid candle is stored or, on error, none are.
+    /// Timestamps are bound as `DateTime<Utc>`, which sqlx stores as RFC 3339 UTC text; every
+    /// query on the `timestamp` column binds the same type so comparisons stay consistent.
+    /// Never bind `OHLCV::timestamp()` millis here.
+    pub async fn upsert_prices_with(
+        &self,
+        ticker: &impl MarketSymbol,
+        interval: Interval,
+        prices: &[impl OHLCV],
+        options: &UpsertOptions,
+    ) -> Result<UpsertReport> {
         if prices.is_empty() {
-            return Ok(0);
+            return Ok(UpsertReport::default());
         }
-    
-        // Filter out invalid OHLCV data before inserting
-        let valid_prices: Vec<_> = prices
-            .iter()
-            .filter(|price| {
-                // Check for null/invalid values
-                let open = price.open();
-                let high = price.high();
-                let low = price.low();
-                let close = price.close();
-                let volume = price.volume();
-                
-                // Filter out records with null, zero, or negative OHLC values
-                let is_valid = !open.is_nan() && !open.is_infinite() && open > 0.0
-                    && !high.is_nan() && !high.is_infinite() && high > 0.0
-                    && !low.is_nan() && !low.is_infinite() && low > 0.0
-                    && !close.is_nan() && !close.is_infinite() && close > 0.0
-                    && !volume.is_nan() && !volume.is_infinite() && volume >= 0.0
-                    && high >= low // High should be >= low
-                    && high >= open && high >= close // High should be >= open and close
-                    && low <= open && low <= close; // Low should be <= open and close
-                
-                if !is_valid {
-                    tracing::debug!(
-                        "Filtering out invalid OHLCV data for {}:{} at {}: O={}, H={}, L={}, C={}, V={}",
-                        ticker.symbol(),
-                        ticker.exchange(),
-                        price.datetime(),
-                        open, high, low, close, volume
-                    );
-                }
-                
-                is_valid
-            })
-            .collect();
-    
+
+        // All chunks share one transaction so a failure leaves nothing behind and the
+        // caller can safely retry the whole slice
+        let _writer = self.write_lock().await;
+        let mut tx = self.pool.begin().await?;
+        let report = Self::upsert_prices_in(&mut tx, ticker, interval, prices, options).await?;
+        tx.commit().await?;
+
+        Ok(report)
+    }
+
+    /// Same as `upsert_prices_with`, but writes through `tx` so it can be combined with other
+    /// writes in [`Self::with_transaction`].
+    pub async fn upsert_prices_in(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        ticker: &impl MarketSymbol,
+        interval: Interval,
+        prices: &[impl OHLCV],
+        options: &UpsertOptions,
+    ) -> Result<UpsertReport> {
+        if prices.is_empty() {
+            return Ok(UpsertReport::default());
+        }
+
+        let (valid_prices, mut report) = prepare_prices(ticker, interval, prices, options)?;
+
         if valid_prices.is_empty() {
             tracing::warn!(
-                "No valid OHLCV data found for {}:{} after filtering", 
-                ticker.symbol(), 
+                "No valid OHLCV data found for {}:{} after filtering",
+                ticker.symbol(),
                 ticker.exchange()
             );
-            return Ok(0);
+            return Ok(report);
         }
-    
+
         tracing::debug!(
-            "Filtered {} invalid records, inserting {} valid records for {}:{}",
-            prices.len() - valid_prices.len(),
+            "Rejected {} invalid records, inserting {} valid records for {}:{}",
+            report.rejected.len(),
             valid_prices.len(),
             ticker.symbol(),
             ticker.exchange()
         );
-    
+
         const BATCH_SIZE: usize = 1000;
-        let mut total_affected = 0u64;
-    
+
         for chunk in valid_prices.chunks(BATCH_SIZE) {
-            let mut tx = self.pool.begin().await?;
-    
             let mut query_builder = sqlx::QueryBuilder::new(
-                "INSERT OR REPLACE INTO OHLCV (symbol, exchange, interval, timestamp, open, high, low, close, volume) ",
+                "INSERT INTO OHLCV (symbol, exchange, interval, timestamp, open, high, low, close, volume) ",
             );
-    
+
             query_builder.push_values(chunk, |mut b, price| {
                 b.push_bind(ticker.symbol())
                     .push_bind(ticker.exchange())
-                    .push_bind(interval.to_string())
+                    .push_bind(interval_to_db_str(interval))
                     .push_bind(price.datetime())
                     .push_bind(price.open())
                     .push_bind(price.high())
@@ -296,15 +823,54 @@ impl Database {
                     .push_bind(price.close())
                     .push_bind(price.volume());
             });
-    
+
+            // Updating in place keeps the columns a fetch doesn't carry, such as adj_close
+            query_builder.push(" ON CONFLICT(symbol, exchange, interval, timestamp) ");
+            match options.mode {
+                InsertMode::Replace => {
+                    query_builder.push("DO UPDATE SET ");
+                    query_builder.push("open = excluded.open, ");
+                    query_builder.push("high = excluded.high, ");
+                    query_builder.push("low = excluded.low, ");
+                    query_builder.push("close = excluded.close, ");
+                    query_builder.push("volume = excluded.volume");
+                }
+                InsertMode::Ignore => {
+                    query_builder.push("DO NOTHING");
+                }
+            }
+
             let query = query_builder.build();
-            let result = query.execute(&mut *tx).await?;
-            total_affected += result.rows_affected();
-    
-            tx.commit().await?;
+            let result = query.execute(&mut **tx).await?;
+            report.inserted += result.rows_affected();
         }
-    
-        Ok(total_affected)
+
+        Ok(report)
+    }
+
+    /// Upsert candles for several tickers and intervals in one write transaction, returning
+    /// one report per entry, in order.
+    ///
+    /// Each entry is stored with `upsert_prices_in` and the default options, so it gets the
+    /// same validation, dedup and checks as `upsert_prices`, but a whole batch result costs
+    /// one commit instead of one per ticker. Either every valid candle is stored or, on
+    /// error, none are.
+    pub async fn upsert_prices_multi(
+        &self,
+        entries: &[(Ticker, Interval, Vec<Candle>)],
+    ) -> Result<Vec<UpsertReport>> {
+        let options = UpsertOptions::default();
+        let mut reports = Vec::with_capacity(entries.len());
+
+        let _writer = self.write_lock().await;
+        let mut tx = self.pool.begin().await?;
+        for (ticker, interval, bars) in entries {
+            let report = Self::upsert_prices_in(&mut tx, ticker, *interval, bars, &options).await?;
+            reports.push(report);
+        }
+        tx.commit().await?;
+
+        Ok(reports)
     }
 
     #[builder]
@@ -314,15 +880,19 @@ impl Database {
         interval: Interval,
         start: Option<DateTime<Utc>>,
         end: Option<DateTime<Utc>>,
+        /// Only return candles strictly after this timestamp
+        after: Option<DateTime<Utc>>,
+        /// Maximum number of candles to return
+        limit: Option<i64>,
     ) -> Result<Vec<Candle>> {
         let mut query = sqlx::QueryBuilder::new(
-            "SELECT timestamp, open, high, low, close, volume FROM OHLCV WHERE symbol = ",
+            "SELECT timestamp, open, high, low, close, volume, adj_close FROM OHLCV WHERE symbol = ",
         );
         query.push_bind(&ticker.symbol);
         query.push(" AND exchange = ");
         query.push_bind(&ticker.exchange);
         query.push(" AND interval = ");
-        query.push_bind(interval.to_string());
+        query.push_bind(interval_to_db_str(interval));
 
         if let Some(start_date) = start {
             query.push(" AND timestamp >= ");
@@ -334,10 +904,20 @@ impl Database {
             query.push_bind(end_date);
         }
 
+        if let Some(after_date) = after {
+            query.push(" AND timestamp > ");
+            query.push_bind(after_date);
+        }
+
         query.push(" ORDER BY timestamp ASC");
 
+        if let Some(limit) = limit {
+            query.push(" LIMIT ");
+            query.push_bind(limit);
+        }
+
         let rows = query
-            .build_query_as::<(chrono::DateTime<Utc>, f64, f64, f64, f64, f64)>()
+            .build_query_as::<(chrono::DateTime<Utc>, f64, f64, f64, f64, f64, Option<f64>)>()
             .fetch_all(&self.pool)
             .await?;
 
@@ -350,120 +930,2244 @@ impl Database {
                 low: row.3,
                 close: row.4,
                 volume: row.5,
+                adj_close: row.6,
             })
             .collect();
 
         Ok(candles)
     }
-    pub async fn search_tickers(&self, query: &str, limit: Option<i64>) -> Result<Vec<Ticker>> {
-        let limit = limit.unwrap_or(50);
-        
-        let tickers = sqlx::query_as!(
-            Ticker,
-            r#"
-            SELECT t.symbol, t.exchange, t.description, t.currency, t.country, 
-                   t.market_type, t.industry, t.sector, t.founded
-            FROM tickers_fts 
-            JOIN TICKERS t ON tickers_fts.rowid = t.rowid
-            WHERE tickers_fts MATCH ?
-            ORDER BY bm25(tickers_fts)
-            LIMIT ?
-            "#,
-            query,
-            limit
-        )
-        .fetch_all(&self.pool)
-        .await?;
 
+    /// Candles of a ticker for several intervals between `start` and `end`, both inclusive,
+    /// keyed by interval and sorted by timestamp.
+    ///
+    /// One query with `interval IN (...)` replaces a round trip per interval. Every requested
+    /// interval has an entry, empty when nothing is stored.
+    pub async fn get_prices_multi(
+        &self,
+        ticker: &Ticker,
+        intervals: &[Interval],
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<std::collections::HashMap<Interval, Vec<Candle>>> {
+        let mut series = intervals
+            .iter()
+            .map(|interval| (*interval, Vec::new()))
+            .collect::<std::collections::HashMap<_, _>>();
+        if intervals.is_empty() {
+            return Ok(series);
+        }
 
-        Ok(tickers)
-    }
+        let mut query = sqlx::QueryBuilder::new(
+            "SELECT interval, timestamp, open, high, low, close, volume, adj_close FROM OHLCV WHERE symbol = ",
+        );
+        query.push_bind(&ticker.symbol);
+        query.push(" AND exchange = ");
+        query.push_bind(&ticker.exchange);
+        query.push(" AND interval IN (");
+        let mut codes = query.separated(", ");
+        for interval in intervals {
+            codes.push_bind(interval_to_db_str(*interval));
+        }
+        query.push(")");
+        if let Some(start) = start {
+            query.push(" AND timestamp >= ");
+            query.push_bind(start);
+        }
+        if let Some(end) = end {
+            query.push(" AND timestamp <= ");
+            query.push_bind(end);
+        }
+        query.push(" ORDER BY interval, timestamp ASC");
 
-    /// Search tickers with additional filtering by exchange
-    pub async fn search_tickers_by_exchange(
-        &self, 
-        query: &str, 
-        exchange: &str, 
-        limit: Option<i64>
-    ) -> Result<Vec<Ticker>> {
-        let limit = limit.unwrap_or(50);
-        
-        let rows = sqlx::query_as!(
-            Ticker,
-            r#"
-            SELECT t.symbol, t.exchange, t.description, t.currency, t.country, 
-                   t.market_type, t.industry, t.sector, t.founded
-            FROM tickers_fts 
-            JOIN TICKERS t ON tickers_fts.rowid = t.rowid
-            WHERE tickers_fts MATCH ? AND t.exchange = ?
-            ORDER BY bm25(tickers_fts)
-            LIMIT ?
-            "#,
-            query,
-            exchange,
-            limit
-        )
-        .fetch_all(&self.pool)
-        .await?;
-    
-        Ok(rows)
-    }
+        let rows = query
+            .build_query_as::<(String, DateTime<Utc>, f64, f64, f64, f64, f64, Option<f64>)>()
+            .fetch_all(&self.pool)
+            .await?;
 
-    pub async fn search_tickers_by_field(
-        &self, 
-        field: &str, 
-        query: &str, 
-        limit: Option<i64>
-    ) -> Result<Vec<Ticker>> {
-        let limit = limit.unwrap_or(50);
-        
-        // Validate field name to prevent SQL injection - now includes all columns
-        let valid_fields = ["symbol", "exchange", "description", "currency", "country", "market_type", "industry", "sector"];
-        if !valid_fields.contains(&field) {
-            return Err(anyhow::anyhow!("Invalid field name: {}", field));
+        for row in rows {
+            let interval = interval_from_db_str(&row.0)?;
+            series.entry(interval).or_default().push(Candle {
+                timestamp: row.1,
+                open: row.2,
+                high: row.3,
+                low: row.4,
+                close: row.5,
+                volume: row.6,
+                adj_close: row.7,
+            });
         }
-    
-        let search_query = format!("{}: {}", field, query);
-        
-        let rows = sqlx::query_as!(
+
+        Ok(series)
+    }
+
+    /// Candles of a ticker between `start` and `end` as a single Arrow batch.
+    ///
+    /// Rows are appended to the column builders as they are read, without building an
+    /// intermediate `Vec<Candle>`. The batch uses [`candle_schema`].
+    pub async fn get_prices_batch(
+        &self,
+        ticker: &Ticker,
+        interval: Interval,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<RecordBatch> {
+        let mut query = sqlx::QueryBuilder::new(
+            "SELECT timestamp, open, high, low, close, volume, adj_close FROM OHLCV WHERE symbol = ",
+        );
+        query.push_bind(&ticker.symbol);
+        query.push(" AND exchange = ");
+        query.push_bind(&ticker.exchange);
+        query.push(" AND interval = ");
+        query.push_bind(interval_to_db_str(interval));
+        if let Some(start) = start {
+            query.push(" AND timestamp >= ");
+            query.push_bind(start);
+        }
+        if let Some(end) = end {
+            query.push(" AND timestamp <= ");
+            query.push_bind(end);
+        }
+        query.push(" ORDER BY timestamp ASC");
+
+        let mut timestamps = TimestampMillisecondBuilder::new().with_timezone("UTC");
+        let mut opens = Float64Builder::new();
+        let mut highs = Float64Builder::new();
+        let mut lows = Float64Builder::new();
+        let mut closes = Float64Builder::new();
+        let mut volumes = Float64Builder::new();
+        let mut adj_closes = Float64Builder::new();
+
+        let mut rows = query
+            .build_query_as::<(DateTime<Utc>, f64, f64, f64, f64, f64, Option<f64>)>()
+            .fetch(&self.pool);
+        while let Some(row) = rows.try_next().await? {
+            timestamps.append_value(row.0.timestamp_millis());
+            opens.append_value(row.1);
+            highs.append_value(row.2);
+            lows.append_value(row.3);
+            closes.append_value(row.4);
+            volumes.append_value(row.5);
+            adj_closes.append_option(row.6);
+        }
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(timestamps.finish()),
+            Arc::new(opens.finish()),
+            Arc::new(highs.finish()),
+            Arc::new(lows.finish()),
+            Arc::new(closes.finish()),
+            Arc::new(volumes.finish()),
+            Arc::new(adj_closes.finish()),
+        ];
+        Ok(RecordBatch::try_new(candle_schema(), columns)?)
+    }
+
+    /// Delete the candles of a ticker between `start` and `end`, both inclusive.
+    ///
+    /// A missing bound leaves that side open, so passing neither deletes the whole series.
+    /// Indicators computed from the deleted candles are removed with them. Returns the
+    /// number of candles deleted.
+    pub async fn delete_prices(
+        &self,
+        ticker: &Ticker,
+        interval: Interval,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<u64> {
+        let mut query = sqlx::QueryBuilder::new("DELETE FROM OHLCV WHERE symbol = ");
+        query.push_bind(&ticker.symbol);
+        query.push(" AND exchange = ");
+        query.push_bind(&ticker.exchange);
+        query.push(" AND interval = ");
+        query.push_bind(interval_to_db_str(interval));
+
+        if let Some(start_date) = start {
+            query.push(" AND timestamp >= ");
+            query.push_bind(start_date);
+        }
+
+        if let Some(end_date) = end {
+            query.push(" AND timestamp <= ");
+            query.push_bind(end_date);
+        }
+
+        let _writer = self.write_lock().await;
+        let result = query.build().execute(&self.pool).await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Same as `get_prices`, wrapped with the series' count, span and identity.
+    #[builder]
+    pub async fn get_prices_with_meta(
+        &self,
+        ticker: &Ticker,
+        interval: Interval,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<PriceSeries> {
+        let candles = self
+            .get_prices()
+            .ticker(ticker)
+            .interval(interval)
+            .maybe_start(start)
+            .maybe_end(end)
+            .call()
+            .await?;
+
+        Ok(PriceSeries::new(ticker, interval, candles))
+    }
+
+    /// Fetch one page of candles in ascending timestamp order.
+    ///
+    /// Pass the returned cursor back as `after` to get the next page; it is `None` once
+    /// the last page has been read.
+    #[builder]
+    pub async fn get_prices_paginated(
+        &self,
+        ticker: &Ticker,
+        interval: Interval,
+        after: Option<Cursor>,
+        #[builder(default = 1000)] limit: i64,
+    ) -> Result<(Vec<Candle>, Option<Cursor>)> {
+        let candles = self
+            .get_prices()
+            .ticker(ticker)
+            .interval(interval)
+            .maybe_after(after.map(|cursor| cursor.0))
+            .limit(limit)
+            .call()
+            .await?;
+
+        let cursor = if candles.len() as i64 == limit {
+            candles.last().map(|candle| Cursor(candle.timestamp))
+        } else {
+            None
+        };
+
+        Ok((candles, cursor))
+    }
+
+    /// Stream candles in ascending timestamp order without loading them all into memory.
+    pub fn stream_prices<'a>(
+        &'a self,
+        ticker: &'a Ticker,
+        interval: Interval,
+    ) -> impl Stream<Item = Result<Candle>> + 'a {
+        sqlx::query_as::<_, (DateTime<Utc>, f64, f64, f64, f64, f64, Option<f64>)>(
+            "SELECT timestamp, open, high, low, close, volume, adj_close FROM OHLCV WHERE symbol = ? AND exchange = ? AND interval = ? ORDER BY timestamp ASC",
+        )
+        .bind(&ticker.symbol)
+        .bind(&ticker.exchange)
+        .bind(interval_to_db_str(interval))
+        .fetch(&self.pool)
+        .map_ok(|row| Candle {
+            timestamp: row.0,
+            open: row.1,
+            high: row.2,
+            low: row.3,
+            close: row.4,
+            volume: row.5,
+            adj_close: row.6,
+        })
+        .map_err(anyhow::Error::from)
+    }
+
+    /// Timestamp of the most recent stored candle, or `None` if nothing is stored yet.
+    pub async fn get_last_candle_timestamp(
+        &self,
+        symbol: &str,
+        exchange: &str,
+        interval: Interval,
+    ) -> Result<Option<DateTime<Utc>>> {
+        let timestamp = sqlx::query_scalar::<_, DateTime<Utc>>(
+            "SELECT timestamp FROM OHLCV WHERE symbol = ? AND exchange = ? AND interval = ? ORDER BY timestamp DESC LIMIT 1",
+        )
+        .bind(symbol)
+        .bind(exchange)
+        .bind(interval_to_db_str(interval))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(timestamp)
+    }
+
+    /// Ranges where consecutive stored candles are farther apart than `interval` implies.
+    ///
+    /// Daily bars are checked against the exchange's trading calendar, so weekends and
+    /// holidays such as Tet are not reported. See
+    /// [`find_gaps_in`](crate::finance::quality::find_gaps_in) for intraday and weekly rules.
+    pub async fn find_price_gaps(
+        &self,
+        ticker: &Ticker,
+        interval: Interval,
+    ) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>> {
+        let timestamps = sqlx::query_scalar::<_, DateTime<Utc>>(
+            "SELECT timestamp FROM OHLCV WHERE symbol = ? AND exchange = ? AND interval = ? ORDER BY timestamp ASC",
+        )
+        .bind(&ticker.symbol)
+        .bind(&ticker.exchange)
+        .bind(interval_to_db_str(interval))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(find_gaps_in(
+            &timestamps,
+            interval,
+            calendar_for_exchange(&ticker.exchange),
+        ))
+    }
+
+    /// Number of stored candles of a ticker.
+    pub async fn count_prices(&self, ticker: &Ticker, interval: Interval) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM OHLCV WHERE symbol = ? AND exchange = ? AND interval = ?",
+        )
+        .bind(&ticker.symbol)
+        .bind(&ticker.exchange)
+        .bind(interval_to_db_str(interval))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Number of stored candles across every ticker and interval.
+    pub async fn count_all_prices(&self) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM OHLCV")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Count, span, close range and total volume of the stored candles of a ticker.
+    pub async fn get_price_stats(&self, ticker: &Ticker, interval: Interval) -> Result<PriceStats> {
+        let stats = sqlx::query_as::<_, PriceStats>(
+            "SELECT COUNT(*) AS count, MIN(timestamp) AS first, MAX(timestamp) AS last, \
+             MIN(close) AS min_close, MAX(close) AS max_close, COALESCE(SUM(volume), 0.0) AS total_volume \
+             FROM OHLCV WHERE symbol = ? AND exchange = ? AND interval = ?",
+        )
+        .bind(&ticker.symbol)
+        .bind(&ticker.exchange)
+        .bind(interval_to_db_str(interval))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(stats)
+    }
+
+    /// Insert or replace computed indicators of a ticker.
+    ///
+    /// Each indicator must line up with a stored candle of the same interval, since
+    /// `TECHNICAL_INDICATORS` references `OHLCV`. Returns the number of rows written.
+    pub async fn upsert_indicators(
+        &self,
+        ticker: &Ticker,
+        interval: Interval,
+        indicators: &[Indicator],
+    ) -> Result<u64> {
+        if indicators.is_empty() {
+            return Ok(0);
+        }
+
+        const BATCH_SIZE: usize = 1000;
+        let interval = interval_to_db_str(interval);
+        let mut total_affected = 0u64;
+
+        let _writer = self.write_lock().await;
+        let mut tx = self.pool.begin().await?;
+        for chunk in indicators.chunks(BATCH_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                "INSERT OR REPLACE INTO TECHNICAL_INDICATORS (symbol, exchange, interval, timestamp, indicator_type, value, metadata) ",
+            );
+            query_builder.push_values(chunk, |mut b, indicator| {
+                b.push_bind(&ticker.symbol)
+                    .push_bind(&ticker.exchange)
+                    .push_bind(interval.as_ref())
+                    .push_bind(indicator.timestamp)
+                    .push_bind(&indicator.indicator_type)
+                    .push_bind(indicator.value)
+                    .push_bind(&indicator.metadata);
+            });
+
+            let result = query_builder.build().execute(&mut *tx).await?;
+            total_affected += result.rows_affected();
+        }
+        tx.commit().await?;
+
+        Ok(total_affected)
+    }
+
+    /// Insert or replace ML feature rows of a ticker, keyed on their timestamp.
+    ///
+    /// Rows are written in batches of 1000 inside one transaction, like `upsert_prices`.
+    /// Returns the number of rows written.
+    pub async fn upsert_ml_features(
+        &self,
+        ticker: &Ticker,
+        interval: Interval,
+        features: &[MlFeatures],
+    ) -> Result<u64> {
+        if features.is_empty() {
+            return Ok(0);
+        }
+
+        const BATCH_SIZE: usize = 1000;
+        let interval = interval_to_db_str(interval);
+        let mut total_affected = 0u64;
+
+        let _writer = self.write_lock().await;
+        let mut tx = self.pool.begin().await?;
+        for chunk in features.chunks(BATCH_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                "INSERT OR REPLACE INTO ML_FEATURES (symbol, exchange, interval, timestamp, open, high, low, close, volume, rsi, mfi, sma_20, ema_12, price_change_pct, volatility_pct) ",
+            );
+            query_builder.push_values(chunk, |mut b, row| {
+                b.push_bind(&ticker.symbol)
+                    .push_bind(&ticker.exchange)
+                    .push_bind(interval.as_ref())
+                    .push_bind(row.timestamp)
+                    .push_bind(row.open)
+                    .push_bind(row.high)
+                    .push_bind(row.low)
+                    .push_bind(row.close)
+                    .push_bind(row.volume)
+                    .push_bind(row.rsi)
+                    .push_bind(row.mfi)
+                    .push_bind(row.sma_20)
+                    .push_bind(row.ema_12)
+                    .push_bind(row.price_change_pct)
+                    .push_bind(row.volatility_pct);
+            });
+
+            let result = query_builder.build().execute(&mut *tx).await?;
+            total_affected += result.rows_affected();
+        }
+        tx.commit().await?;
+
+        Ok(total_affected)
+    }
+
+    /// Stored ML feature rows of a ticker between `start` and `end`, both inclusive, in
+    /// ascending timestamp order. A missing bound leaves that side open.
+    pub async fn get_ml_features(
+        &self,
+        ticker: &Ticker,
+        interval: Interval,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<Vec<MlFeatures>> {
+        let mut query = sqlx::QueryBuilder::new(
+            "SELECT timestamp, open, high, low, close, volume, rsi, mfi, sma_20, ema_12, price_change_pct, volatility_pct FROM ML_FEATURES WHERE symbol = ",
+        );
+        query.push_bind(&ticker.symbol);
+        query.push(" AND exchange = ");
+        query.push_bind(&ticker.exchange);
+        query.push(" AND interval = ");
+        query.push_bind(interval_to_db_str(interval));
+        if let Some(start) = start {
+            query.push(" AND timestamp >= ");
+            query.push_bind(start);
+        }
+        if let Some(end) = end {
+            query.push(" AND timestamp <= ");
+            query.push_bind(end);
+        }
+        query.push(" ORDER BY timestamp ASC");
+
+        let features = query
+            .build_query_as::<MlFeatures>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(features)
+    }
+
+    /// Most recent candle of every ticker with data for `interval`, ordered by symbol.
+    ///
+    /// Runs as a single query ranking each ticker's candles with `ROW_NUMBER()`, rather than
+    /// one lookup per ticker.
+    pub async fn get_latest_prices(&self, interval: Interval) -> Result<Vec<(Ticker, Candle)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT t.symbol, t.exchange, t.description, t.currency, t.country,
+                   t.market_type, t.industry, t.sector, t.founded,
+                   o.timestamp, o.open, o.high, o.low, o.close, o.volume, o.adj_close
+            FROM (
+                SELECT *, ROW_NUMBER() OVER (
+                    PARTITION BY symbol, exchange ORDER BY timestamp DESC
+                ) AS rn
+                FROM OHLCV
+                WHERE interval = ?
+            ) o
+            JOIN TICKERS t ON t.symbol = o.symbol AND t.exchange = o.exchange
+            WHERE o.rn = 1
+            ORDER BY t.symbol, t.exchange
+            "#,
+        )
+        .bind(interval_to_db_str(interval))
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| Ok((Ticker::from_row(row)?, Candle::from_row(row)?)))
+            .collect()
+    }
+
+    /// Data-quality report for the stored candles of a ticker.
+    ///
+    /// See [`QualityReport::from_candles`] for how each component is weighted.
+    pub async fn quality_score(
+        &self,
+        ticker: &Ticker,
+        interval: Interval,
+    ) -> Result<QualityReport> {
+        let candles = self
+            .get_prices()
+            .ticker(ticker)
+            .interval(interval)
+            .call()
+            .await?;
+
+        Ok(QualityReport::from_candles(&candles, interval))
+    }
+
+    /// Record a split or dividend, replacing any action of the same kind on the same date.
+    pub async fn upsert_corporate_action(
+        &self,
+        symbol: &str,
+        exchange: &str,
+        action: &CorporateAction,
+    ) -> Result<()> {
+        let _writer = self.write_lock().await;
+        sqlx::query(
+            "INSERT OR REPLACE INTO CORPORATE_ACTIONS (symbol, exchange, date, kind, ratio) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(symbol)
+        .bind(exchange)
+        .bind(action.date)
+        .bind(action.kind.as_str())
+        .bind(action.ratio)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Corporate actions recorded for a ticker, oldest ex-date first.
+    pub async fn get_corporate_actions(
+        &self,
+        symbol: &str,
+        exchange: &str,
+    ) -> Result<Vec<CorporateAction>> {
+        let rows = sqlx::query_as::<_, (NaiveDate, String, f64)>(
+            "SELECT date, kind, ratio FROM CORPORATE_ACTIONS WHERE symbol = ? AND exchange = ? ORDER BY date, kind",
+        )
+        .bind(symbol)
+        .bind(exchange)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(date, kind, ratio)| {
+                Ok(CorporateAction {
+                    date,
+                    ratio,
+                    kind: kind.parse()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Full-text search over tickers, best match first.
+    ///
+    /// `query` is free text, escaped with [`sanitize_fts_query`]; set `prefix` to match
+    /// partial last terms. Returns up to `limit` rows (default 50) after skipping `offset`
+    /// rows. Ties in bm25 score are broken by symbol and exchange so consecutive pages never
+    /// overlap; use [`Self::count_search_tickers`] for the total number of matches.
+    pub async fn search_tickers(
+        &self,
+        query: &str,
+        prefix: bool,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<Vec<Ticker>> {
+        let Some(query) = sanitize_fts_query(query, prefix) else {
+            return Ok(Vec::new());
+        };
+        let limit = limit.unwrap_or(50);
+        let offset = offset.unwrap_or(0);
+
+        let tickers = sqlx::query_as::<_, Ticker>(
+            r#"
+            SELECT t.symbol, t.exchange, t.description, t.currency, t.country,
+                   t.market_type, t.industry, t.sector, t.founded
+            FROM tickers_fts
+            JOIN TICKERS t ON tickers_fts.rowid = t.rowid
+            WHERE tickers_fts MATCH ?
+            ORDER BY bm25(tickers_fts), t.symbol, t.exchange
+            LIMIT ? OFFSET ?
+            "#,
+        )
+        .bind(query)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(tickers)
+    }
+
+    /// Total number of tickers matching `query`, for paging through [`Self::search_tickers`].
+    pub async fn count_search_tickers(&self, query: &str, prefix: bool) -> Result<i64> {
+        let Some(query) = sanitize_fts_query(query, prefix) else {
+            return Ok(0);
+        };
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM tickers_fts WHERE tickers_fts MATCH ?",
+        )
+        .bind(query)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Search tickers with additional filtering by exchange
+    ///
+    /// `query` is escaped like in [`Self::search_tickers`].
+    pub async fn search_tickers_by_exchange(
+        &self,
+        query: &str,
+        exchange: &str,
+        limit: Option<i64>,
+    ) -> Result<Vec<Ticker>> {
+        let Some(query) = sanitize_fts_query(query, false) else {
+            return Ok(Vec::new());
+        };
+        let limit = limit.unwrap_or(50);
+
+        let rows = sqlx::query_as!(
             Ticker,
             r#"
             SELECT t.symbol, t.exchange, t.description, t.currency, t.country, 
                    t.market_type, t.industry, t.sector, t.founded
             FROM tickers_fts 
             JOIN TICKERS t ON tickers_fts.rowid = t.rowid
-            WHERE tickers_fts MATCH ?
+            WHERE tickers_fts MATCH ? AND t.exchange = ?
             ORDER BY bm25(tickers_fts)
             LIMIT ?
             "#,
-            search_query,
+            query,
+            exchange,
             limit
         )
         .fetch_all(&self.pool)
         .await?;
-    
+
         Ok(rows)
     }
 
+    /// Search `query` in any of `fields`, e.g. `&["industry", "sector"]`.
+    ///
+    /// Builds `(field1 : (query) OR field2 : (query))`, so the result is the union of the
+    /// per-field matches. Every field must be a `tickers_fts` column, which keeps field
+    /// names out of reach of injection; `query` is escaped like in [`Self::search_tickers`].
+    /// With `exchange`, only tickers listed there are returned.
+    pub async fn search_tickers_by_field(
+        &self,
+        fields: &[&str],
+        query: &str,
+        exchange: Option<&str>,
+        limit: Option<i64>,
+    ) -> Result<Vec<Ticker>> {
+        if fields.is_empty() {
+            return Err(anyhow::anyhow!("No fields given to search"));
+        }
+        if let Some(field) = fields
+            .iter()
+            .find(|field| !SEARCH_COLUMN_WEIGHTS.iter().any(|(name, _)| name == *field))
+        {
+            return Err(anyhow::anyhow!("Invalid field name: {}", field));
+        }
+        let Some(query) = sanitize_fts_query(query, false) else {
+            return Ok(Vec::new());
+        };
+        let limit = limit.unwrap_or(50);
 
-    pub async fn rebuild_search_index(&self) -> Result<()> {
-        // Clear existing FTS data
-        sqlx::query("DELETE FROM tickers_fts").execute(&self.pool).await?;
-        
-        // Repopulate FTS table with all searchable columns
-        sqlx::query!(
-            "INSERT INTO tickers_fts(symbol, exchange, description, currency, country, market_type, industry, sector) 
-             SELECT symbol, exchange, description, currency, country, market_type, industry, sector FROM TICKERS"
-        )
-        .execute(&self.pool)
-        .await?;
-    
-        // Optimize the FTS index
-        sqlx::query("INSERT INTO tickers_fts(tickers_fts) VALUES('optimize')")
-            .execute(&self.pool)
+        let search_query = fields
+            .iter()
+            .map(|field| format!("{field} : ({query})"))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+
+        let rows = sqlx::query_as::<_, Ticker>(
+            r#"
+            SELECT t.symbol, t.exchange, t.description, t.currency, t.country,
+                   t.market_type, t.industry, t.sector, t.founded
+            FROM tickers_fts
+            JOIN TICKERS t ON tickers_fts.rowid = t.rowid
+            WHERE tickers_fts MATCH ? AND (? IS NULL OR t.exchange = ?)
+            ORDER BY bm25(tickers_fts), t.symbol, t.exchange
+            LIMIT ?
+            "#,
+        )
+        .bind(search_query)
+        .bind(exchange)
+        .bind(exchange)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Search symbol, description, industry and sector together, boosting symbol matches.
+    ///
+    /// Each hit comes with its relevance, the negated bm25 score computed with
+    /// `SEARCH_COLUMN_WEIGHTS`: higher is better, and a symbol hit weighs five times a
+    /// description hit. `query` is escaped like in [`Self::search_tickers`].
+    pub async fn search_tickers_weighted(
+        &self,
+        query: &str,
+        limit: Option<i64>,
+    ) -> Result<Vec<(Ticker, f64)>> {
+        let Some(query) = sanitize_fts_query(query, false) else {
+            return Ok(Vec::new());
+        };
+        let limit = limit.unwrap_or(50);
+
+        let columns = SEARCH_COLUMN_WEIGHTS
+            .iter()
+            .filter(|(_, weight)| *weight > 0.0)
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let weights = SEARCH_COLUMN_WEIGHTS
+            .iter()
+            .map(|(_, weight)| weight.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT t.symbol, t.exchange, t.description, t.currency, t.country,
+                   t.market_type, t.industry, t.sector, t.founded,
+                   -bm25(tickers_fts, {weights}) AS relevance
+            FROM tickers_fts
+            JOIN TICKERS t ON tickers_fts.rowid = t.rowid
+            WHERE tickers_fts MATCH ?
+            ORDER BY relevance DESC, t.symbol, t.exchange
+            LIMIT ?
+            "#
+        ))
+        .bind(format!("{{{columns}}} : ({query})"))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| Ok((Ticker::from_row(row)?, row.try_get("relevance")?)))
+            .collect()
+    }
+
+    /// Rebuild the `tickers_fts` index from `TICKERS`, then optimize it.
+    ///
+    /// Uses the fts5 `'rebuild'` command, which re-reads the content table with its rowids, so
+    /// the index still joins back to the right tickers after rows were deleted.
+    pub async fn rebuild_search_index(&self) -> Result<()> {
+        let _writer = self.write_lock().await;
+        sqlx::query("INSERT INTO tickers_fts(tickers_fts) VALUES('rebuild')")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("INSERT INTO tickers_fts(tickers_fts) VALUES('optimize')")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Re-index only the FTS rows of `tickers`, leaving the rest of the index untouched.
+    ///
+    /// Much cheaper than [`Self::rebuild_search_index`] after upserting a handful of tickers.
+    /// Tickers that are not stored are skipped.
+    pub async fn update_search_index_for(&self, tickers: &[Ticker]) -> Result<()> {
+        let _writer = self.write_lock().await;
+        let mut tx = self.pool.begin().await?;
+
+        for ticker in tickers {
+            sqlx::query(
+                "INSERT INTO tickers_fts(tickers_fts, rowid, symbol, exchange, description, currency, country, market_type, industry, sector) \
+                 SELECT 'delete', rowid, symbol, exchange, description, currency, country, market_type, industry, sector \
+                 FROM TICKERS WHERE symbol = ? AND exchange = ?",
+            )
+            .bind(&ticker.symbol)
+            .bind(&ticker.exchange)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(
+                "INSERT INTO tickers_fts(rowid, symbol, exchange, description, currency, country, market_type, industry, sector) \
+                 SELECT rowid, symbol, exchange, description, currency, country, market_type, industry, sector \
+                 FROM TICKERS WHERE symbol = ? AND exchange = ?",
+            )
+            .bind(&ticker.symbol)
+            .bind(&ticker.exchange)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// The bars of `prices` to store under `options`, and a report listing the rejected ones.
+///
+/// Bars failing `options.validation` are rejected, then of the bars sharing a timestamp only
+/// the last one is kept, and the spacing and alignment checks run on what is left. Shared by
+/// the SQLite and Postgres backends so both store the same bars.
+pub(crate) fn prepare_prices<'a, P: OHLCV>(
+    ticker: &impl MarketSymbol,
+    interval: Interval,
+    prices: &'a [P],
+    options: &UpsertOptions,
+) -> Result<(Vec<&'a P>, UpsertReport)> {
+    // Set invalid OHLCV data aside before inserting
+    let mut report = UpsertReport::default();
+    let mut valid_prices = Vec::with_capacity(prices.len());
+    for price in prices {
+        match options.validation.validate(price) {
+            Ok(()) => valid_prices.push(price),
+            Err(e) => {
+                tracing::debug!(
+                    "Rejecting invalid OHLCV data for {}:{} at {}: {} (O={}, H={}, L={}, C={}, V={})",
+                    ticker.symbol(),
+                    ticker.exchange(),
+                    price.datetime(),
+                    e,
+                    price.open(),
+                    price.high(),
+                    price.low(),
+                    price.close(),
+                    price.volume()
+                );
+                report
+                    .rejected
+                    .push((price.datetime().timestamp_millis(), e));
+            }
+        }
+    }
+
+    // Dedup after validation so an invalid duplicate can't displace a valid bar
+    let before = valid_prices.len();
+    let valid_prices = dedup_by_timestamp(valid_prices);
+    if valid_prices.len() < before {
+        tracing::debug!(
+            "Dropped {} duplicate timestamps for {}:{}, keeping the last occurrence",
+            before - valid_prices.len(),
+            ticker.symbol(),
+            ticker.exchange()
+        );
+    }
+
+    let timestamps = valid_prices
+        .iter()
+        .map(|price| price.datetime())
+        .collect::<Vec<_>>();
+    check_interval_spacing(ticker, interval, &timestamps, options.spacing_check)?;
+    check_interval_alignment(ticker, interval, &timestamps, options.alignment_check)?;
+
+    Ok((valid_prices, report))
+}
+
+/// Keep only the last bar for each timestamp, in input order.
+///
+/// Replays can return overlapping bars; without this, which duplicate ends up stored would
+/// depend on how the rows are split into statements.
+fn dedup_by_timestamp<P: OHLCV>(prices: Vec<&P>) -> Vec<&P> {
+    let mut seen = std::collections::HashSet::new();
+    let mut deduped = prices
+        .into_iter()
+        .rev()
+        .filter(|price| seen.insert(price.datetime()))
+        .collect::<Vec<_>>();
+    deduped.reverse();
+    deduped
+}
+
+/// Compare the median spacing of the incoming candles with the declared interval.
+///
+/// Anything between half and three times the nominal bar length is accepted, which leaves
+/// room for weekends, lunch breaks and holidays while still catching minute bars labelled as daily.
+fn check_interval_spacing(
+    ticker: &impl MarketSymbol,
+    interval: Interval,
+    timestamps: &[DateTime<Utc>],
+    check: SpacingCheck,
+) -> Result<()> {
+    if check == SpacingCheck::Off {
+        return Ok(());
+    }
+
+    let Some(expected) = interval_duration(interval) else {
+        return Ok(());
+    };
+    let Some(median) = median_spacing(timestamps) else {
+        return Ok(());
+    };
+
+    let ratio = median.num_seconds() as f64 / expected.num_seconds() as f64;
+    if (0.5..=3.0).contains(&ratio) {
+        return Ok(());
+    }
+
+    let message = format!(
+        "Interval mismatch for {}:{}: declared {} but median candle spacing is {}s",
+        ticker.symbol(),
+        ticker.exchange(),
+        interval,
+        median.num_seconds()
+    );
+    match check {
+        SpacingCheck::Error => Err(anyhow::anyhow!(message)),
+        _ => {
+            tracing::warn!("{}", message);
+            Ok(())
+        }
+    }
+}
+
+/// Check that every intraday candle starts on a boundary of its interval.
+///
+/// Bars of up to an hour must start on a multiple of their length; 2h and 4h bars follow
+/// the exchange session, so they are only required to start on the hour. Daily and longer
+/// bars are stamped with the session open and are not checked.
+fn check_interval_alignment(
+    ticker: &impl MarketSymbol,
+    interval: Interval,
+    timestamps: &[DateTime<Utc>],
+    check: SpacingCheck,
+) -> Result<()> {
+    if check == SpacingCheck::Off {
+        return Ok(());
+    }
+
+    let Some(length) = interval_duration(interval).filter(|d| *d < chrono::Duration::days(1))
+    else {
+        return Ok(());
+    };
+    let boundary = length.min(chrono::Duration::hours(1)).num_seconds();
+
+    let misaligned = timestamps
+        .iter()
+        .filter(|t| t.timestamp_subsec_nanos() != 0 || t.timestamp() % boundary != 0)
+        .collect::<Vec<_>>();
+    let Some(first) = misaligned.first() else {
+        return Ok(());
+    };
+
+    let message = format!(
+        "{} candles for {}:{} are not aligned to the {} interval, first at {}",
+        misaligned.len(),
+        ticker.symbol(),
+        ticker.exchange(),
+        interval,
+        first
+    );
+    match check {
+        SpacingCheck::Error => Err(anyhow::anyhow!(message)),
+        _ => {
+            tracing::warn!("{}", message);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::daily_candles;
+    use chrono::{Duration, TimeZone};
+
+    async fn seeded_db(symbols: &[(&str, &str)]) -> Result<Database> {
+        let db = Database::new("sqlite::memory:").await?;
+        let tickers = symbols
+            .iter()
+            .map(|(symbol, exchange)| Ticker::new(*symbol, *exchange))
+            .collect::<Vec<_>>();
+        db.upsert_tickers(&tickers).await?;
+        Ok(db)
+    }
+
+    #[tokio::test]
+    async fn test_get_prices_multi() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        let daily = daily_candles(3);
+        let start = daily[0].timestamp;
+        let hourly = (0..5)
+            .map(|i| Candle {
+                timestamp: start + Duration::hours(i),
+                ..daily[0].clone()
+            })
+            .collect::<Vec<_>>();
+        db.upsert_prices(&ticker, Interval::OneDay, &daily).await?;
+        db.upsert_prices(&ticker, Interval::OneHour, &hourly)
+            .await?;
+
+        let series = db
+            .get_prices_multi(
+                &ticker,
+                &[Interval::OneHour, Interval::OneDay, Interval::OneWeek],
+                None,
+                None,
+            )
+            .await?;
+        let timestamps = |interval| {
+            series[&interval]
+                .iter()
+                .map(|c: &Candle| c.timestamp)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            timestamps(Interval::OneDay),
+            daily.iter().map(|c| c.timestamp).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            timestamps(Interval::OneHour),
+            hourly.iter().map(|c| c.timestamp).collect::<Vec<_>>()
+        );
+        assert!(series[&Interval::OneWeek].is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upsert_prices_validation_policy() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        let mut candles = daily_candles(2);
+        candles[1].volume = 0.0;
+
+        let strict = UpsertOptions {
+            validation: ValidationPolicy {
+                allow_zero_volume: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let report = db
+            .upsert_prices_with(&ticker, Interval::OneDay, &candles, &strict)
+            .await?;
+        assert_eq!(report.inserted, 1);
+        assert_eq!(
+            report.rejected,
+            vec![(
+                candles[1].timestamp.timestamp_millis(),
+                OhlcvError::ZeroVolume
+            )]
+        );
+
+        let report = db
+            .upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+        assert_eq!(report.inserted, 2);
+        assert!(report.rejected.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upsert_prices_keeps_last_duplicate() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        let mut candles = daily_candles(3);
+        let mut replayed = candles[1].clone();
+        replayed.close = 11.5;
+        candles.push(replayed);
+
+        let report = db
+            .upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+        assert_eq!(report.inserted, 3);
+
+        let stored = db
+            .get_prices()
+            .ticker(&ticker)
+            .interval(Interval::OneDay)
+            .call()
+            .await?;
+        assert_eq!(stored.len(), 3);
+        assert_eq!(stored[1].close, 11.5);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_duplicate_keeps_valid_bar() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        let mut candles = daily_candles(3);
+        let mut broken = candles[1].clone();
+        broken.high = broken.low - 1.0;
+        candles.push(broken);
+
+        let report = db
+            .upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+        assert_eq!(report.inserted, 3);
+        assert_eq!(report.rejected.len(), 1);
+
+        let stored = db
+            .get_prices()
+            .ticker(&ticker)
+            .interval(Interval::OneDay)
+            .call()
+            .await?;
+        assert_eq!(stored.len(), 3);
+        assert_eq!(stored[1].close, candles[1].close);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_duplicates_pass_strict_spacing() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        // Every bar twice would give a median spacing of zero if duplicates were counted
+        let candles = daily_candles(3)
+            .into_iter()
+            .flat_map(|candle| [candle.clone(), candle])
+            .collect::<Vec<_>>();
+
+        let report = db
+            .upsert_prices_strict(&ticker, Interval::OneDay, &candles)
+            .await?;
+        assert_eq!(report.inserted, 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_interval_mismatch_is_detected() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        // Daily bars labelled as minute bars
+        let candles = daily_candles(5);
+
+        let err = db
+            .upsert_prices_strict(&ticker, Interval::OneMinute, &candles)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("Interval mismatch for VCB:HOSE"),
+            "{err}"
+        );
+        assert_eq!(db.count_prices(&ticker, Interval::OneMinute).await?, 0);
+
+        // The default only warns
+        let report = db
+            .upsert_prices(&ticker, Interval::OneMinute, &candles)
+            .await?;
+        assert_eq!(report.inserted, 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_prepare_prices_keeps_the_last_valid_duplicate() -> Result<()> {
+        let ticker = Ticker::new("VCB", "HOSE");
+        let mut candles = daily_candles(3);
+        let mut revised = candles[2].clone();
+        revised.close = 12.5;
+        let mut invalid = candles[2].clone();
+        invalid.high = invalid.low - 1.0;
+        candles.extend([revised, invalid]);
+
+        let (kept, report) = prepare_prices(
+            &ticker,
+            Interval::OneDay,
+            &candles,
+            &UpsertOptions::strict(),
+        )?;
+        assert_eq!(kept.len(), 3);
+        assert_eq!(kept[2].close, 12.5);
+        assert_eq!(report.rejected.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upsert_prices_multi() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE"), ("FPT", "HOSE")]).await?;
+        let vcb = Ticker::new("VCB", "HOSE");
+        let fpt = Ticker::new("FPT", "HOSE");
+        let mut invalid = daily_candles(1);
+        invalid[0].high = invalid[0].low - 1.0;
+        // Every VCB bar twice, which must not count twice
+        let duplicated = daily_candles(3)
+            .into_iter()
+            .flat_map(|candle| [candle.clone(), candle])
+            .collect::<Vec<_>>();
+        let entries = vec![
+            (vcb.clone(), Interval::OneDay, duplicated),
+            (fpt.clone(), Interval::OneDay, daily_candles(1200)),
+            (fpt.clone(), Interval::OneWeek, invalid),
+        ];
+
+        let reports = db.upsert_prices_multi(&entries).await?;
+        let inserted = reports.iter().map(|r| r.inserted).collect::<Vec<_>>();
+        assert_eq!(inserted, [3, 1200, 0]);
+        assert_eq!(reports[2].rejected.len(), 1);
+        assert_eq!(db.count_prices(&vcb, Interval::OneDay).await?, 3);
+        assert_eq!(db.count_prices(&fpt, Interval::OneDay).await?, 1200);
+        assert_eq!(db.count_prices(&fpt, Interval::OneWeek).await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_refetch_keeps_adj_close() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        let mut candles = daily_candles(3);
+        db.upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+        db.execute("UPDATE OHLCV SET adj_close = close / 2").await?;
+
+        // A re-fetch revises the last close but carries no adjusted close
+        candles[2].close = 12.5;
+        let report = db
+            .upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+        assert_eq!(report.inserted, 3);
+
+        let stored = db
+            .get_prices()
+            .ticker(&ticker)
+            .interval(Interval::OneDay)
+            .call()
+            .await?;
+        assert_eq!(stored[2].close, 12.5);
+        assert_eq!(stored[2].adj_close, Some(6.0));
+        assert_eq!(stored[0].adj_close, Some(5.0));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_export_sql_dump_round_trip() -> Result<()> {
+        let db = Database::new("sqlite::memory:").await?;
+        let mut ticker = Ticker::new("VCB", "HOSE");
+        ticker.description =
+            Some("Joint Stock Commercial Bank for Foreign Trade of Viet Nam's".to_string());
+        ticker.founded = Some(1963);
+        db.upsert_tickers(std::slice::from_ref(&ticker)).await?;
+        let mut candles = daily_candles(3);
+        candles[1].close = 10.1 + 0.2;
+        db.upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+        db.execute("UPDATE OHLCV SET adj_close = close / 3").await?;
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("dump.sql");
+        let path = path.to_str().unwrap();
+        assert_eq!(db.export_sql_dump(path).await?, 4);
+
+        let restored = Database::new("sqlite::memory:").await?;
+        restored.execute(&std::fs::read_to_string(path)?).await?;
+
+        let stored = restored.get_ticker("VCB", "HOSE").await?.unwrap();
+        assert_eq!(stored.description, ticker.description);
+        assert_eq!(stored.founded, Some(1963));
+        let prices = restored
+            .get_prices()
+            .ticker(&ticker)
+            .interval(Interval::OneDay)
+            .call()
+            .await?;
+        assert_eq!(prices.len(), 3);
+        for (stored, original) in prices.iter().zip(&candles) {
+            assert_eq!(stored.timestamp, original.timestamp);
+            assert_eq!(stored.close, original.close);
+            assert_eq!(stored.adj_close, Some(original.close / 3.0));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_ticker_by_symbol_ci() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE"), ("FPT", "HOSE")]).await?;
+
+        assert!(db.get_ticker_by_symbol("vcb").await?.is_none());
+        let found = db.get_ticker_by_symbol_ci("vcb").await?.unwrap();
+        assert_eq!(
+            (found.symbol.as_str(), found.exchange.as_str()),
+            ("VCB", "HOSE")
+        );
+        assert!(db.get_ticker_by_symbol_ci("vc").await?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_tickers_by_symbols() -> Result<()> {
+        let db = seeded_db(&[
+            ("VCB", "HOSE"),
+            ("FPT", "HOSE"),
+            ("SHS", "HNX"),
+            ("ACB", "HOSE"),
+        ])
+        .await?;
+        let pair = |symbol: &str, exchange: &str| (symbol.to_string(), exchange.to_string());
+
+        let found = db
+            .get_tickers_by_symbols(&[
+                pair("VCB", "HOSE"),
+                pair("SHS", "HNX"),
+                pair("FPT", "HOSE"),
+                pair("SHS", "HOSE"),
+                pair("XYZ", "HOSE"),
+                pair("VCB", "HOSE"),
+            ])
+            .await?;
+        let keys = found
+            .iter()
+            .map(|t| (t.symbol.as_str(), t.exchange.as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(keys, [("FPT", "HOSE"), ("SHS", "HNX"), ("VCB", "HOSE")]);
+        assert!(db.get_tickers_by_symbols(&[]).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_transaction_rolls_back_on_error() -> Result<()> {
+        let db = seeded_db(&[]).await?;
+        let insert = "INSERT INTO TICKERS (symbol, exchange) VALUES (?, ?)";
+
+        let failed = db
+            .with_transaction(|tx| {
+                Box::pin(async move {
+                    sqlx::query(insert)
+                        .bind("VCB")
+                        .bind("HOSE")
+                        .execute(&mut **tx)
+                        .await?;
+                    Err::<(), _>(anyhow::anyhow!("failed after the first write"))
+                })
+            })
+            .await;
+        assert!(failed.is_err());
+        assert_eq!(db.get_ticker_count().await?, 0);
+
+        let inserted = db
+            .with_transaction(|tx| {
+                Box::pin(async move {
+                    for symbol in ["VCB", "FPT"] {
+                        sqlx::query(insert)
+                            .bind(symbol)
+                            .bind("HOSE")
+                            .execute(&mut **tx)
+                            .await?;
+                    }
+                    Ok(2)
+                })
+            })
+            .await?;
+        assert_eq!(inserted, 2);
+        assert_eq!(db.get_ticker_count().await?, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_transaction_composes_upserts() -> Result<()> {
+        let db = seeded_db(&[]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+
+        // Daily bars declared hourly fail the strict spacing check, so the tickers are
+        // rolled back too
+        let (tickers, candles) = (vec![ticker.clone()], daily_candles(3));
+        let failed = db
+            .with_transaction(move |tx| {
+                Box::pin(async move {
+                    Database::upsert_tickers_in(tx, &tickers).await?;
+                    Database::upsert_prices_in(
+                        tx,
+                        &tickers[0],
+                        Interval::OneHour,
+                        &candles,
+                        &UpsertOptions::strict(),
+                    )
+                    .await
+                })
+            })
+            .await;
+        assert!(failed.is_err());
+        assert_eq!(db.get_ticker_count().await?, 0);
+
+        let (tickers, candles) = (vec![ticker.clone()], daily_candles(3));
+        let report = db
+            .with_transaction(move |tx| {
+                Box::pin(async move {
+                    Database::upsert_tickers_in(tx, &tickers).await?;
+                    Database::upsert_prices_in(
+                        tx,
+                        &tickers[0],
+                        Interval::OneDay,
+                        &candles,
+                        &UpsertOptions::default(),
+                    )
+                    .await
+                })
+            })
+            .await?;
+        assert_eq!(report.inserted, 3);
+        assert_eq!(db.get_ticker_count().await?, 1);
+        let stored = db
+            .get_prices()
+            .ticker(&ticker)
+            .interval(Interval::OneDay)
+            .call()
+            .await?;
+        assert_eq!(stored.len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_health_check() -> Result<()> {
+        let db = Database::new("sqlite::memory:").await?;
+        let status = db.health_check().await?;
+        assert!(status.is_healthy(), "{status:?}");
+        assert!(status.pool_size >= 1);
+
+        let blank = Database::builder()
+            .url("sqlite::memory:")
+            .migrate(false)
+            .connect()
             .await?;
-    
+        let status = blank.health_check().await?;
+        assert!(!status.is_healthy());
+        assert_eq!(status.missing_tables.len(), REQUIRED_TABLES.len());
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_schema_version_after_migrations() -> Result<()> {
+        let db = Database::new("sqlite::memory:").await?;
+        let latest = sqlx::migrate!("./migrations")
+            .iter()
+            .map(|m| m.version)
+            .max()
+            .unwrap();
+
+        assert!(db.schema_version().await? > 0);
+        assert_eq!(db.schema_version().await?, latest);
+
+        let blank = Database::builder()
+            .url("sqlite::memory:")
+            .migrate(false)
+            .connect()
+            .await?;
+        assert_eq!(blank.schema_version().await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upsert_tickers_fills_country_from_exchange() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE"), ("BTCUSDT", "BINANCE")]).await?;
+
+        let vcb = db.get_ticker("VCB", "HOSE").await?.unwrap();
+        assert_eq!(vcb.country.as_deref(), Some("VN"));
+        let btc = db.get_ticker("BTCUSDT", "BINANCE").await?.unwrap();
+        assert_eq!(btc.country, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_last_candle_timestamp() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+
+        let last = db
+            .get_last_candle_timestamp("VCB", "HOSE", Interval::OneDay)
+            .await?;
+        assert!(last.is_none());
+
+        let candles = daily_candles(3);
+        db.upsert_prices(&Ticker::new("VCB", "HOSE"), Interval::OneDay, &candles)
+            .await?;
+
+        let last = db
+            .get_last_candle_timestamp("VCB", "HOSE", Interval::OneDay)
+            .await?;
+        assert_eq!(last, Some(candles[2].timestamp));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_prices_paginated() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        let candles = daily_candles(10);
+        db.upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (page, next) = db
+                .get_prices_paginated()
+                .ticker(&ticker)
+                .interval(Interval::OneDay)
+                .maybe_after(cursor)
+                .limit(3)
+                .call()
+                .await?;
+            seen.extend(page.into_iter().map(|c| c.timestamp));
+            match next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        let expected = candles.iter().map(|c| c.timestamp).collect::<Vec<_>>();
+        assert_eq!(seen, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_prices_matches_get_prices() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        db.upsert_prices(&ticker, Interval::OneDay, &daily_candles(5))
+            .await?;
+
+        let streamed = db
+            .stream_prices(&ticker, Interval::OneDay)
+            .try_collect::<Vec<_>>()
+            .await?;
+        let collected = db
+            .get_prices()
+            .ticker(&ticker)
+            .interval(Interval::OneDay)
+            .call()
+            .await?;
+
+        assert!(candles_approx_eq(&streamed, &collected, 0.0));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upsert_prices_append_only() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        db.upsert_prices(&ticker, Interval::OneDay, &daily_candles(3))
+            .await?;
+
+        let mut incoming = daily_candles(4);
+        for candle in &mut incoming {
+            candle.close += 0.25;
+        }
+        let options = UpsertOptions {
+            mode: InsertMode::Ignore,
+            ..Default::default()
+        };
+        let inserted = db
+            .upsert_prices_with(&ticker, Interval::OneDay, &incoming, &options)
+            .await?;
+        assert_eq!(inserted.inserted, 1);
+
+        let stored = db
+            .get_prices()
+            .ticker(&ticker)
+            .interval(Interval::OneDay)
+            .call()
+            .await?;
+        assert_eq!(stored.len(), 4);
+        assert_eq!(stored[0].close, daily_candles(1)[0].close);
+        assert_eq!(stored[3].close, incoming[3].close);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upsert_prices_is_all_or_nothing() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        // Fail inside the second 1000-row chunk
+        db.execute(
+            "CREATE TRIGGER fail_late BEFORE INSERT ON OHLCV WHEN NEW.close > 1200 \
+             BEGIN SELECT RAISE(ABORT, 'boom'); END",
+        )
+        .await?;
+
+        let result = db
+            .upsert_prices(&ticker, Interval::OneDay, &daily_candles(1500))
+            .await;
+        assert!(result.is_err());
+        let last = db
+            .get_last_candle_timestamp("VCB", "HOSE", Interval::OneDay)
+            .await?;
+        assert!(last.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_search_index_for() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE"), ("FPT", "HOSE")]).await?;
+        let ticker = Ticker {
+            description: Some("Vietcombank".to_string()),
+            ..Ticker::new("VCB", "HOSE")
+        };
+        db.upsert_tickers(std::slice::from_ref(&ticker)).await?;
+
+        db.update_search_index_for(&[ticker]).await?;
+        db.update_search_index_for(&[Ticker::new("MISSING", "HOSE")])
+            .await?;
+
+        let found = db.search_tickers("Vietcombank", false, None, None).await?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].symbol, "VCB");
+        assert_eq!(db.search_tickers("FPT", false, None, None).await?.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_search_index_after_delete() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE"), ("FPT", "HOSE"), ("HPG", "HOSE")]).await?;
+        assert!(db.delete_ticker("FPT", "HOSE").await?);
+
+        db.rebuild_search_index().await?;
+
+        assert!(
+            db.search_tickers("FPT", false, None, None)
+                .await?
+                .is_empty()
+        );
+        for symbol in ["VCB", "HPG"] {
+            let found = db.search_tickers(symbol, false, None, None).await?;
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].symbol, symbol);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_ticker_by_symbol_is_deterministic() -> Result<()> {
+        let db = seeded_db(&[("VCB", "UPCOM"), ("VCB", "HOSE"), ("VCB", "HNX")]).await?;
+
+        let listings = db.get_tickers_by_symbol("VCB").await?;
+        let exchanges = listings
+            .iter()
+            .map(|t| t.exchange.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(exchanges, ["HNX", "HOSE", "UPCOM"]);
+
+        assert_eq!(
+            db.get_ticker_by_symbol("VCB").await?.unwrap().exchange,
+            "HNX"
+        );
+        let preferred = db
+            .get_ticker_by_symbol_preferring("VCB", &["NYSE", "HOSE"])
+            .await?;
+        assert_eq!(preferred.unwrap().exchange, "HOSE");
+        assert!(db.get_ticker_by_symbol("FPT").await?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_exchanges() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE"), ("FPT", "HOSE"), ("SHS", "HNX")]).await?;
+
+        let exchanges = db.get_exchanges().await?;
+        assert_eq!(
+            exchanges,
+            vec![("HOSE".to_string(), 2), ("HNX".to_string(), 1)]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_import_tickers_round_trip() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE"), ("FPT", "HOSE"), ("SHS", "HNX")]).await?;
+        let dir = tempfile::tempdir()?;
+        let parquet_path = dir.path().join("import_tickers.parquet");
+        let csv_path = dir.path().join("import_tickers.csv");
+        let parquet_path = parquet_path.to_str().unwrap();
+        let csv_path = csv_path.to_str().unwrap();
+
+        let tickers = db.get_all_tickers().await?;
+        crate::utils::format::save_parquet(
+            tickers.clone(),
+            parquet_path,
+            crate::utils::format::default_parquet_compression(),
+        )?;
+        crate::utils::format::save_csv(tickers, csv_path)?;
+
+        db.execute("DELETE FROM TICKERS").await?;
+        assert_eq!(db.get_ticker_count().await?, 0);
+        let imported = db.import_tickers_from_parquet(parquet_path).await;
+        let parquet_count = db.get_ticker_count().await?;
+
+        db.execute("DELETE FROM TICKERS").await?;
+        let csv_imported = db.import_tickers_from_csv(csv_path).await;
+        let csv_count = db.get_ticker_count().await?;
+
+        assert_eq!(imported?, 3);
+        assert_eq!(parquet_count, 3);
+        assert_eq!(csv_imported?, 3);
+        assert_eq!(csv_count, 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_prices_with_meta() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        let candles = daily_candles(5);
+        db.upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+
+        let series = db
+            .get_prices_with_meta()
+            .ticker(&ticker)
+            .interval(Interval::OneDay)
+            .start(candles[1].timestamp)
+            .call()
+            .await?;
+        assert_eq!(series.symbol, "VCB");
+        assert_eq!(series.count, 4);
+        assert_eq!(series.first, Some(candles[1].timestamp));
+        assert_eq!(series.last, Some(candles[4].timestamp));
+
+        let empty = db
+            .get_prices_with_meta()
+            .ticker(&Ticker::new("VCB", "HOSE"))
+            .interval(Interval::OneWeek)
+            .call()
+            .await?;
+        assert_eq!(empty.count, 0);
+        assert!(empty.first.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_timestamps_round_trip_exactly() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        let timestamp =
+            Utc.with_ymd_and_hms(2024, 1, 2, 2, 0, 0).unwrap() + Duration::milliseconds(250);
+        let candle = Candle {
+            timestamp,
+            ..daily_candles(1)[0].clone()
+        };
+        db.upsert_prices(&ticker, Interval::OneDay, &[candle])
+            .await?;
+
+        let stored = db
+            .get_prices()
+            .ticker(&ticker)
+            .interval(Interval::OneDay)
+            .start(timestamp)
+            .end(timestamp)
+            .call()
+            .await?;
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].timestamp, timestamp);
+
+        let last = db
+            .get_last_candle_timestamp("VCB", "HOSE", Interval::OneDay)
+            .await?;
+        assert_eq!(last, Some(timestamp));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_corporate_actions_ordered_by_date() -> Result<()> {
+        use crate::finance::corporate::CorporateActionKind;
+
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let date = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        let dividend = CorporateAction {
+            date: date(7, 1),
+            ratio: 1200.0,
+            kind: CorporateActionKind::Dividend,
+        };
+        let split = CorporateAction {
+            date: date(3, 15),
+            ratio: 2.0,
+            kind: CorporateActionKind::Split,
+        };
+        db.upsert_corporate_action("VCB", "HOSE", &dividend).await?;
+        db.upsert_corporate_action("VCB", "HOSE", &split).await?;
+
+        let actions = db.get_corporate_actions("VCB", "HOSE").await?;
+        assert_eq!(actions, vec![split, dividend]);
+        assert!(db.get_corporate_actions("FPT", "HOSE").await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_builder_configures_pool() -> Result<()> {
+        let db = Database::builder()
+            .url("sqlite::memory:")
+            .max_connections(3)
+            .busy_timeout(std::time::Duration::from_secs(10))
+            .wal(true)
+            .connect()
+            .await?;
+
+        assert_eq!(db.get_pool().await.options().get_max_connections(), 3);
+        assert_eq!(db.get_ticker_count().await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_upserts_all_land() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("concurrent_upserts.sqlite3");
+        let db = Database::new(&format!("sqlite://{}?mode=rwc", path.display())).await?;
+
+        let tickers = (0..20)
+            .map(|i| Ticker::new(format!("T{i:02}"), "HOSE".to_string()))
+            .collect::<Vec<_>>();
+        db.upsert_tickers(&tickers).await?;
+
+        let tasks = tickers
+            .into_iter()
+            .map(|ticker| {
+                let db = db.clone();
+                tokio::spawn(async move {
+                    db.upsert_prices(&ticker, Interval::OneDay, &daily_candles(50))
+                        .await
+                })
+            })
+            .collect::<Vec<_>>();
+        for task in tasks {
+            assert_eq!(task.await??.inserted, 50);
+        }
+
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM OHLCV")
+            .fetch_one(db.get_pool().await)
+            .await?;
+        db.close().await?;
+
+        assert_eq!(stored, 20 * 50);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_vacuum_and_analyze_populated_db() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        db.upsert_prices(&ticker, Interval::OneDay, &daily_candles(30))
+            .await?;
+
+        db.vacuum().await?;
+        db.analyze().await?;
+
+        let candles = db
+            .get_prices()
+            .ticker(&ticker)
+            .interval(Interval::OneDay)
+            .call()
+            .await?;
+        assert_eq!(candles.len(), 30);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_price_stats() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        let candles = daily_candles(3);
+        db.upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+
+        let stats = db.get_price_stats(&ticker, Interval::OneDay).await?;
+        assert_eq!(
+            stats,
+            PriceStats {
+                count: 3,
+                first: Some(candles[0].timestamp),
+                last: Some(candles[2].timestamp),
+                min_close: Some(10.0),
+                max_close: Some(12.0),
+                total_volume: 3000.0,
+            }
+        );
+
+        let empty = db.get_price_stats(&ticker, Interval::OneWeek).await?;
+        assert_eq!(empty, PriceStats::default());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_prices_middle_range() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        let candles = daily_candles(5);
+        db.upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+
+        let deleted = db
+            .delete_prices(
+                &ticker,
+                Interval::OneDay,
+                Some(candles[1].timestamp),
+                Some(candles[3].timestamp),
+            )
+            .await?;
+        assert_eq!(deleted, 3);
+
+        let remaining = db
+            .get_prices()
+            .ticker(&ticker)
+            .interval(Interval::OneDay)
+            .call()
+            .await?;
+        let timestamps = remaining.iter().map(|c| c.timestamp).collect::<Vec<_>>();
+        assert_eq!(timestamps, vec![candles[0].timestamp, candles[4].timestamp]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search_tickers_pages_do_not_overlap() -> Result<()> {
+        let db = seeded_db(&[("FPT", "HOSE")]).await?;
+        let banks = ["ACB", "BID", "CTG", "MBB", "TCB", "VCB", "VPB"]
+            .into_iter()
+            .map(|symbol| Ticker {
+                description: Some(format!("{symbol} Commercial Bank")),
+                ..Ticker::new(symbol, "HOSE")
+            })
+            .collect::<Vec<_>>();
+        db.upsert_tickers(&banks).await?;
+
+        assert_eq!(db.count_search_tickers("bank", false).await?, 7);
+
+        let mut seen = Vec::new();
+        for page in 0..4 {
+            let results = db
+                .search_tickers("bank", false, Some(2), Some(page * 2))
+                .await?;
+            assert!(results.len() <= 2);
+            seen.extend(results.into_iter().map(|t| t.symbol));
+        }
+        let mut unique = seen.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(seen.len(), 7);
+        assert_eq!(unique.len(), 7);
+
+        let again = db.search_tickers("bank", false, Some(2), Some(2)).await?;
+        assert_eq!(
+            again.iter().map(|t| t.symbol.clone()).collect::<Vec<_>>(),
+            seen[2..4]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search_tickers_prefix_and_reserved_characters() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE"), ("FPT", "HOSE")]).await?;
+
+        assert!(db.search_tickers("VC", false, None, None).await?.is_empty());
+        let found = db.search_tickers("VC", true, None, None).await?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].symbol, "VCB");
+
+        for query in ["VCB\"", "\"", "FPT OR (", "NEAR(", "symbol:*", "   "] {
+            db.search_tickers(query, true, None, None).await?;
+            db.count_search_tickers(query, false).await?;
+        }
+        assert_eq!(
+            sanitize_fts_query(r#"a"b OR"#, true).as_deref(),
+            Some(r#""a""b" "OR"*"#)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search_tickers_by_several_fields() -> Result<()> {
+        let db = seeded_db(&[]).await?;
+        let tickers = vec![
+            Ticker {
+                industry: Some("Information Technology Services".to_string()),
+                ..Ticker::new("FPT", "HOSE")
+            },
+            Ticker {
+                sector: Some("Technology".to_string()),
+                ..Ticker::new("CMG", "HOSE")
+            },
+            Ticker {
+                description: Some("Technology bank".to_string()),
+                ..Ticker::new("TCB", "HOSE")
+            },
+        ];
+        db.upsert_tickers(&tickers).await?;
+
+        let symbols = |found: Vec<Ticker>| {
+            let mut symbols = found.into_iter().map(|t| t.symbol).collect::<Vec<_>>();
+            symbols.sort();
+            symbols
+        };
+        let industry = db
+            .search_tickers_by_field(&["industry"], "technology", None, None)
+            .await?;
+        assert_eq!(symbols(industry), ["FPT"]);
+        let both = db
+            .search_tickers_by_field(&["industry", "sector"], "technology", None, None)
+            .await?;
+        assert_eq!(symbols(both), ["CMG", "FPT"]);
+        let on_hnx = db
+            .search_tickers_by_field(&["industry", "sector"], "technology", Some("HNX"), None)
+            .await?;
+        assert!(on_hnx.is_empty());
+
+        let invalid = db
+            .search_tickers_by_field(&["sector", "symbol) OR 1=1 --"], "technology", None, None)
+            .await;
+        assert!(invalid.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_weighted_search_boosts_symbol_matches() -> Result<()> {
+        let db = seeded_db(&[]).await?;
+        let tickers = vec![
+            Ticker {
+                description: Some("Holding company and major shareholder of VCB".to_string()),
+                ..Ticker::new("ABC", "HOSE")
+            },
+            Ticker {
+                description: Some("Vietcombank".to_string()),
+                ..Ticker::new("VCB", "HOSE")
+            },
+        ];
+        db.upsert_tickers(&tickers).await?;
+
+        let results = db.search_tickers_weighted("VCB", None).await?;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0.symbol, "VCB");
+        assert_eq!(results[1].0.symbol, "ABC");
+        assert!(results[0].1 > results[1].1);
+
+        // Exchange is not among the weighted columns
+        assert!(db.search_tickers_weighted("HOSE", None).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_all_tickers_matches_get_all_tickers() -> Result<()> {
+        let db = seeded_db(&[]).await?;
+        let tickers = (0..TICKER_PAGE_SIZE * 2 + 5)
+            .map(|i| {
+                Ticker::new(
+                    format!("T{i:04}"),
+                    ["HNX", "HOSE"][i as usize % 2].to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        db.upsert_tickers(&tickers).await?;
+
+        let key = |t: &Ticker| (t.symbol.clone(), t.exchange.clone());
+        let streamed = db
+            .stream_all_tickers()
+            .map_ok(|t| key(&t))
+            .try_collect::<Vec<_>>()
+            .await?;
+        let mut all = db
+            .get_all_tickers()
+            .await?
+            .iter()
+            .map(key)
+            .collect::<Vec<_>>();
+        all.sort();
+
+        assert_eq!(streamed.len(), tickers.len());
+        assert_eq!(streamed, all);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_count_prices() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE"), ("FPT", "HOSE")]).await?;
+        let vcb = Ticker::new("VCB", "HOSE");
+        let fpt = Ticker::new("FPT", "HOSE");
+        db.upsert_prices(&vcb, Interval::OneDay, &daily_candles(5))
+            .await?;
+        db.upsert_prices(&fpt, Interval::OneDay, &daily_candles(3))
+            .await?;
+
+        assert_eq!(db.count_prices(&vcb, Interval::OneDay).await?, 5);
+        assert_eq!(db.count_prices(&fpt, Interval::OneDay).await?, 3);
+        assert_eq!(db.count_prices(&vcb, Interval::OneWeek).await?, 0);
+        assert_eq!(db.count_all_prices().await?, 8);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_strict_upsert_rejects_off_grid_bars() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 2, 0, 0).unwrap();
+        let mut candles = daily_candles(5)
+            .into_iter()
+            .enumerate()
+            .map(|(i, candle)| Candle {
+                timestamp: start + Duration::minutes(i as i64),
+                ..candle
+            })
+            .collect::<Vec<_>>();
+        candles[3].timestamp += Duration::seconds(30);
+
+        assert!(
+            db.upsert_prices_strict(&ticker, Interval::OneMinute, &candles)
+                .await
+                .is_err()
+        );
+        assert_eq!(db.count_prices(&ticker, Interval::OneMinute).await?, 0);
+
+        let stored = db
+            .upsert_prices(&ticker, Interval::OneMinute, &candles)
+            .await?;
+        assert_eq!(stored.inserted, 5);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upsert_report_categorizes_rejected_bars() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        let mut candles = daily_candles(6);
+        candles[1].volume = -1.0;
+        candles[2].high = candles[2].low - 1.0;
+        candles[3].close = f64::NAN;
+        candles[4].volume = -5.0;
+
+        let report = db
+            .upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+
+        assert_eq!(report.inserted, 2);
+        assert_eq!(
+            report.rejected,
+            vec![
+                (
+                    candles[1].timestamp.timestamp_millis(),
+                    OhlcvError::NegativeVolume
+                ),
+                (
+                    candles[2].timestamp.timestamp_millis(),
+                    OhlcvError::HighLessThanLow
+                ),
+                (
+                    candles[3].timestamp.timestamp_millis(),
+                    OhlcvError::NonFinite("close")
+                ),
+                (
+                    candles[4].timestamp.timestamp_millis(),
+                    OhlcvError::NegativeVolume
+                ),
+            ]
+        );
+        let by_kind = report.rejected_by_kind();
+        assert_eq!(by_kind[&OhlcvError::NegativeVolume], 2);
+        assert_eq!(by_kind[&OhlcvError::HighLessThanLow], 1);
+        assert_eq!(by_kind[&OhlcvError::NonFinite("close")], 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_prices_batch() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        let candles = daily_candles(5);
+        db.upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+
+        let batch = db
+            .get_prices_batch(&ticker, Interval::OneDay, Some(candles[1].timestamp), None)
+            .await?;
+
+        assert_eq!(batch.schema(), candle_schema());
+        assert_eq!(batch.num_rows(), 4);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_latest_prices() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE"), ("FPT", "HOSE"), ("SHS", "HNX")]).await?;
+        for (symbol, exchange, count) in [("VCB", "HOSE", 5), ("FPT", "HOSE", 3)] {
+            db.upsert_prices(
+                &Ticker::new(symbol, exchange),
+                Interval::OneDay,
+                &daily_candles(count),
+            )
+            .await?;
+        }
+
+        let latest = db.get_latest_prices(Interval::OneDay).await?;
+
+        assert_eq!(latest.len(), 2);
+        for (ticker, candle) in &latest {
+            let stats = db.get_price_stats(ticker, Interval::OneDay).await?;
+            assert_eq!(Some(candle.timestamp), stats.last);
+        }
+        assert_eq!(latest[0].0.symbol, "FPT");
+        assert!(db.get_latest_prices(Interval::OneWeek).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upsert_indicators() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        let candles = daily_candles(25);
+        db.upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+
+        let indicators = crate::finance::ta::compute_indicator(
+            "sma_20",
+            &candles,
+            &crate::finance::calendar::HoseCalendar,
+        )?;
+        assert_eq!(
+            db.upsert_indicators(&ticker, Interval::OneDay, &indicators)
+                .await?,
+            6
+        );
+        // Recomputing replaces rather than duplicates
+        db.upsert_indicators(&ticker, Interval::OneDay, &indicators)
+            .await?;
+
+        let stored = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM TECHNICAL_INDICATORS WHERE indicator_type = 'sma_20'",
+        )
+        .fetch_one(db.get_pool().await)
+        .await?;
+        assert_eq!(stored, 6);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ml_features_round_trip() -> Result<()> {
+        let db = seeded_db(&[("VCB", "HOSE")]).await?;
+        let ticker = Ticker::new("VCB", "HOSE");
+        let features = crate::finance::features::build_ml_features(&daily_candles(25));
+        assert!(features[0].rsi.is_none());
+
+        assert_eq!(
+            db.upsert_ml_features(&ticker, Interval::OneDay, &features)
+                .await?,
+            25
+        );
+        db.upsert_ml_features(&ticker, Interval::OneDay, &features)
+            .await?;
+
+        let stored = db
+            .get_ml_features(&ticker, Interval::OneDay, None, None)
+            .await?;
+        assert_eq!(stored, features);
+
+        let tail = db
+            .get_ml_features(
+                &ticker,
+                Interval::OneDay,
+                Some(features[20].timestamp),
+                None,
+            )
+            .await?;
+        assert_eq!(tail, features[20..]);
+        Ok(())
+    }
 }
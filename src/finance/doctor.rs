@@ -0,0 +1,236 @@
+use sqlx::SqlitePool;
+use std::path::PathBuf;
+use std::time::Duration;
+use tradingview::{UserCookies, get_quote_token};
+
+/// Host probed to decide whether TradingView is reachable.
+const SOURCE_ADDR: &str = "data.tradingview.com:443";
+const SOURCE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// The check does not apply, e.g. no cookies file was given
+    Skip,
+}
+
+/// Outcome of one `doctor` check.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+
+    fn from_result(name: &'static str, result: anyhow::Result<String>) -> Self {
+        match result {
+            Ok(detail) => Self::new(name, CheckStatus::Pass, detail),
+            Err(e) => Self::new(name, CheckStatus::Fail, e.to_string()),
+        }
+    }
+
+    pub fn failed(&self) -> bool {
+        self.status == CheckStatus::Fail
+    }
+}
+
+/// Run every check in order. The database checks fail when `database_url` is missing and
+/// the cookies check is skipped when no cookies file is given.
+pub async fn run_checks(
+    database_url: Option<&str>,
+    cookies_path: Option<&str>,
+) -> Vec<CheckResult> {
+    vec![
+        check_database(database_url).await,
+        check_writable(database_url),
+        check_cookies(cookies_path).await,
+        check_source().await,
+    ]
+}
+
+/// The database accepts connections and every bundled migration has been applied.
+pub async fn check_database(database_url: Option<&str>) -> CheckResult {
+    let Some(database_url) = database_url else {
+        return CheckResult::new("database", CheckStatus::Fail, "DATABASE_URL is not set");
+    };
+
+    let result = async {
+        let pool = SqlitePool::connect(database_url).await?;
+        let applied = applied_migrations(&pool).await;
+        pool.close().await;
+        let applied = applied?;
+
+        let pending = sqlx::migrate!("./migrations")
+            .iter()
+            .filter(|m| !applied.contains(&m.version))
+            .count();
+        if pending > 0 {
+            return Err(anyhow::anyhow!(
+                "{} migrations pending; run any database command to apply them",
+                pending
+            ));
+        }
+        Ok(format!("connected, {} migrations applied", applied.len()))
+    }
+    .await;
+
+    CheckResult::from_result("database", result)
+}
+
+/// Versions of the migrations applied to `pool`.
+///
+/// A missing migrations table means nothing has been applied yet; any other failure, such
+/// as a locked database, is an error rather than an empty list.
+async fn applied_migrations(pool: &SqlitePool) -> anyhow::Result<Vec<i64>> {
+    let tracked: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')",
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| anyhow::anyhow!("cannot read the schema: {}", e))?;
+    if !tracked {
+        return Ok(Vec::new());
+    }
+
+    sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = 1")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("cannot read applied migrations: {}", e))
+}
+
+/// The SQLite file behind `database_url` can be opened for writing.
+pub fn check_writable(database_url: Option<&str>) -> CheckResult {
+    let Some(path) = database_url.and_then(sqlite_file_path) else {
+        return CheckResult::new(
+            "writable",
+            CheckStatus::Skip,
+            "not a file-backed SQLite database",
+        );
+    };
+
+    let result = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .map(|_| format!("{} is writable", path.display()))
+        .map_err(|e| anyhow::anyhow!("cannot write {}: {}", path.display(), e));
+    CheckResult::from_result("writable", result)
+}
+
+/// The cookies file parses and its session still yields a quote token.
+pub async fn check_cookies(cookies_path: Option<&str>) -> CheckResult {
+    let Some(cookies_path) = cookies_path else {
+        return CheckResult::new("cookies", CheckStatus::Skip, "no cookies file given");
+    };
+
+    let result = async {
+        let json = std::fs::read_to_string(cookies_path)
+            .map_err(|e| anyhow::anyhow!("cannot read {}: {}", cookies_path, e))?;
+        let user: UserCookies = serde_json::from_str(&json)
+            .map_err(|e| anyhow::anyhow!("cannot parse {}: {}", cookies_path, e))?;
+        get_quote_token(&user)
+            .await
+            .map_err(|e| anyhow::anyhow!("session is not valid: {}", e))?;
+        Ok("session is valid".to_string())
+    }
+    .await;
+
+    CheckResult::from_result("cookies", result)
+}
+
+/// TradingView accepts TCP connections within a few seconds.
+pub async fn check_source() -> CheckResult {
+    let result =
+        match tokio::time::timeout(SOURCE_TIMEOUT, tokio::net::TcpStream::connect(SOURCE_ADDR))
+            .await
+        {
+            Ok(Ok(_)) => Ok(format!("{SOURCE_ADDR} is reachable")),
+            Ok(Err(e)) => Err(anyhow::anyhow!("cannot reach {}: {}", SOURCE_ADDR, e)),
+            Err(_) => Err(anyhow::anyhow!(
+                "timed out connecting to {} after {}s",
+                SOURCE_ADDR,
+                SOURCE_TIMEOUT.as_secs()
+            )),
+        };
+    CheckResult::from_result("source", result)
+}
+
+/// Path of the database file for `sqlite:` URLs, or `None` for in-memory databases.
+fn sqlite_file_path(database_url: &str) -> Option<PathBuf> {
+    let rest = database_url
+        .strip_prefix("sqlite://")
+        .or_else(|| database_url.strip_prefix("sqlite:"))?;
+    let path = rest.split('?').next()?;
+    (!path.is_empty() && !path.starts_with(":memory:")).then(|| PathBuf::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_file_path() {
+        assert_eq!(
+            sqlite_file_path("sqlite://data.sqlite3?mode=rwc"),
+            Some(PathBuf::from("data.sqlite3"))
+        );
+        assert_eq!(
+            sqlite_file_path("sqlite:data.sqlite3"),
+            Some(PathBuf::from("data.sqlite3"))
+        );
+        assert_eq!(sqlite_file_path("sqlite::memory:"), None);
+        assert_eq!(sqlite_file_path("postgres://localhost/db"), None);
+    }
+
+    #[tokio::test]
+    async fn test_checks_without_configuration() {
+        assert!(check_database(None).await.failed());
+        assert_eq!(check_writable(None).status, CheckStatus::Skip);
+        assert_eq!(check_cookies(None).await.status, CheckStatus::Skip);
+
+        let missing = check_cookies(Some("does/not/exist.json")).await;
+        assert!(missing.failed());
+    }
+
+    #[tokio::test]
+    async fn test_check_database_reports_query_errors() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let url = format!(
+            "sqlite://{}?mode=rwc",
+            dir.path().join("db.sqlite3").display()
+        );
+
+        let blank = check_database(Some(&url)).await;
+        assert!(blank.failed());
+        assert!(
+            blank.detail.contains("migrations pending"),
+            "{}",
+            blank.detail
+        );
+
+        // A migrations table sqlx can't read must not pass as "nothing applied"
+        let pool = SqlitePool::connect(&url).await?;
+        sqlx::query("CREATE TABLE _sqlx_migrations (version INTEGER)")
+            .execute(&pool)
+            .await?;
+        pool.close().await;
+
+        let broken = check_database(Some(&url)).await;
+        assert!(broken.failed());
+        assert!(
+            broken.detail.contains("cannot read applied migrations"),
+            "{}",
+            broken.detail
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use crate::finance::{
+    models::{Candle, MlFeatures},
+    ta,
+};
+
+/// Window used for the rolling volatility of returns.
+const VOLATILITY_WINDOW: usize = 20;
+
+/// Build one `MlFeatures` row per candle.
+///
+/// Indicators use RSI(14), MFI(14), SMA(20) and EMA(12) on close. `price_change_pct` is the
+/// close-over-prior-close change in percent and `volatility_pct` the rolling standard
+/// deviation of those changes over 20 bars. Fields whose window isn't full yet are `None`.
+pub fn build_ml_features(candles: &[Candle]) -> Vec<MlFeatures> {
+    let closes = candles.iter().map(|c| c.close).collect::<Vec<_>>();
+    let rsi = ta::rsi(&closes, 14);
+    let mfi = ta::mfi(candles, 14);
+    let sma_20 = ta::sma(&closes, 20);
+    let ema_12 = ta::ema(&closes, 12);
+
+    let price_change_pct = simple_returns(candles)
+        .into_iter()
+        .map(|r| r.map(|r| r * 100.0))
+        .collect::<Vec<_>>();
+    let volatility_pct = rolling_std_opt(&price_change_pct, VOLATILITY_WINDOW);
+
+    candles
+        .iter()
+        .enumerate()
+        .map(|(i, candle)| MlFeatures {
+            timestamp: candle.timestamp,
+            open: candle.open,
+            high: candle.high,
+            low: candle.low,
+            close: candle.close,
+            volume: candle.volume,
+            rsi: rsi[i],
+            mfi: mfi[i],
+            sma_20: sma_20[i],
+            ema_12: ema_12[i],
+            price_change_pct: price_change_pct[i],
+            volatility_pct: volatility_pct[i],
+        })
+        .collect()
+}
+
+/// Close-to-close returns, `close / prior close - 1`, one per candle.
+///
+/// The first bar has no prior close and is `None`, as is any bar where either close is
+/// zero, negative or not finite.
+pub fn simple_returns(candles: &[Candle]) -> Vec<Option<f64>> {
+    close_to_close(candles, |ratio| ratio - 1.0)
+}
+
+/// Close-to-close log returns, `ln(close / prior close)`, one per candle.
+///
+/// Missing values follow [`simple_returns`]. Unlike simple returns, log returns add up: the
+/// sum over a run of bars is the log return from its first close to its last.
+pub fn log_returns(candles: &[Candle]) -> Vec<Option<f64>> {
+    close_to_close(candles, f64::ln)
+}
+
+fn close_to_close(candles: &[Candle], f: impl Fn(f64) -> f64) -> Vec<Option<f64>> {
+    let usable = |price: f64| price.is_finite() && price > 0.0;
+    std::iter::once(None)
+        .chain(candles.windows(2).map(|w| {
+            (usable(w[0].close) && usable(w[1].close)).then(|| f(w[1].close / w[0].close))
+        }))
+        .take(candles.len())
+        .collect()
+}
+
+/// Rolling standard deviation that is `None` whenever the window contains a missing value.
+fn rolling_std_opt(values: &[Option<f64>], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; values.len()];
+    for (i, window) in values.windows(period).enumerate() {
+        if let Some(window) = window.iter().copied().collect::<Option<Vec<_>>>() {
+            out[i + period - 1] = ta::rolling_std(&window, period)[period - 1];
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::daily_series;
+
+    fn series(count: usize) -> Vec<Candle> {
+        // Alternate +2 / -1 steps so both gains and losses occur
+        let closes = (0..count)
+            .map(|i| 100.0 + (i / 2) as f64 + if i % 2 == 1 { 2.0 } else { 0.0 })
+            .collect::<Vec<_>>();
+        daily_series(&closes)
+            .into_iter()
+            .map(|candle| Candle {
+                high: candle.close + 1.0,
+                low: candle.close - 1.0,
+                ..candle
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_returns() {
+        let mut candles = series(4);
+        for (candle, close) in candles.iter_mut().zip([100.0, 110.0, 99.0, 0.0]) {
+            candle.close = close;
+        }
+
+        let simple = simple_returns(&candles);
+        assert_eq!(simple.len(), 4);
+        assert!(simple[0].is_none());
+        assert!((simple[1].unwrap() - 0.1).abs() < 1e-12);
+        assert!((simple[2].unwrap() + 0.1).abs() < 1e-12);
+        assert!(simple[3].is_none());
+        assert!(simple_returns(&[]).is_empty());
+
+        let log = log_returns(&candles);
+        assert!(log[0].is_none() && log[3].is_none());
+        assert!((log[1].unwrap() - 1.1f64.ln()).abs() < 1e-12);
+        let total = log[1].unwrap() + log[2].unwrap();
+        assert!((total - (99.0f64 / 100.0).ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_build_ml_features_warm_up() {
+        let candles = series(30);
+        let features = build_ml_features(&candles);
+
+        assert_eq!(features.len(), 30);
+        assert!(features[0].price_change_pct.is_none());
+        assert!((features[1].price_change_pct.unwrap() - 2.0).abs() < 1e-9);
+        assert!(features[13].rsi.is_none() && features[14].rsi.is_some());
+        assert!(features[10].ema_12.is_none() && features[11].ema_12.is_some());
+        assert!(features[18].sma_20.is_none() && features[19].sma_20.is_some());
+        assert!(features[19].volatility_pct.is_none() && features[20].volatility_pct.is_some());
+
+        let expected_sma = candles[10..30].iter().map(|c| c.close).sum::<f64>() / 20.0;
+        assert!((features[29].sma_20.unwrap() - expected_sma).abs() < 1e-9);
+    }
+}
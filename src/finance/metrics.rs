@@ -0,0 +1,39 @@
+use std::sync::OnceLock;
+
+/// Observability hooks called from the fetch drivers.
+///
+/// Every method has a no-op default, so an implementation only overrides what its backend
+/// cares about. Install one with [`set_metrics`] at startup.
+pub trait Metrics: Send + Sync {
+    /// A TradingView request for one ticker finished.
+    fn record_fetch(&self, _symbol: &str, _exchange: &str, _success: bool) {}
+
+    /// A failed operation is about to be retried.
+    fn record_retry(&self, _operation: &str) {}
+
+    /// Rows were written to the database for one ticker.
+    fn record_rows(&self, _symbol: &str, _exchange: &str, _rows: u64) {}
+}
+
+/// Metrics sink that discards everything, used until [`set_metrics`] is called.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
+
+static METRICS: OnceLock<Box<dyn Metrics>> = OnceLock::new();
+
+/// Install the process-wide metrics sink. Fails if one is already installed.
+pub fn set_metrics(metrics: impl Metrics + 'static) -> anyhow::Result<()> {
+    METRICS
+        .set(Box::new(metrics))
+        .map_err(|_| anyhow::anyhow!("A metrics sink is already installed"))
+}
+
+/// The installed metrics sink, or [`NoopMetrics`] when none is installed.
+pub fn metrics() -> &'static dyn Metrics {
+    match METRICS.get() {
+        Some(metrics) => metrics.as_ref(),
+        None => &NoopMetrics,
+    }
+}
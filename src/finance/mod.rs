@@ -1,4 +1,23 @@
+pub mod analytics;
+pub mod calendar;
+pub mod checkpoint;
 pub mod cmd;
+pub mod corporate;
+#[cfg(feature = "datafusion")]
+pub mod datafusion;
 pub mod db;
+pub mod doctor;
+pub mod features;
+pub mod metrics;
 pub mod models;
+#[cfg(feature = "postgres")]
+pub mod pg;
+pub mod progress;
+pub mod quality;
+pub mod rate_limit;
+pub mod resample;
+pub mod retry;
 pub mod ta;
+pub mod token;
+
+pub use cmd::fetch_tickers;
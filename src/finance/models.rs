@@ -1,5 +1,7 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use tradingview::Interval;
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, Default)]
 pub struct Ticker {
@@ -14,14 +16,94 @@ pub struct Ticker {
     pub founded: Option<i64>,
 }
 
+/// Longest symbol or exchange name accepted by [`Ticker::validate`].
+pub const MAX_TICKER_FIELD_LEN: usize = 32;
+
+/// Why a ticker failed [`Ticker::validate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TickerError {
+    /// The named field is empty or only whitespace
+    Empty(&'static str),
+    /// The named field is longer than [`MAX_TICKER_FIELD_LEN`]
+    TooLong(&'static str, usize),
+}
+
+impl std::fmt::Display for TickerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TickerError::Empty(field) => write!(f, "{field} is empty"),
+            TickerError::TooLong(field, len) => write!(
+                f,
+                "{field} is {len} characters long, the limit is {MAX_TICKER_FIELD_LEN}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TickerError {}
+
+impl Ticker {
+    /// Check that the symbol and exchange are non-blank and at most
+    /// [`MAX_TICKER_FIELD_LEN`] characters. Returns the first problem found.
+    pub fn validate(&self) -> Result<(), TickerError> {
+        for (field, value) in [("symbol", &self.symbol), ("exchange", &self.exchange)] {
+            if value.trim().is_empty() {
+                return Err(TickerError::Empty(field));
+            }
+            let len = value.chars().count();
+            if len > MAX_TICKER_FIELD_LEN {
+                return Err(TickerError::TooLong(field, len));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// ISO country code of an exchange's home market, e.g. `VN` for `HOSE`.
+///
+/// Returns `None` for unknown exchanges and for venues without a single home country,
+/// such as crypto exchanges.
+pub fn exchange_to_country(exchange: &str) -> Option<&'static str> {
+    let country = match exchange.trim().to_ascii_uppercase().as_str() {
+        "HOSE" | "HSX" | "HNX" | "UPCOM" => "VN",
+        "NYSE" | "NASDAQ" | "AMEX" | "NYSEARCA" => "US",
+        "LSE" => "GB",
+        "TSE" => "JP",
+        "HKEX" => "HK",
+        "SSE" | "SZSE" => "CN",
+        "KRX" => "KR",
+        "SGX" => "SG",
+        "SET" => "TH",
+        "IDX" => "ID",
+        _ => return None,
+    };
+    Some(country)
+}
+
+/// Earliest founding year accepted by [`normalize_founded`].
+const MIN_FOUNDED_YEAR: i64 = 1800;
+
+/// Map a raw founding year to `None` when it is missing (zero) or implausible.
+///
+/// Years from 1800 through next year are kept; TradingView reports `0` when it has no
+/// founding date.
+pub fn normalize_founded(raw: i64) -> Option<i64> {
+    let latest = i64::from(Utc::now().year()) + 1;
+    (MIN_FOUNDED_YEAR..=latest).contains(&raw).then_some(raw)
+}
+
 impl From<tradingview::Symbol> for Ticker {
     fn from(symbol: tradingview::Symbol) -> Self {
+        // Symbol lists leave the country blank for some exchanges
+        let country = Some(symbol.country_code)
+            .filter(|code| !code.is_empty())
+            .or_else(|| exchange_to_country(&symbol.exchange).map(str::to_string));
         Self {
             symbol: symbol.symbol,
             exchange: symbol.exchange,
             description: Some(symbol.description),
             currency: Some(symbol.currency_code),
-            country: Some(symbol.country_code),
+            country,
             market_type: Some(symbol.market_type),
             industry: None,
             sector: None,
@@ -70,6 +152,64 @@ pub struct Candle {
     pub low: f64,
     pub close: f64,
     pub volume: f64,
+    /// Close adjusted for later splits and dividends, see [`crate::finance::corporate`]
+    #[serde(default)]
+    pub adj_close: Option<f64>,
+}
+
+impl Candle {
+    /// Copy any OHLCV bar into an owned `Candle`.
+    pub fn from_ohlcv(bar: &impl tradingview::OHLCV) -> Self {
+        Self {
+            timestamp: bar.datetime(),
+            open: bar.open(),
+            high: bar.high(),
+            low: bar.low(),
+            close: bar.close(),
+            volume: bar.volume(),
+            adj_close: None,
+        }
+    }
+
+    /// Field-wise comparison tolerant to float noise; NaN is considered equal to NaN.
+    pub fn approx_eq(&self, other: &Candle, epsilon: f64) -> bool {
+        self.timestamp == other.timestamp
+            && approx_eq_f64(self.open, other.open, epsilon)
+            && approx_eq_f64(self.high, other.high, epsilon)
+            && approx_eq_f64(self.low, other.low, epsilon)
+            && approx_eq_f64(self.close, other.close, epsilon)
+            && approx_eq_f64(self.volume, other.volume, epsilon)
+            && match (self.adj_close, other.adj_close) {
+                (Some(a), Some(b)) => approx_eq_f64(a, b, epsilon),
+                (a, b) => a.is_none() && b.is_none(),
+            }
+    }
+
+    /// Total ordering by timestamp, for use with `sort_by`.
+    pub fn cmp_by_timestamp(&self, other: &Candle) -> std::cmp::Ordering {
+        self.timestamp.cmp(&other.timestamp)
+    }
+}
+
+/// Compare two candle series element-wise with [`Candle::approx_eq`].
+pub fn candles_approx_eq(a: &[Candle], b: &[Candle], epsilon: f64) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.approx_eq(y, epsilon))
+}
+
+/// Combine two candle series into one sorted by timestamp with one candle per timestamp.
+///
+/// Where both series have a candle at the same timestamp the one from `new` wins. Neither
+/// input has to be sorted.
+pub fn merge_candles(existing: Vec<Candle>, new: Vec<Candle>) -> Vec<Candle> {
+    let mut merged = std::collections::BTreeMap::new();
+    for candle in existing.into_iter().chain(new) {
+        merged.insert(candle.timestamp, candle);
+    }
+    merged.into_values().collect()
+}
+
+fn approx_eq_f64(a: f64, b: f64, epsilon: f64) -> bool {
+    (a.is_nan() && b.is_nan()) || a == b || (a - b).abs() <= epsilon
 }
 
 impl tradingview::OHLCV for Candle {
@@ -106,6 +246,216 @@ impl tradingview::OHLCV for Candle {
     }
 }
 
+/// Why an OHLCV bar failed [`validate_ohlcv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OhlcvError {
+    /// The named field is NaN or infinite
+    NonFinite(&'static str),
+    /// The named price is zero or negative
+    NonPositivePrice(&'static str),
+    NegativeVolume,
+    HighLessThanLow,
+    /// High is below the open or the close
+    HighBelowBody,
+    /// Low is above the open or the close
+    LowAboveBody,
+    /// Volume is zero and the policy doesn't allow it
+    ZeroVolume,
+    /// Open, high, low and close are equal and the policy doesn't allow it
+    FlatBar,
+}
+
+impl std::fmt::Display for OhlcvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OhlcvError::NonFinite(field) => write!(f, "{field} is not a finite number"),
+            OhlcvError::NonPositivePrice(field) => write!(f, "{field} is not positive"),
+            OhlcvError::NegativeVolume => write!(f, "volume is negative"),
+            OhlcvError::HighLessThanLow => write!(f, "high is less than low"),
+            OhlcvError::HighBelowBody => write!(f, "high is below open or close"),
+            OhlcvError::LowAboveBody => write!(f, "low is above open or close"),
+            OhlcvError::ZeroVolume => write!(f, "volume is zero"),
+            OhlcvError::FlatBar => write!(f, "open, high, low and close are equal"),
+        }
+    }
+}
+
+impl std::error::Error for OhlcvError {}
+
+/// Which OHLCV bars validation accepts.
+///
+/// Non-finite values and negative volumes are always rejected. The default is what
+/// [`validate_ohlcv`] checks: zero volume and flat bars are accepted, prices must be
+/// positive and the high and low must enclose the open and close.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationPolicy {
+    /// Accept bars with a volume of zero, e.g. untraded sessions
+    pub allow_zero_volume: bool,
+    /// Accept bars whose open, high, low and close are all equal
+    pub allow_flat_ohlc: bool,
+    /// Reject zero and negative prices
+    pub require_positive_prices: bool,
+    /// Reject bars whose high is below their low
+    pub require_high_ge_low: bool,
+    /// Reject bars whose high or low doesn't enclose the open and close
+    pub require_range_encloses_body: bool,
+}
+
+impl Default for ValidationPolicy {
+    fn default() -> Self {
+        Self {
+            allow_zero_volume: true,
+            allow_flat_ohlc: true,
+            require_positive_prices: true,
+            require_high_ge_low: true,
+            require_range_encloses_body: true,
+        }
+    }
+}
+
+impl ValidationPolicy {
+    /// Check a bar against this policy. Returns the first problem found.
+    pub fn check(
+        &self,
+        open: f64,
+        high: f64,
+        low: f64,
+        close: f64,
+        volume: f64,
+    ) -> Result<(), OhlcvError> {
+        let fields = [
+            ("open", open),
+            ("high", high),
+            ("low", low),
+            ("close", close),
+            ("volume", volume),
+        ];
+        if let Some((field, _)) = fields.iter().find(|(_, value)| !value.is_finite()) {
+            return Err(OhlcvError::NonFinite(field));
+        }
+        if self.require_positive_prices
+            && let Some((field, _)) = fields[..4].iter().find(|(_, value)| *value <= 0.0)
+        {
+            return Err(OhlcvError::NonPositivePrice(field));
+        }
+        if volume < 0.0 {
+            return Err(OhlcvError::NegativeVolume);
+        }
+        if volume == 0.0 && !self.allow_zero_volume {
+            return Err(OhlcvError::ZeroVolume);
+        }
+        if self.require_high_ge_low && high < low {
+            return Err(OhlcvError::HighLessThanLow);
+        }
+        if self.require_range_encloses_body {
+            if high < open || high < close {
+                return Err(OhlcvError::HighBelowBody);
+            }
+            if low > open || low > close {
+                return Err(OhlcvError::LowAboveBody);
+            }
+        }
+        if !self.allow_flat_ohlc && open == high && high == low && low == close {
+            return Err(OhlcvError::FlatBar);
+        }
+        Ok(())
+    }
+
+    /// [`Self::check`] for any OHLCV bar.
+    pub fn validate(&self, bar: &impl tradingview::OHLCV) -> Result<(), OhlcvError> {
+        self.check(bar.open(), bar.high(), bar.low(), bar.close(), bar.volume())
+    }
+}
+
+/// Check that a bar has finite, positive prices, a non-negative volume, and a high and low
+/// that enclose the open and close. Returns the first problem found.
+pub fn validate_ohlcv(
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+) -> Result<(), OhlcvError> {
+    ValidationPolicy::default().check(open, high, low, close, volume)
+}
+
+/// Whether [`validate_ohlcv`] accepts the bar.
+pub fn is_valid_ohlcv(open: f64, high: f64, low: f64, close: f64, volume: f64) -> bool {
+    validate_ohlcv(open, high, low, close, volume).is_ok()
+}
+
+/// [`validate_ohlcv`] for any OHLCV bar, e.g. a `Candle` from another source.
+pub fn validate_candle(bar: &impl tradingview::OHLCV) -> Result<(), OhlcvError> {
+    validate_ohlcv(bar.open(), bar.high(), bar.low(), bar.close(), bar.volume())
+}
+
+/// Candles of one ticker and interval together with their count and span.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PriceSeries {
+    pub symbol: String,
+    pub exchange: String,
+    pub interval: String,
+    pub count: usize,
+    /// Timestamp of the first candle, `None` when the series is empty
+    pub first: Option<DateTime<Utc>>,
+    /// Timestamp of the last candle, `None` when the series is empty
+    pub last: Option<DateTime<Utc>>,
+    pub candles: Vec<Candle>,
+}
+
+impl PriceSeries {
+    /// Wrap candles sorted by timestamp.
+    pub fn new(ticker: &Ticker, interval: Interval, candles: Vec<Candle>) -> Self {
+        Self {
+            symbol: ticker.symbol.clone(),
+            exchange: ticker.exchange.clone(),
+            interval: interval_to_db_str(interval).into_owned(),
+            count: candles.len(),
+            first: candles.first().map(|c| c.timestamp),
+            last: candles.last().map(|c| c.timestamp),
+            candles,
+        }
+    }
+}
+
+/// Aggregate summary of the stored candles of one ticker and interval.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow, Default)]
+pub struct PriceStats {
+    pub count: i64,
+    /// Timestamp of the first candle, `None` when nothing is stored
+    pub first: Option<DateTime<Utc>>,
+    /// Timestamp of the last candle, `None` when nothing is stored
+    pub last: Option<DateTime<Utc>>,
+    pub min_close: Option<f64>,
+    pub max_close: Option<f64>,
+    pub total_volume: f64,
+}
+
+/// Readiness snapshot returned by `Database::health_check`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct HealthStatus {
+    /// Connections currently open in the pool
+    pub pool_size: u32,
+    /// Open connections that are not in use
+    pub idle_connections: usize,
+    /// Required tables that don't exist, e.g. because migrations haven't run
+    pub missing_tables: Vec<String>,
+}
+
+impl HealthStatus {
+    pub fn is_healthy(&self) -> bool {
+        self.missing_tables.is_empty()
+    }
+}
+
+/// Per-ticker outcome of a concurrent price fetch.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FetchReport {
+    pub successful: Vec<Ticker>,
+    /// Failed tickers with the error message of their last attempt
+    pub failed: Vec<(Ticker, String)>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, Default)]
 pub struct Indicator {
     pub timestamp: DateTime<Utc>,
@@ -114,7 +464,7 @@ pub struct Indicator {
     pub metadata: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
 pub struct MlFeatures {
     pub timestamp: DateTime<Utc>,
     pub open: f64,
@@ -129,3 +479,277 @@ pub struct MlFeatures {
     pub price_change_pct: Option<f64>,
     pub volatility_pct: Option<f64>,
 }
+
+/// Nominal duration of a bar for the given interval.
+///
+/// Monthly bars are approximated as 30 days. Returns `None` for intervals without a fixed length.
+pub fn interval_duration(interval: Interval) -> Option<Duration> {
+    match interval {
+        Interval::OneMinute => Some(Duration::minutes(1)),
+        Interval::FiveMinutes => Some(Duration::minutes(5)),
+        Interval::FifteenMinutes => Some(Duration::minutes(15)),
+        Interval::ThirtyMinutes => Some(Duration::minutes(30)),
+        Interval::OneHour => Some(Duration::hours(1)),
+        Interval::TwoHours => Some(Duration::hours(2)),
+        Interval::FourHours => Some(Duration::hours(4)),
+        Interval::OneDay => Some(Duration::days(1)),
+        Interval::OneWeek => Some(Duration::weeks(1)),
+        Interval::OneMonth => Some(Duration::days(30)),
+        _ => None,
+    }
+}
+
+/// Stable codes under which intervals are stored in the `interval` column.
+///
+/// These match what `tradingview`'s `Display` produced when the data was first written and
+/// must never change, or existing rows become unreachable.
+const INTERVAL_DB_CODES: [(Interval, &str); 10] = [
+    (Interval::OneMinute, "1"),
+    (Interval::FiveMinutes, "5"),
+    (Interval::FifteenMinutes, "15"),
+    (Interval::ThirtyMinutes, "30"),
+    (Interval::OneHour, "60"),
+    (Interval::TwoHours, "120"),
+    (Interval::FourHours, "240"),
+    (Interval::OneDay, "1D"),
+    (Interval::OneWeek, "1W"),
+    (Interval::OneMonth, "1M"),
+];
+
+/// Canonical database encoding of `interval`, independent of `tradingview`'s `Display`.
+///
+/// Intervals outside the supported set fall back to their `Display` code.
+pub fn interval_to_db_str(interval: Interval) -> Cow<'static, str> {
+    INTERVAL_DB_CODES
+        .iter()
+        .find(|(known, _)| *known == interval)
+        .map(|(_, code)| Cow::Borrowed(*code))
+        .unwrap_or_else(|| Cow::Owned(interval.to_string()))
+}
+
+/// Parse an interval code written by [`interval_to_db_str`].
+pub fn interval_from_db_str(code: &str) -> anyhow::Result<Interval> {
+    INTERVAL_DB_CODES
+        .iter()
+        .find(|(_, known)| *known == code)
+        .map(|(interval, _)| *interval)
+        .ok_or_else(|| anyhow::anyhow!("Unknown interval code '{}'", code))
+}
+
+/// Median distance between consecutive distinct timestamps, or `None` with fewer than two gaps.
+pub fn median_spacing(timestamps: &[DateTime<Utc>]) -> Option<Duration> {
+    let mut sorted = timestamps.to_vec();
+    sorted.sort();
+
+    let mut deltas = sorted
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .filter(|d| *d > Duration::zero())
+        .collect::<Vec<_>>();
+    if deltas.len() < 2 {
+        return None;
+    }
+
+    deltas.sort();
+    Some(deltas[deltas.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn candle(day: u32, close: f64) -> Candle {
+        Candle {
+            timestamp: Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: f64::NAN,
+            adj_close: None,
+        }
+    }
+
+    #[test]
+    fn test_candle_approx_eq() {
+        let a = candle(1, 10.0);
+        let b = candle(1, 10.0 + 1e-12);
+
+        assert!(a.approx_eq(&b, 1e-9));
+        assert!(!a.approx_eq(&candle(1, 10.1), 1e-9));
+        assert!(!a.approx_eq(&candle(2, 10.0), 1e-9));
+    }
+
+    #[test]
+    fn test_candle_sort_by_timestamp() {
+        let mut candles = vec![candle(3, 3.0), candle(1, 1.0), candle(2, 2.0)];
+        candles.sort_by(Candle::cmp_by_timestamp);
+
+        assert!(candles_approx_eq(
+            &candles,
+            &[candle(1, 1.0), candle(2, 2.0), candle(3, 3.0)],
+            1e-9
+        ));
+    }
+
+    #[test]
+    fn test_median_spacing() {
+        let day = |d| Utc.with_ymd_and_hms(2024, 1, d, 0, 0, 0).unwrap();
+
+        // Duplicates and ordering don't matter, a weekend gap doesn't move the median
+        let timestamps = [day(5), day(1), day(2), day(2), day(3), day(8)];
+        assert_eq!(median_spacing(&timestamps), Some(Duration::days(1)));
+        assert_eq!(median_spacing(&[day(1), day(2)]), None);
+        assert_eq!(
+            interval_duration(Interval::FourHours),
+            Some(Duration::hours(4))
+        );
+    }
+
+    #[test]
+    fn test_interval_db_codes_are_stable() -> anyhow::Result<()> {
+        // Rows already on disk use these exact codes
+        assert_eq!(interval_to_db_str(Interval::OneMinute), "1");
+        assert_eq!(interval_to_db_str(Interval::OneHour), "60");
+        assert_eq!(interval_to_db_str(Interval::OneDay), "1D");
+        assert_eq!(interval_to_db_str(Interval::OneMonth), "1M");
+
+        for (interval, code) in INTERVAL_DB_CODES {
+            assert_eq!(interval_from_db_str(code)?, interval);
+        }
+        assert!(interval_from_db_str("1d").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_ohlcv_failure_modes() {
+        assert_eq!(validate_ohlcv(10.0, 11.0, 9.0, 10.5, 100.0), Ok(()));
+        assert_eq!(validate_ohlcv(10.0, 10.0, 10.0, 10.0, 0.0), Ok(()));
+
+        let cases = [
+            (
+                (f64::NAN, 11.0, 9.0, 10.5, 100.0),
+                OhlcvError::NonFinite("open"),
+            ),
+            (
+                (10.0, f64::INFINITY, 9.0, 10.5, 100.0),
+                OhlcvError::NonFinite("high"),
+            ),
+            (
+                (10.0, 11.0, 9.0, 10.5, f64::NAN),
+                OhlcvError::NonFinite("volume"),
+            ),
+            (
+                (10.0, 11.0, 0.0, 10.5, 100.0),
+                OhlcvError::NonPositivePrice("low"),
+            ),
+            (
+                (10.0, 11.0, 9.0, -1.0, 100.0),
+                OhlcvError::NonPositivePrice("close"),
+            ),
+            ((10.0, 11.0, 9.0, 10.5, -5.0), OhlcvError::NegativeVolume),
+            ((10.0, 9.0, 11.0, 10.0, 100.0), OhlcvError::HighLessThanLow),
+            ((10.0, 10.2, 9.0, 10.5, 100.0), OhlcvError::HighBelowBody),
+            ((10.0, 11.0, 10.1, 10.5, 100.0), OhlcvError::LowAboveBody),
+        ];
+        for ((open, high, low, close, volume), expected) in cases {
+            assert_eq!(
+                validate_ohlcv(open, high, low, close, volume),
+                Err(expected)
+            );
+            assert!(!is_valid_ohlcv(open, high, low, close, volume));
+        }
+
+        assert_eq!(
+            validate_candle(&candle(2, 10.0)),
+            Err(OhlcvError::NonFinite("volume"))
+        );
+    }
+
+    #[test]
+    fn test_validation_policy() {
+        let strict = ValidationPolicy {
+            allow_zero_volume: false,
+            allow_flat_ohlc: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            strict.check(10.0, 11.0, 9.0, 10.5, 0.0),
+            Err(OhlcvError::ZeroVolume)
+        );
+        assert_eq!(
+            strict.check(10.0, 10.0, 10.0, 10.0, 100.0),
+            Err(OhlcvError::FlatBar)
+        );
+
+        let lenient = ValidationPolicy {
+            require_positive_prices: false,
+            require_high_ge_low: false,
+            require_range_encloses_body: false,
+            ..Default::default()
+        };
+        assert_eq!(lenient.check(0.0, 9.0, 11.0, 10.0, 100.0), Ok(()));
+        assert_eq!(
+            lenient.check(0.0, 9.0, 11.0, 10.0, -1.0),
+            Err(OhlcvError::NegativeVolume)
+        );
+    }
+
+    #[test]
+    fn test_merge_candles_prefers_new() {
+        let existing = vec![candle(3, 3.0), candle(1, 1.0), candle(2, 2.0)];
+        let new = vec![candle(4, 40.0), candle(2, 20.0)];
+
+        assert!(candles_approx_eq(
+            &merge_candles(existing, new),
+            &[
+                candle(1, 1.0),
+                candle(2, 20.0),
+                candle(3, 3.0),
+                candle(4, 40.0)
+            ],
+            1e-9
+        ));
+    }
+
+    #[test]
+    fn test_normalize_founded() {
+        assert_eq!(normalize_founded(0), None);
+        assert_eq!(normalize_founded(1976), Some(1976));
+        assert_eq!(normalize_founded(1799), None);
+        assert_eq!(normalize_founded(9999), None);
+    }
+
+    #[test]
+    fn test_exchange_to_country() {
+        assert_eq!(exchange_to_country("HOSE"), Some("VN"));
+        assert_eq!(exchange_to_country("upcom"), Some("VN"));
+        assert_eq!(exchange_to_country("NASDAQ"), Some("US"));
+        assert_eq!(exchange_to_country("BINANCE"), None);
+    }
+
+    #[test]
+    fn test_ticker_validate() {
+        let ticker = |symbol: &str, exchange: &str| Ticker {
+            symbol: symbol.to_string(),
+            exchange: exchange.to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(ticker("FPT", "HOSE").validate(), Ok(()));
+        assert_eq!(
+            ticker("", "HOSE").validate(),
+            Err(TickerError::Empty("symbol"))
+        );
+        assert_eq!(
+            ticker("FPT", " ").validate(),
+            Err(TickerError::Empty("exchange"))
+        );
+        assert_eq!(
+            ticker(&"X".repeat(40), "HOSE").validate(),
+            Err(TickerError::TooLong("symbol", 40))
+        );
+        assert_eq!(TickerError::Empty("symbol").to_string(), "symbol is empty");
+    }
+}
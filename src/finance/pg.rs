@@ -0,0 +1,231 @@
+use crate::finance::db::{InsertMode, UpsertOptions, UpsertReport, prepare_prices};
+use crate::finance::models::*;
+use anyhow::Result;
+use sqlx::PgPool;
+use sqlx::postgres::PgPoolOptions;
+use tradingview::{Interval, MarketSymbol, OHLCV};
+
+/// Rows per `INSERT` statement. Postgres allows at most 65535 bind parameters per query.
+const BATCH_SIZE: usize = 1000;
+
+/// Postgres-backed store for tickers and OHLCV bars, enabled with the `postgres` feature.
+/// Clones share the pool.
+///
+/// A parallel path to [`Database`](crate::finance::db::Database) for multi-user deployments
+/// that only covers ticker and price upserts so far; search, indicators, corporate actions
+/// and ML features still need SQLite. The schema lives in `migrations_pg/`. Postgres handles
+/// concurrent writers itself, so writes are not queued.
+#[derive(Debug, Clone)]
+pub struct PgDatabase {
+    pool: PgPool,
+}
+
+impl PgDatabase {
+    /// Connect to `database_url` and run the Postgres migrations.
+    pub async fn new(database_url: &str) -> Result<Self> {
+        let pool = PgPoolOptions::new().connect(database_url).await?;
+        sqlx::migrate!("./migrations_pg").run(&pool).await?;
+        Ok(Self { pool })
+    }
+
+    pub async fn get_ticker(&self, symbol: &str, exchange: &str) -> Result<Option<Ticker>> {
+        let row = sqlx::query_as::<_, Ticker>(
+            "SELECT symbol, exchange, description, currency, country, market_type, industry, sector, founded FROM TICKERS WHERE symbol = $1 AND exchange = $2",
+        )
+        .bind(symbol)
+        .bind(exchange)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Insert or update tickers, matching `Database::upsert_tickers`.
+    pub async fn upsert_tickers(&self, tickers: &[Ticker]) -> Result<u64> {
+        if tickers.is_empty() {
+            return Ok(0);
+        }
+
+        let mut total_affected = 0u64;
+        let mut tx = self.pool.begin().await?;
+        for chunk in tickers.chunks(BATCH_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                "INSERT INTO TICKERS (symbol, exchange, description, currency, country, market_type, industry, sector, founded) ",
+            );
+
+            query_builder.push_values(chunk, |mut b, ticker| {
+                b.push_bind(&ticker.symbol)
+                    .push_bind(&ticker.exchange)
+                    .push_bind(&ticker.description)
+                    .push_bind(&ticker.currency)
+                    .push_bind(
+                        ticker
+                            .country
+                            .as_deref()
+                            .or_else(|| exchange_to_country(&ticker.exchange)),
+                    )
+                    .push_bind(&ticker.market_type)
+                    .push_bind(&ticker.industry)
+                    .push_bind(&ticker.sector)
+                    .push_bind(ticker.founded.and_then(normalize_founded));
+            });
+
+            query_builder.push(" ON CONFLICT (symbol, exchange) DO UPDATE SET ");
+            query_builder.push("description = excluded.description, ");
+            query_builder.push("currency = excluded.currency, ");
+            query_builder.push("country = excluded.country, ");
+            query_builder.push("market_type = excluded.market_type, ");
+            query_builder.push("industry = excluded.industry, ");
+            query_builder.push("sector = excluded.sector, ");
+            query_builder.push("founded = excluded.founded, ");
+            query_builder.push("updated_at = CURRENT_TIMESTAMP");
+
+            let result = query_builder.build().execute(&mut *tx).await?;
+            total_affected += result.rows_affected();
+        }
+        tx.commit().await?;
+
+        Ok(total_affected)
+    }
+
+    /// Insert or overwrite OHLCV bars, matching `Database::upsert_prices`.
+    pub async fn upsert_prices(
+        &self,
+        ticker: &impl MarketSymbol,
+        interval: Interval,
+        prices: &[impl OHLCV],
+    ) -> Result<UpsertReport> {
+        self.upsert_prices_with(ticker, interval, prices, &UpsertOptions::default())
+            .await
+    }
+
+    /// Insert OHLCV bars applying `options`, matching `Database::upsert_prices_with`.
+    ///
+    /// Bars rejected by `options.validation` are skipped and listed in the returned report,
+    /// and of several bars sharing a timestamp only the last one is written, since one
+    /// `ON CONFLICT DO UPDATE` statement can't update the same row twice.
+    pub async fn upsert_prices_with(
+        &self,
+        ticker: &impl MarketSymbol,
+        interval: Interval,
+        prices: &[impl OHLCV],
+        options: &UpsertOptions,
+    ) -> Result<UpsertReport> {
+        let (valid_prices, mut report) = prepare_prices(ticker, interval, prices, options)?;
+        if valid_prices.is_empty() {
+            return Ok(report);
+        }
+
+        let mut tx = self.pool.begin().await?;
+        for chunk in valid_prices.chunks(BATCH_SIZE) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                "INSERT INTO OHLCV (symbol, exchange, interval, timestamp, open, high, low, close, volume) ",
+            );
+
+            query_builder.push_values(chunk, |mut b, price| {
+                b.push_bind(ticker.symbol())
+                    .push_bind(ticker.exchange())
+                    .push_bind(interval_to_db_str(interval))
+                    .push_bind(price.datetime())
+                    .push_bind(price.open())
+                    .push_bind(price.high())
+                    .push_bind(price.low())
+                    .push_bind(price.close())
+                    .push_bind(price.volume());
+            });
+
+            query_builder.push(" ON CONFLICT (symbol, exchange, interval, timestamp) ");
+            match options.mode {
+                InsertMode::Replace => {
+                    query_builder.push("DO UPDATE SET ");
+                    query_builder.push("open = excluded.open, ");
+                    query_builder.push("high = excluded.high, ");
+                    query_builder.push("low = excluded.low, ");
+                    query_builder.push("close = excluded.close, ");
+                    query_builder.push("volume = excluded.volume");
+                }
+                InsertMode::Ignore => {
+                    query_builder.push("DO NOTHING");
+                }
+            }
+
+            let result = query_builder.build().execute(&mut *tx).await?;
+            report.inserted += result.rows_affected();
+        }
+        tx.commit().await?;
+
+        Ok(report)
+    }
+
+    pub async fn count_prices(&self, ticker: &Ticker, interval: Interval) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM OHLCV WHERE symbol = $1 AND exchange = $2 AND interval = $3",
+        )
+        .bind(&ticker.symbol)
+        .bind(&ticker.exchange)
+        .bind(interval_to_db_str(interval))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::daily_candles;
+    use testcontainers_modules::{postgres::Postgres, testcontainers::runners::AsyncRunner};
+
+    /// Run with `cargo test --features postgres -- --ignored` on a machine with Docker.
+    #[tokio::test]
+    #[ignore = "needs Docker"]
+    async fn test_pg_upserts() -> Result<()> {
+        let container = Postgres::default().start().await?;
+        let url = format!(
+            "postgres://postgres:postgres@{}:{}/postgres",
+            container.get_host().await?,
+            container.get_host_port_ipv4(5432).await?
+        );
+        let db = PgDatabase::new(&url).await?;
+
+        let ticker = Ticker {
+            symbol: "VCB".to_string(),
+            exchange: "HOSE".to_string(),
+            founded: Some(1963),
+            ..Default::default()
+        };
+        assert_eq!(db.upsert_tickers(std::slice::from_ref(&ticker)).await?, 1);
+        let stored = db.get_ticker("VCB", "HOSE").await?.unwrap();
+        assert_eq!(stored.country.as_deref(), Some("VN"));
+        assert_eq!(stored.founded, Some(1963));
+
+        let candles = daily_candles(3);
+        let report = db
+            .upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+        assert_eq!(report.inserted, 3);
+
+        // Re-upserting overwrites instead of duplicating
+        db.upsert_prices(&ticker, Interval::OneDay, &candles)
+            .await?;
+        assert_eq!(db.count_prices(&ticker, Interval::OneDay).await?, 3);
+
+        // A timestamp repeated within one statement keeps the last bar
+        let mut duplicated = candles.clone();
+        duplicated.push(Candle {
+            close: 12.5,
+            ..candles[2].clone()
+        });
+        let mut invalid = candles[0].clone();
+        invalid.high = invalid.low - 1.0;
+        duplicated.push(invalid);
+        let report = db
+            .upsert_prices(&ticker, Interval::OneDay, &duplicated)
+            .await?;
+        assert_eq!(report.inserted, 3);
+        assert_eq!(report.rejected.len(), 1);
+        assert_eq!(db.count_prices(&ticker, Interval::OneDay).await?, 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use indicatif::ProgressBar;
+
+/// Observer for the batch fetchers in [`crate::finance::cmd`].
+///
+/// Implement it to drive a custom UI. `()` ignores every callback, and
+/// [`indicatif::ProgressBar`] tracks `done` out of `total`.
+pub trait FetchProgress: Send + Sync {
+    /// Called once per ticker when it finishes, with `done` counting from 1 up to `total`.
+    fn on_ticker_done(&self, done: usize, total: usize, ok: bool) {
+        let _ = (done, total, ok);
+    }
+}
+
+impl FetchProgress for () {}
+
+impl FetchProgress for ProgressBar {
+    fn on_ticker_done(&self, done: usize, total: usize, _ok: bool) {
+        self.set_length(total as u64);
+        self.set_position(done as u64);
+    }
+}
+
+/// Reports the tickers of one chunk as part of a larger run.
+pub(crate) struct ChunkProgress<'a> {
+    pub inner: &'a dyn FetchProgress,
+    /// Tickers finished before this chunk started
+    pub offset: usize,
+    /// Tickers in the whole run
+    pub total: usize,
+}
+
+impl FetchProgress for ChunkProgress<'_> {
+    fn on_ticker_done(&self, done: usize, _total: usize, ok: bool) {
+        self.inner
+            .on_ticker_done(self.offset + done, self.total, ok);
+    }
+}
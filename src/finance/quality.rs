@@ -0,0 +1,285 @@
+use crate::finance::{
+    calendar::{ExchangeCalendar, WeekdayCalendar},
+    models::{Candle, interval_duration},
+};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tradingview::Interval;
+
+/// Close-to-close moves larger than this fraction are counted as anomalies.
+const ANOMALY_RETURN_THRESHOLD: f64 = 0.3;
+
+/// Combined data-quality signals for one ticker and interval.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QualityReport {
+    /// Number of stored candles
+    pub count: usize,
+    /// Number of candles expected over the stored span
+    pub expected: usize,
+    /// `count / expected`, capped at 1.0
+    pub coverage_ratio: f64,
+    /// Number of gaps reported by [`find_gaps`]
+    pub gap_count: usize,
+    /// Bars with zero volume or a close-to-close move above 30%
+    pub anomaly_count: usize,
+    /// Composite score from 0 (unusable) to 100 (complete and clean)
+    pub score: f64,
+}
+
+impl QualityReport {
+    /// Build a report from candles sorted by timestamp.
+    ///
+    /// The composite score weighs the components as follows:
+    /// - 60% coverage ratio
+    /// - 20% share of consecutive bar pairs that are not gaps
+    /// - 20% share of bars that are not anomalies
+    ///
+    /// Expected bars are the weekdays in the span for `OneDay`, the number of whole periods
+    /// for weekly and monthly bars, and for intraday intervals the number of days with data
+    /// times the largest number of bars seen on a single day.
+    pub fn from_candles(candles: &[Candle], interval: Interval) -> Self {
+        let count = candles.len();
+        let (Some(first), Some(last)) = (candles.first(), candles.last()) else {
+            return Self::default();
+        };
+
+        let expected = expected_bars(candles, first.timestamp, last.timestamp, interval).max(count);
+        let coverage_ratio = if expected == 0 {
+            0.0
+        } else {
+            (count as f64 / expected as f64).min(1.0)
+        };
+
+        let timestamps = candles.iter().map(|c| c.timestamp).collect::<Vec<_>>();
+        let gap_count = find_gaps(&timestamps, interval).len();
+
+        let anomaly_count = candles
+            .iter()
+            .enumerate()
+            .filter(|(i, candle)| {
+                candle.volume == 0.0
+                    || (*i > 0 && {
+                        let prev = candles[i - 1].close;
+                        prev > 0.0
+                            && ((candle.close - prev) / prev).abs() > ANOMALY_RETURN_THRESHOLD
+                    })
+            })
+            .count();
+
+        let gap_ratio = if count > 1 {
+            gap_count as f64 / (count - 1) as f64
+        } else {
+            0.0
+        };
+        let anomaly_ratio = anomaly_count as f64 / count as f64;
+        let score = (100.0
+            * (0.6 * coverage_ratio + 0.2 * (1.0 - gap_ratio) + 0.2 * (1.0 - anomaly_ratio)))
+            .clamp(0.0, 100.0);
+
+        Self {
+            count,
+            expected,
+            coverage_ratio,
+            gap_count,
+            anomaly_count,
+            score,
+        }
+    }
+}
+
+fn expected_bars(
+    candles: &[Candle],
+    first: DateTime<Utc>,
+    last: DateTime<Utc>,
+    interval: Interval,
+) -> usize {
+    let Some(length) = interval_duration(interval) else {
+        return candles.len();
+    };
+
+    if length < Duration::days(1) {
+        let mut per_day: HashMap<NaiveDate, usize> = HashMap::new();
+        for candle in candles {
+            *per_day.entry(candle.timestamp.date_naive()).or_default() += 1;
+        }
+        per_day.len() * per_day.values().copied().max().unwrap_or(0)
+    } else if length == Duration::days(1) {
+        first
+            .date_naive()
+            .iter_days()
+            .take_while(|d| *d <= last.date_naive())
+            .filter(|d| !matches!(d.weekday(), Weekday::Sat | Weekday::Sun))
+            .count()
+    } else {
+        ((last - first).num_seconds() / length.num_seconds()) as usize + 1
+    }
+}
+
+/// Find ranges between consecutive timestamps that are farther apart than `interval` implies.
+///
+/// `timestamps` must be sorted ascending. Each returned pair is `(last bar before the gap,
+/// first bar after the gap)`.
+///
+/// - Intraday intervals only report gaps inside a single UTC day, so overnight and weekend
+///   closes are never reported. Bars missing during a session break, such as the lunch
+///   break of HOSE and HNX, are not gaps with [`find_gaps_in`] and the exchange's calendar.
+/// - `OneDay` reports a gap when at least one weekday between two bars has no bar.
+/// - `OneWeek` and `OneMonth` report a gap when a whole period is missing.
+pub fn find_gaps(
+    timestamps: &[DateTime<Utc>],
+    interval: Interval,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    find_gaps_in(timestamps, interval, &WeekdayCalendar)
+}
+
+/// Like [`find_gaps`], but `OneDay` only counts dates `calendar` trades on as missing,
+/// so exchange holidays such as Tet are not reported. Intraday bars are grouped by
+/// `calendar`'s session date, and bars that would fall in one of its breaks are not missing.
+pub fn find_gaps_in(
+    timestamps: &[DateTime<Utc>],
+    interval: Interval,
+    calendar: &dyn ExchangeCalendar,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let Some(expected) = interval_duration(interval) else {
+        return Vec::new();
+    };
+
+    timestamps
+        .windows(2)
+        .filter(|w| is_gap(w[0], w[1], expected, calendar))
+        .map(|w| (w[0], w[1]))
+        .collect()
+}
+
+fn is_gap(
+    prev: DateTime<Utc>,
+    next: DateTime<Utc>,
+    expected: Duration,
+    calendar: &dyn ExchangeCalendar,
+) -> bool {
+    let delta = next - prev;
+    if delta <= expected {
+        return false;
+    }
+
+    if expected < Duration::days(1) {
+        calendar.session_date(prev) == calendar.session_date(next)
+            && missing_bars(prev, next, expected).any(|t| !calendar.is_break(t))
+    } else if expected == Duration::days(1) {
+        missing_trading_days(prev.date_naive(), next.date_naive(), calendar) > 0
+    } else {
+        // Weekly and monthly bars: allow a few days of drift before calling it a gap
+        delta > expected + expected / 2
+    }
+}
+
+/// Start times of the bars between `prev` and `next`, exclusive, for bars `length` apart.
+fn missing_bars(
+    prev: DateTime<Utc>,
+    next: DateTime<Utc>,
+    length: Duration,
+) -> impl Iterator<Item = DateTime<Utc>> {
+    std::iter::successors(Some(prev + length), move |t| Some(*t + length))
+        .take_while(move |t| *t < next)
+}
+
+/// Number of trading dates strictly between `from` and `to`.
+fn missing_trading_days(from: NaiveDate, to: NaiveDate, calendar: &dyn ExchangeCalendar) -> usize {
+    from.iter_days()
+        .skip(1)
+        .take_while(|d| *d < to)
+        .filter(|d| calendar.is_trading_day(*d))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finance::calendar::HoseCalendar;
+    use crate::test_support::flat_candle;
+    use chrono::TimeZone;
+
+    fn day(d: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, d, 2, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_daily_gaps_ignore_weekends() {
+        // 2024-01-05 is a Friday, 2024-01-08 a Monday
+        let timestamps = vec![day(4), day(5), day(8), day(9), day(11)];
+        let gaps = find_gaps(&timestamps, Interval::OneDay);
+
+        assert_eq!(gaps, vec![(day(9), day(11))]);
+    }
+
+    #[test]
+    fn test_quality_report() {
+        let candle = |d: u32, close: f64| flat_candle(day(d), close);
+
+        // Thu, Fri, Mon, Tue: complete and clean
+        let complete = [4, 5, 8, 9]
+            .into_iter()
+            .map(|d| candle(d, 10.0))
+            .collect::<Vec<_>>();
+        let report = QualityReport::from_candles(&complete, Interval::OneDay);
+        assert_eq!(report.coverage_ratio, 1.0);
+        assert_eq!(report.score, 100.0);
+
+        // Missing Wednesday and a 50% jump on Thursday
+        let degraded = vec![candle(8, 10.0), candle(9, 10.0), candle(11, 15.0)];
+        let report = QualityReport::from_candles(&degraded, Interval::OneDay);
+        assert_eq!(report.expected, 4);
+        assert_eq!(report.gap_count, 1);
+        assert_eq!(report.anomaly_count, 1);
+        assert!(report.score < 100.0);
+    }
+
+    #[test]
+    fn test_intraday_gaps_ignore_overnight() {
+        let base = day(4);
+        let timestamps = vec![
+            base,
+            base + Duration::hours(1),
+            base + Duration::hours(3),
+            day(5),
+        ];
+        let gaps = find_gaps(&timestamps, Interval::OneHour);
+
+        assert_eq!(
+            gaps,
+            vec![(base + Duration::hours(1), base + Duration::hours(3))]
+        );
+    }
+
+    #[test]
+    fn test_daily_gaps_skip_exchange_holidays() {
+        // Last session before Tet 2024 was Wed 2024-02-07; trading resumed Thu 2024-02-15
+        let before = Utc.with_ymd_and_hms(2024, 2, 7, 2, 0, 0).unwrap();
+        let after = Utc.with_ymd_and_hms(2024, 2, 15, 2, 0, 0).unwrap();
+        let timestamps = vec![before, after];
+
+        assert_eq!(find_gaps(&timestamps, Interval::OneDay).len(), 1);
+        assert!(find_gaps_in(&timestamps, Interval::OneDay, &HoseCalendar).is_empty());
+    }
+
+    #[test]
+    fn test_intraday_gaps_skip_lunch_break() {
+        // A full HOSE session of 15 minute bars, 09:00-11:30 and 13:00-14:45 local time
+        let open = Utc.with_ymd_and_hms(2024, 1, 4, 2, 0, 0).unwrap();
+        let bars = |from: i64, to: i64| (from..to).map(move |i| open + Duration::minutes(15 * i));
+        let session = bars(0, 10).chain(bars(16, 23)).collect::<Vec<_>>();
+
+        assert!(find_gaps_in(&session, Interval::FifteenMinutes, &HoseCalendar).is_empty());
+        // Without a calendar that knows the break it is one gap
+        assert_eq!(find_gaps(&session, Interval::FifteenMinutes).len(), 1);
+
+        // A bar missing in the afternoon is still a gap
+        let mut missing = session.clone();
+        missing.remove(12);
+        assert_eq!(
+            find_gaps_in(&missing, Interval::FifteenMinutes, &HoseCalendar),
+            vec![(session[11], session[13])]
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Requests-per-second cap shared by every task that holds a reference to it.
+///
+/// Permits are handed out at a fixed spacing of `1 / requests_per_second`, with no burst,
+/// so concurrent tasks are serialized onto the same schedule.
+#[derive(Debug)]
+pub struct RateLimiter {
+    spacing: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// A limiter allowing `requests_per_second` permits per second; `0` is treated as `1`.
+    pub fn new(requests_per_second: u32) -> Self {
+        Self {
+            spacing: Duration::from_secs(1) / requests_per_second.max(1),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait until the next permit is available.
+    pub async fn acquire(&self) {
+        let ready_at = {
+            let mut next = self.next.lock().await;
+            let ready_at = (*next).max(Instant::now());
+            *next = ready_at + self.spacing;
+            ready_at
+        };
+        tokio::time::sleep_until(ready_at).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_rate_limiter_is_shared_across_tasks() {
+        let start = Instant::now();
+        let limiter = Arc::new(RateLimiter::new(20));
+
+        let tasks = (0..5)
+            .map(|_| {
+                let limiter = limiter.clone();
+                tokio::spawn(async move { limiter.acquire().await })
+            })
+            .collect::<Vec<_>>();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        // 5 permits at 20/s need at least 4 spacings of 50ms
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,139 @@
+use crate::finance::models::{Candle, interval_duration};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use tradingview::Interval;
+
+/// Aggregate candles of interval `from` into candles of the larger interval `to`.
+///
+/// Buckets are aligned to UTC boundaries of the target interval (weeks start on Monday,
+/// months on the 1st). Each bucket takes the first open, the max high, the min low, the
+/// last close (and adjusted close) and the summed volume, and is stamped with the bucket start. A trailing
+/// bucket that isn't complete yet is still emitted.
+pub fn resample_candles(
+    candles: &[Candle],
+    from: Interval,
+    to: Interval,
+) -> anyhow::Result<Vec<Candle>> {
+    let (Some(from_len), Some(to_len)) = (interval_duration(from), interval_duration(to)) else {
+        return Err(anyhow::anyhow!(
+            "Cannot resample between intervals {} and {}",
+            from,
+            to
+        ));
+    };
+    if to_len < from_len {
+        return Err(anyhow::anyhow!(
+            "Cannot downsample candles from {} to the smaller interval {}",
+            from,
+            to
+        ));
+    }
+
+    let mut sorted = candles.to_vec();
+    sorted.sort_by(Candle::cmp_by_timestamp);
+
+    let mut resampled: Vec<Candle> = Vec::new();
+    for candle in sorted {
+        let start = bucket_start(candle.timestamp, to, to_len);
+        match resampled.last_mut() {
+            Some(bucket) if bucket.timestamp == start => {
+                bucket.high = bucket.high.max(candle.high);
+                bucket.low = bucket.low.min(candle.low);
+                bucket.close = candle.close;
+                bucket.volume += candle.volume;
+                bucket.adj_close = candle.adj_close;
+            }
+            _ => resampled.push(Candle {
+                timestamp: start,
+                ..candle
+            }),
+        }
+    }
+
+    Ok(resampled)
+}
+
+/// Start of the `interval` bucket containing `timestamp`.
+pub fn bucket_start(
+    timestamp: DateTime<Utc>,
+    interval: Interval,
+    length: Duration,
+) -> DateTime<Utc> {
+    match interval {
+        Interval::OneWeek => {
+            let date = timestamp.date_naive();
+            let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+            monday.and_hms_opt(0, 0, 0).unwrap().and_utc()
+        }
+        Interval::OneMonth => NaiveDate::from_ymd_opt(timestamp.year(), timestamp.month(), 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc(),
+        _ => {
+            let seconds = timestamp.timestamp();
+            let floored = seconds - seconds.rem_euclid(length.num_seconds());
+            DateTime::from_timestamp(floored, 0).unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::flat_candle;
+    use chrono::TimeZone;
+
+    fn minute_series() -> Vec<Candle> {
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 2, 0, 0).unwrap();
+        (0..90)
+            .map(|i| {
+                let price = 100.0 + i as f64;
+                Candle {
+                    high: price + 0.5,
+                    low: price - 0.5,
+                    close: price + 0.25,
+                    volume: 10.0,
+                    ..flat_candle(start + Duration::minutes(i), price)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resample_minutes_to_hours() -> anyhow::Result<()> {
+        let hourly = resample_candles(&minute_series(), Interval::OneMinute, Interval::OneHour)?;
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 2, 0, 0).unwrap();
+
+        let expected = vec![
+            Candle {
+                timestamp: start,
+                open: 100.0,
+                high: 159.5,
+                low: 99.5,
+                close: 159.25,
+                volume: 600.0,
+                adj_close: None,
+            },
+            // Partial trailing bucket with 30 minutes of data
+            Candle {
+                timestamp: start + Duration::hours(1),
+                open: 160.0,
+                high: 189.5,
+                low: 159.5,
+                close: 189.25,
+                volume: 300.0,
+                adj_close: None,
+            },
+        ];
+        assert!(crate::finance::models::candles_approx_eq(
+            &hourly, &expected, 1e-9
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_resample_rejects_downsampling() {
+        let result = resample_candles(&minute_series(), Interval::OneDay, Interval::OneMinute);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::finance::metrics::{Metrics, metrics};
+use rand::Rng;
+use std::future::Future;
+use std::time::Duration;
+
+/// Delay before retry number `attempt` (starting at 1).
+///
+/// The delay doubles with each attempt, starting at `base_delay`, and gets up to 50% random
+/// jitter on top so concurrent tasks that failed together don't retry in lockstep.
+pub fn backoff_delay(attempt: u32, base_delay: Duration) -> Duration {
+    let exponential = base_delay.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+    let jitter = rand::rng().random_range(0.0..=0.5);
+    exponential.mul_f64(1.0 + jitter)
+}
+
+/// [`backoff_delay`] clamped to `max_delay`, so long retry chains don't sleep for hours.
+pub fn capped_backoff_delay(attempt: u32, base_delay: Duration, max_delay: Duration) -> Duration {
+    backoff_delay(attempt, base_delay).min(max_delay)
+}
+
+/// Run `op` until it succeeds, retrying up to `max_retries` times with [`backoff_delay`].
+///
+/// Returns the error of the last attempt when every attempt fails.
+pub async fn retry_async<F, Fut, T>(
+    op: F,
+    max_retries: usize,
+    base_delay: Duration,
+) -> anyhow::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    retry_async_with(op, max_retries, base_delay, metrics()).await
+}
+
+/// Like [`retry_async`], but reports retries to `sink` instead of the installed sink.
+pub async fn retry_async_with<F, Fut, T>(
+    mut op: F,
+    max_retries: usize,
+    base_delay: Duration,
+    sink: &dyn Metrics,
+) -> anyhow::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < max_retries => {
+                attempt += 1;
+                sink.record_retry("request");
+                let delay = backoff_delay(attempt as u32, base_delay);
+                tracing::warn!(
+                    "Attempt {}/{} failed, retrying in {:.2}s: {}",
+                    attempt,
+                    max_retries + 1,
+                    delay.as_secs_f64(),
+                    e
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_retry_async_succeeds_after_failures() -> anyhow::Result<()> {
+        let counter = AtomicUsize::new(0);
+        let attempts = &counter;
+        let result = retry_async(
+            move || async move {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(anyhow::anyhow!("transient")),
+                    n => Ok(n),
+                }
+            },
+            3,
+            Duration::from_millis(1),
+        )
+        .await?;
+
+        assert_eq!(result, 2);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        attempts.store(0, Ordering::SeqCst);
+        let failed: anyhow::Result<()> = retry_async(
+            move || async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(anyhow::anyhow!("permanent"))
+            },
+            2,
+            Duration::from_millis(1),
+        )
+        .await;
+        assert!(failed.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct CountingRetries(AtomicUsize);
+
+    impl Metrics for CountingRetries {
+        fn record_retry(&self, _operation: &str) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_are_reported_to_the_sink() -> anyhow::Result<()> {
+        let sink = CountingRetries::default();
+        let counter = AtomicUsize::new(0);
+        let attempts = &counter;
+        retry_async_with(
+            move || async move {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(anyhow::anyhow!("transient")),
+                    n => Ok(n),
+                }
+            },
+            3,
+            Duration::from_millis(1),
+            &sink,
+        )
+        .await?;
+
+        assert_eq!(sink.0.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_backoff_delay_bounds() {
+        let base = Duration::from_millis(100);
+        for attempt in 1..=4 {
+            let delay = backoff_delay(attempt, base);
+            let floor = base * 2u32.pow(attempt - 1);
+            assert!(delay >= floor && delay <= floor.mul_f64(1.5));
+        }
+    }
+
+    #[test]
+    fn test_capped_backoff_delay_is_clamped() {
+        let base = Duration::from_secs(1);
+        let max = Duration::from_secs(10);
+
+        assert!(capped_backoff_delay(2, base, max) <= Duration::from_secs(3));
+        for attempt in [5, 10, 100] {
+            assert_eq!(capped_backoff_delay(attempt, base, max), max);
+        }
+    }
+}
@@ -1,5 +1,562 @@
-pub fn poc() -> anyhow::Result<Vec<f64>>{
+use crate::finance::{
+    calendar::ExchangeCalendar,
+    models::{Candle, Indicator, median_spacing},
+};
+use chrono::Duration;
 
+/// Simple moving average. The first `period - 1` values are `None`.
+pub fn sma(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; values.len()];
+    if period == 0 {
+        return out;
+    }
 
-    todo!()
-}
\ No newline at end of file
+    let mut sum = 0.0;
+    for (i, value) in values.iter().enumerate() {
+        sum += value;
+        if i >= period {
+            sum -= values[i - period];
+        }
+        if i + 1 >= period {
+            out[i] = Some(sum / period as f64);
+        }
+    }
+    out
+}
+
+/// Exponential moving average seeded with the SMA of the first `period` values.
+pub fn ema(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; values.len()];
+    if period == 0 || values.len() < period {
+        return out;
+    }
+
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut current = values[..period].iter().sum::<f64>() / period as f64;
+    out[period - 1] = Some(current);
+    for (i, value) in values.iter().enumerate().skip(period) {
+        current = alpha * value + (1.0 - alpha) * current;
+        out[i] = Some(current);
+    }
+    out
+}
+
+/// Rolling population standard deviation over `period` values.
+pub fn rolling_std(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; values.len()];
+    if period == 0 {
+        return out;
+    }
+
+    for (i, window) in values.windows(period).enumerate() {
+        let mean = window.iter().sum::<f64>() / period as f64;
+        let variance = window.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / period as f64;
+        out[i + period - 1] = Some(variance.sqrt());
+    }
+    out
+}
+
+/// Relative Strength Index using Wilder's smoothing.
+///
+/// The first value is available at index `period`, once `period` price changes are known.
+pub fn rsi(closes: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; closes.len()];
+    if period == 0 || closes.len() <= period {
+        return out;
+    }
+
+    let changes = closes.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+    let mut avg_gain = changes[..period].iter().map(|c| c.max(0.0)).sum::<f64>() / period as f64;
+    let mut avg_loss = changes[..period].iter().map(|c| (-c).max(0.0)).sum::<f64>() / period as f64;
+    out[period] = Some(rsi_value(avg_gain, avg_loss));
+
+    for (i, change) in changes.iter().enumerate().skip(period) {
+        avg_gain = (avg_gain * (period - 1) as f64 + change.max(0.0)) / period as f64;
+        avg_loss = (avg_loss * (period - 1) as f64 + (-change).max(0.0)) / period as f64;
+        out[i + 1] = Some(rsi_value(avg_gain, avg_loss));
+    }
+    out
+}
+
+fn rsi_value(avg_gain: f64, avg_loss: f64) -> f64 {
+    if avg_loss == 0.0 {
+        100.0
+    } else {
+        100.0 - 100.0 / (1.0 + avg_gain / avg_loss)
+    }
+}
+
+/// Money Flow Index over `period` bars, based on the typical price `(h + l + c) / 3`.
+pub fn mfi(candles: &[Candle], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; candles.len()];
+    if period == 0 || candles.len() <= period {
+        return out;
+    }
+
+    let typical = candles
+        .iter()
+        .map(|c| (c.high + c.low + c.close) / 3.0)
+        .collect::<Vec<_>>();
+    // Signed raw money flow for each bar after the first
+    let flows = (1..candles.len())
+        .map(|i| {
+            let flow = typical[i] * candles[i].volume;
+            if typical[i] > typical[i - 1] {
+                flow
+            } else if typical[i] < typical[i - 1] {
+                -flow
+            } else {
+                0.0
+            }
+        })
+        .collect::<Vec<_>>();
+
+    for (i, window) in flows.windows(period).enumerate() {
+        let positive = window.iter().filter(|f| **f > 0.0).sum::<f64>();
+        let negative = -window.iter().filter(|f| **f < 0.0).sum::<f64>();
+        out[i + period] = Some(if negative == 0.0 {
+            100.0
+        } else {
+            100.0 - 100.0 / (1.0 + positive / negative)
+        });
+    }
+    out
+}
+
+/// True range of each candle: `max(high - low, |high - prev_close|, |low - prev_close|)`.
+///
+/// The first candle has no previous close, so its true range is just `high - low`.
+pub fn true_range(candles: &[Candle]) -> Vec<f64> {
+    candles
+        .iter()
+        .enumerate()
+        .map(|(i, candle)| {
+            let range = candle.high - candle.low;
+            match i.checked_sub(1).map(|prev| candles[prev].close) {
+                Some(prev_close) => range
+                    .max((candle.high - prev_close).abs())
+                    .max((candle.low - prev_close).abs()),
+                None => range,
+            }
+        })
+        .collect()
+}
+
+/// Average True Range using Wilder's smoothing.
+///
+/// Seeded with the mean of the first `period` true ranges (see [`true_range`] for the
+/// first bar), so the first value is available at index `period - 1`.
+pub fn atr(candles: &[Candle], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; candles.len()];
+    if period == 0 || candles.len() < period {
+        return out;
+    }
+
+    let ranges = true_range(candles);
+    let mut current = ranges[..period].iter().sum::<f64>() / period as f64;
+    out[period - 1] = Some(current);
+    for (i, range) in ranges.iter().enumerate().skip(period) {
+        current = (current * (period - 1) as f64 + range) / period as f64;
+        out[i] = Some(current);
+    }
+    out
+}
+
+/// Volume-weighted average price: cumulative `typical_price * volume` over cumulative volume.
+///
+/// For intraday series (median bar spacing under a day) the sums reset at the start of each
+/// trading session, with sessions split on `calendar`'s session date of each bar (see
+/// `calendar_for_exchange`). Daily and longer series accumulate over the whole input. A
+/// value is `None` until the session has traded some volume.
+pub fn vwap(candles: &[Candle], calendar: &dyn ExchangeCalendar) -> Vec<Option<f64>> {
+    let timestamps = candles.iter().map(|c| c.timestamp).collect::<Vec<_>>();
+    let intraday = median_spacing(&timestamps).is_some_and(|spacing| spacing < Duration::days(1));
+
+    let mut out = Vec::with_capacity(candles.len());
+    let mut session = None;
+    let mut price_volume = 0.0;
+    let mut volume = 0.0;
+    for candle in candles {
+        if intraday {
+            let date = calendar.session_date(candle.timestamp);
+            if session != Some(date) {
+                session = Some(date);
+                price_volume = 0.0;
+                volume = 0.0;
+            }
+        }
+
+        price_volume += (candle.high + candle.low + candle.close) / 3.0 * candle.volume;
+        volume += candle.volume;
+        out.push((volume > 0.0).then(|| price_volume / volume));
+    }
+    out
+}
+
+/// Turn a per-candle series into `Indicator` rows, skipping warm-up values.
+pub fn to_indicators(
+    candles: &[Candle],
+    values: &[Option<f64>],
+    indicator_type: &str,
+) -> Vec<Indicator> {
+    candles
+        .iter()
+        .zip(values)
+        .filter_map(|(candle, value)| {
+            value.map(|value| Indicator {
+                timestamp: candle.timestamp,
+                indicator_type: indicator_type.to_string(),
+                value: Some(value),
+                metadata: None,
+            })
+        })
+        .collect()
+}
+
+fn closes(candles: &[Candle]) -> Vec<f64> {
+    candles.iter().map(|c| c.close).collect()
+}
+
+/// SMA of close, emitted as `sma_{period}`.
+pub fn compute_sma(candles: &[Candle], period: usize) -> Vec<Indicator> {
+    to_indicators(
+        candles,
+        &sma(&closes(candles), period),
+        &format!("sma_{period}"),
+    )
+}
+
+/// EMA of close, emitted as `ema_{period}`.
+pub fn compute_ema(candles: &[Candle], period: usize) -> Vec<Indicator> {
+    to_indicators(
+        candles,
+        &ema(&closes(candles), period),
+        &format!("ema_{period}"),
+    )
+}
+
+/// RSI of close, emitted as `rsi_{period}`.
+pub fn compute_rsi(candles: &[Candle], period: usize) -> Vec<Indicator> {
+    to_indicators(
+        candles,
+        &rsi(&closes(candles), period),
+        &format!("rsi_{period}"),
+    )
+}
+
+/// MFI, emitted as `mfi_{period}`.
+pub fn compute_mfi(candles: &[Candle], period: usize) -> Vec<Indicator> {
+    to_indicators(candles, &mfi(candles, period), &format!("mfi_{period}"))
+}
+
+/// ATR, emitted as `atr_{period}`.
+pub fn compute_atr(candles: &[Candle], period: usize) -> Vec<Indicator> {
+    to_indicators(candles, &atr(candles, period), &format!("atr_{period}"))
+}
+
+/// VWAP, emitted as `vwap`. See [`vwap`] for when the sums reset.
+pub fn compute_vwap(candles: &[Candle], calendar: &dyn ExchangeCalendar) -> Vec<Indicator> {
+    to_indicators(candles, &vwap(candles, calendar), "vwap")
+}
+
+/// MACD line, signal line and histogram for each candle.
+///
+/// The line is `EMA(fast) - EMA(slow)` of close. The signal EMA is seeded only once the
+/// line has `signal` values, so it is `None` for the first `slow + signal - 2` bars.
+pub fn macd(
+    closes: &[f64],
+    fast: usize,
+    slow: usize,
+    signal: usize,
+) -> (Vec<Option<f64>>, Vec<Option<f64>>, Vec<Option<f64>>) {
+    let fast_ema = ema(closes, fast);
+    let slow_ema = ema(closes, slow);
+    let line = fast_ema
+        .iter()
+        .zip(&slow_ema)
+        .map(|(f, s)| Some((*f)? - (*s)?))
+        .collect::<Vec<_>>();
+
+    let mut signal_line = vec![None; closes.len()];
+    if let Some(offset) = line.iter().position(Option::is_some) {
+        let defined = line[offset..].iter().flatten().copied().collect::<Vec<_>>();
+        for (i, value) in ema(&defined, signal).into_iter().enumerate() {
+            signal_line[offset + i] = value;
+        }
+    }
+
+    let histogram = line
+        .iter()
+        .zip(&signal_line)
+        .map(|(l, s)| Some((*l)? - (*s)?))
+        .collect();
+
+    (line, signal_line, histogram)
+}
+
+/// MACD emitted as `macd_line`, `macd_signal` and `macd_hist` rows.
+pub fn compute_macd(candles: &[Candle], fast: usize, slow: usize, signal: usize) -> Vec<Indicator> {
+    let (line, signal_line, histogram) = macd(&closes(candles), fast, slow, signal);
+
+    let mut indicators = to_indicators(candles, &line, "macd_line");
+    indicators.extend(to_indicators(candles, &signal_line, "macd_signal"));
+    indicators.extend(to_indicators(candles, &histogram, "macd_hist"));
+    indicators.sort_by_key(|i| i.timestamp);
+    indicators
+}
+
+/// Bollinger Bands: `SMA(period) ± num_std * σ` of close, with σ the population standard
+/// deviation over the same window. Returns `(upper, middle, lower)`; all three are `None`
+/// for the first `period - 1` bars.
+pub fn bollinger(
+    closes: &[f64],
+    period: usize,
+    num_std: f64,
+) -> (Vec<Option<f64>>, Vec<Option<f64>>, Vec<Option<f64>>) {
+    let middle = sma(closes, period);
+    let std = rolling_std(closes, period);
+    let band = |sign: f64| {
+        middle
+            .iter()
+            .zip(&std)
+            .map(|(m, s)| Some((*m)? + sign * num_std * (*s)?))
+            .collect::<Vec<_>>()
+    };
+
+    let (upper, lower) = (band(1.0), band(-1.0));
+    (upper, middle, lower)
+}
+
+/// Bollinger Bands emitted as `bb_upper`, `bb_mid` and `bb_lower` rows.
+pub fn compute_bollinger(candles: &[Candle], period: usize, num_std: f64) -> Vec<Indicator> {
+    let (upper, middle, lower) = bollinger(&closes(candles), period, num_std);
+
+    let mut indicators = to_indicators(candles, &upper, "bb_upper");
+    indicators.extend(to_indicators(candles, &middle, "bb_mid"));
+    indicators.extend(to_indicators(candles, &lower, "bb_lower"));
+    indicators.sort_by_key(|i| i.timestamp);
+    indicators
+}
+
+/// Compute an indicator from its name, e.g. `rsi_14`, `sma_20` or `macd_12_26_9`.
+///
+/// Supported names are `sma_{n}`, `ema_{n}`, `rsi_{n}`, `mfi_{n}`, `atr_{n}`,
+/// `macd_{fast}_{slow}_{signal}`, `bb_{n}_{num_std}` and `vwap`. Parameters must be positive
+/// integers. Unknown names and malformed parameters are errors. `calendar` is the exchange
+/// calendar of the candles, which only `vwap` uses.
+pub fn compute_indicator(
+    name: &str,
+    candles: &[Candle],
+    calendar: &dyn ExchangeCalendar,
+) -> anyhow::Result<Vec<Indicator>> {
+    let mut parts = name.trim().split('_');
+    let kind = parts.next().unwrap_or_default().to_ascii_lowercase();
+    let params = parts
+        .map(|part| match part.parse::<usize>() {
+            Ok(value) if value > 0 => Ok(value),
+            _ => Err(anyhow::anyhow!(
+                "Invalid parameter '{}' in indicator '{}', expected a positive integer",
+                part,
+                name
+            )),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let indicators = match (kind.as_str(), params.as_slice()) {
+        ("sma", &[period]) => compute_sma(candles, period),
+        ("ema", &[period]) => compute_ema(candles, period),
+        ("rsi", &[period]) => compute_rsi(candles, period),
+        ("mfi", &[period]) => compute_mfi(candles, period),
+        ("atr", &[period]) => compute_atr(candles, period),
+        ("macd", &[fast, slow, signal]) => compute_macd(candles, fast, slow, signal),
+        ("bb", &[period, num_std]) => compute_bollinger(candles, period, num_std as f64),
+        ("vwap", &[]) => compute_vwap(candles, calendar),
+        _ => return Err(anyhow::anyhow!("Unknown indicator '{}'", name)),
+    };
+    Ok(indicators)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finance::calendar::{HoseCalendar, WeekdayCalendar};
+    use crate::test_support::flat_candle;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_sma_and_ema_warm_up() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+
+        assert_eq!(
+            sma(&values, 3),
+            vec![None, None, Some(2.0), Some(3.0), Some(4.0)]
+        );
+        let ema = ema(&values, 3);
+        assert_eq!(ema[..2], [None, None]);
+        assert_eq!(ema[2], Some(2.0));
+        assert_eq!(ema[3], Some(3.0));
+    }
+
+    #[test]
+    fn test_rsi_bounds() {
+        let rising = (0..20).map(|i| i as f64).collect::<Vec<_>>();
+        assert_eq!(rsi(&rising, 14)[14], Some(100.0));
+
+        let falling = rising.iter().rev().copied().collect::<Vec<_>>();
+        assert_eq!(rsi(&falling, 14)[19], Some(0.0));
+    }
+
+    #[test]
+    fn test_macd_histogram_is_line_minus_signal() {
+        let start = chrono::Utc::now();
+        let candles = (0..60)
+            .map(|i| Candle {
+                timestamp: start + chrono::Duration::days(i),
+                close: 100.0 + (i as f64 / 3.0).sin() * 5.0,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        let indicators = compute_macd(&candles, 12, 26, 9);
+        let value = |kind: &str, ts| {
+            indicators
+                .iter()
+                .find(|i| i.indicator_type == kind && i.timestamp == ts)
+                .and_then(|i| i.value)
+        };
+
+        let (_, signal, _) = macd(&closes(&candles), 12, 26, 9);
+        assert_eq!(signal.iter().position(Option::is_some), Some(33));
+
+        for candle in &candles {
+            if let Some(hist) = value("macd_hist", candle.timestamp) {
+                let line = value("macd_line", candle.timestamp).unwrap();
+                let signal = value("macd_signal", candle.timestamp).unwrap();
+                assert!((hist - (line - signal)).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_vwap_resets_each_session() {
+        // 02:00 UTC is 09:00 in Vietnam, when the morning session opens
+        let open = chrono::Utc.with_ymd_and_hms(2024, 1, 2, 2, 0, 0).unwrap();
+        let bar = |timestamp, price, volume| Candle {
+            volume,
+            ..flat_candle(timestamp, price)
+        };
+        let candles = vec![
+            bar(open, 10.0, 100.0),
+            bar(open + Duration::hours(1), 20.0, 300.0),
+            bar(open + Duration::days(1), 30.0, 50.0),
+            bar(open + Duration::days(1) + Duration::hours(1), 40.0, 50.0),
+        ];
+
+        assert_eq!(
+            vwap(&candles, &HoseCalendar),
+            vec![Some(10.0), Some(17.5), Some(30.0), Some(35.0)]
+        );
+        assert_eq!(
+            compute_vwap(&candles, &HoseCalendar)[2].indicator_type,
+            "vwap"
+        );
+
+        // 16:00 and 18:00 UTC share a UTC date but straddle midnight in Vietnam
+        let late = chrono::Utc.with_ymd_and_hms(2024, 1, 2, 16, 0, 0).unwrap();
+        let candles = vec![
+            bar(late, 10.0, 100.0),
+            bar(late + Duration::hours(2), 20.0, 100.0),
+        ];
+        assert_eq!(vwap(&candles, &HoseCalendar), vec![Some(10.0), Some(20.0)]);
+        assert_eq!(
+            vwap(&candles, &WeekdayCalendar),
+            vec![Some(10.0), Some(15.0)]
+        );
+    }
+
+    #[test]
+    fn test_bollinger_bands_are_symmetric_and_widen() {
+        let closes = [10.0, 10.0, 10.0, 10.0, 12.0, 8.0, 14.0, 6.0];
+        let (upper, middle, lower) = bollinger(&closes, 4, 2.0);
+
+        assert_eq!(middle, sma(&closes, 4));
+        assert!(upper[..3].iter().chain(&lower[..3]).all(Option::is_none));
+        assert_eq!((upper[3], lower[3]), (Some(10.0), Some(10.0)));
+
+        let width = |i: usize| upper[i].unwrap() - lower[i].unwrap();
+        for i in 3..closes.len() {
+            let mid = middle[i].unwrap();
+            assert!(((upper[i].unwrap() - mid) - (mid - lower[i].unwrap())).abs() < 1e-12);
+        }
+        assert!(width(5) > width(4));
+        assert!(width(7) > width(5));
+    }
+
+    #[test]
+    fn test_atr_hand_computed() {
+        let bar = |high: f64, low: f64, close: f64| Candle {
+            high,
+            low,
+            close,
+            ..Default::default()
+        };
+        let candles = vec![
+            bar(10.0, 8.0, 9.0),
+            bar(11.0, 9.0, 10.5),
+            bar(13.0, 10.0, 12.0),
+            bar(12.5, 11.0, 11.5),
+            // Gap down: the distance to the previous close dominates
+            bar(10.0, 9.0, 9.5),
+        ];
+
+        assert_eq!(true_range(&candles), vec![2.0, 2.0, 3.0, 1.5, 2.5]);
+
+        let atr = atr(&candles, 3);
+        let expected = [7.0 / 3.0, 18.5 / 9.0, 59.5 / 27.0];
+        assert_eq!(atr[..2], [None, None]);
+        for (value, expected) in atr[2..].iter().zip(expected) {
+            assert!((value.unwrap() - expected).abs() < 1e-12);
+        }
+        assert_eq!(compute_atr(&candles, 3)[0].indicator_type, "atr_3");
+    }
+
+    #[test]
+    fn test_compute_indicator_parses_names() {
+        let candles = (0..40)
+            .map(|i| Candle {
+                close: 100.0 + i as f64,
+                high: 101.0 + i as f64,
+                low: 99.0 + i as f64,
+                volume: 10.0,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        for name in [
+            "rsi_14",
+            "sma_20",
+            "ema_12",
+            "macd_12_26_9",
+            "bb_20_2",
+            "vwap",
+        ] {
+            assert!(
+                !compute_indicator(name, &candles, &HoseCalendar)
+                    .unwrap()
+                    .is_empty(),
+                "{name}"
+            );
+        }
+        assert_eq!(
+            compute_indicator("SMA_20", &candles, &HoseCalendar).unwrap()[0].indicator_type,
+            "sma_20"
+        );
+
+        for name in ["", "rsi", "rsi_x", "sma_0", "foo_3", "macd_12_26", "vwap_5"] {
+            assert!(
+                compute_indicator(name, &candles, &HoseCalendar).is_err(),
+                "{name}"
+            );
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::future::Future;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tradingview::{UserCookies, get_quote_token};
+
+/// File the quote token is cached in when no other path is given.
+pub const TOKEN_FILE: &str = ".vnquant_token.json";
+
+/// How long a fetched quote token is reused before it is fetched again.
+pub const DEFAULT_TOKEN_TTL: Duration = Duration::hours(1);
+
+/// TradingView session cookies from the file at `path`, else from the `json` string
+/// (usually the `TV_COOKIES` variable). `None` when neither is given.
+pub fn load_user_cookies(
+    path: Option<&str>,
+    json: Option<&str>,
+) -> anyhow::Result<Option<UserCookies>> {
+    let (source, json) = match (path, json) {
+        (Some(path), _) => (
+            path,
+            std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read cookies file {}: {}", path, e))?,
+        ),
+        (None, Some(json)) => ("TV_COOKIES", json.to_string()),
+        (None, None) => return Ok(None),
+    };
+    let user = serde_json::from_str(&json)
+        .map_err(|e| anyhow::anyhow!("Invalid cookies JSON in {}: {}", source, e))?;
+    Ok(Some(user))
+}
+
+/// Key identifying the TradingView account `user`'s cookies belong to, so a cached token
+/// is never handed to another account. A hash of the cookies, not a secret by itself.
+pub fn account_key(user: &UserCookies) -> anyhow::Result<String> {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(user)?.hash(&mut hasher);
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+/// A quote token, the account it belongs to and the time after which it must be fetched
+/// again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedToken {
+    pub token: String,
+    /// [`account_key`] of the cookies the token was fetched with
+    pub account: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Caches the TradingView quote token in a file so separate CLI runs can reuse it instead
+/// of logging in with the cookies every time.
+///
+/// The cache holds one token. A token cached for another account counts as missing, so
+/// switching cookies fetches a new one. The file is only readable by its owner on unix.
+#[derive(Debug, Clone)]
+pub struct TokenStore {
+    path: PathBuf,
+    ttl: Duration,
+}
+
+impl Default for TokenStore {
+    fn default() -> Self {
+        Self::new(TOKEN_FILE)
+    }
+}
+
+impl TokenStore {
+    /// Store backed by the file at `path`, keeping tokens for [`DEFAULT_TOKEN_TTL`].
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            ttl: DEFAULT_TOKEN_TTL,
+        }
+    }
+
+    /// Keep fetched tokens for `ttl` instead of [`DEFAULT_TOKEN_TTL`].
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// The token cached for `account`, or `None` when there is none, it belongs to another
+    /// account, it has expired or the file can't be parsed.
+    pub fn load(&self, account: &str) -> Option<String> {
+        let json = std::fs::read_to_string(&self.path).ok()?;
+        let cached: CachedToken = match serde_json::from_str(&json) {
+            Ok(cached) => cached,
+            Err(e) => {
+                tracing::warn!(
+                    "Ignoring unreadable token cache {}: {}",
+                    self.path.display(),
+                    e
+                );
+                return None;
+            }
+        };
+        (cached.account == account && cached.expires_at > Utc::now()).then_some(cached.token)
+    }
+
+    /// Cache `token` for `account` until the TTL from now has passed.
+    ///
+    /// The cache is written to a temporary file that then replaces it, so a crash never
+    /// leaves a truncated cache behind.
+    pub fn save(&self, token: &str, account: &str) -> anyhow::Result<()> {
+        static SAVES: AtomicU64 = AtomicU64::new(0);
+
+        let cached = CachedToken {
+            token: token.to_string(),
+            account: account.to_string(),
+            expires_at: Utc::now() + self.ttl,
+        };
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            SAVES.fetch_add(1, Ordering::Relaxed)
+        ));
+        let tmp = PathBuf::from(tmp);
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let json = serde_json::to_string_pretty(&cached)?;
+        let written = options.open(&tmp).and_then(|mut file| {
+            file.write_all(json.as_bytes())?;
+            file.sync_all()
+        });
+        if let Err(e) = written.and_then(|()| std::fs::rename(&tmp, &self.path)) {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(anyhow::anyhow!(
+                "Failed to write token cache {}: {}",
+                self.path.display(),
+                e
+            ));
+        }
+        Ok(())
+    }
+
+    /// The token cached for `account` if it is still valid, else a token from `fetch`,
+    /// which is cached.
+    pub async fn get_or_refresh_with<F, Fut>(
+        &self,
+        account: &str,
+        fetch: F,
+    ) -> anyhow::Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<String>>,
+    {
+        if let Some(token) = self.load(account) {
+            return Ok(token);
+        }
+
+        let token = fetch().await?;
+        self.save(&token, account)?;
+        Ok(token)
+    }
+
+    /// The cached token if it is still valid and was fetched for `user`, else a fresh one
+    /// for `user`'s session.
+    pub async fn get_or_refresh_token(&self, user: &UserCookies) -> anyhow::Result<String> {
+        self.get_or_refresh_with(&account_key(user)?, || async {
+            Ok(get_quote_token(user).await?)
+        })
+        .await
+    }
+}
+
+/// A TradingView session and the store its quote token is cached in.
+///
+/// Ask for [`Self::token`] before every request rather than keeping the result: a long run
+/// then switches to a fresh token once the cached one expires instead of sending a stale one.
+#[derive(Debug, Clone)]
+pub struct QuoteAuth {
+    store: TokenStore,
+    user: UserCookies,
+}
+
+impl QuoteAuth {
+    pub fn new(store: TokenStore, user: UserCookies) -> Self {
+        Self { store, user }
+    }
+
+    /// A currently valid quote token, fetched again when the cached one has expired.
+    pub async fn token(&self) -> anyhow::Result<String> {
+        self.store.get_or_refresh_token(&self.user).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_user_cookies() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("cookies.json");
+        let json = serde_json::to_string(&UserCookies::default())?;
+        std::fs::write(&path, &json)?;
+        let path = path.to_str().unwrap();
+
+        assert!(load_user_cookies(Some(path), None)?.is_some());
+        assert!(load_user_cookies(None, Some(&json))?.is_some());
+        assert!(load_user_cookies(None, None)?.is_none());
+
+        std::fs::write(path, "{\"session\": ")?;
+        let err = load_user_cookies(Some(path), Some(&json)).unwrap_err();
+        assert!(err.to_string().contains("Invalid cookies JSON in"), "{err}");
+        std::fs::remove_file(path)?;
+
+        let err = load_user_cookies(Some(path), None).unwrap_err();
+        assert!(
+            err.to_string().contains("Failed to read cookies file"),
+            "{err}"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cached_token_skips_fetch() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("token.json");
+        let store = TokenStore::new(&path);
+        store.save("cached", "alice")?;
+
+        let token = store
+            .get_or_refresh_with("alice", || async {
+                Err(anyhow::anyhow!("fetched despite a cached token"))
+            })
+            .await?;
+        assert_eq!(token, "cached");
+
+        // Another account's token is a miss
+        assert_eq!(store.load("bob"), None);
+        let token = store
+            .get_or_refresh_with("bob", || async { Ok("bob's".to_string()) })
+            .await?;
+        assert_eq!(token, "bob's");
+        assert_eq!(store.load("alice"), None);
+
+        let expired = TokenStore::new(&path).with_ttl(Duration::seconds(-1));
+        expired.save("stale", "alice")?;
+        assert_eq!(expired.load("alice"), None);
+        let token = expired
+            .get_or_refresh_with("alice", || async { Ok("fresh".to_string()) })
+            .await?;
+        assert_eq!(token, "fresh");
+        Ok(())
+    }
+
+    #[test]
+    fn test_saved_token_is_private() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("private_token.json");
+        std::fs::write(&path, "truncated")?;
+        TokenStore::new(&path).save("secret", "alice")?;
+
+        let cached: CachedToken = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        assert_eq!(cached.token, "secret");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path)?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_quote_auth_reads_the_store_per_call() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("quote_auth.json");
+        let store = TokenStore::new(&path);
+        let user = UserCookies::default();
+        let account = account_key(&user)?;
+        let auth = QuoteAuth::new(store.clone(), user);
+
+        store.save("first", &account)?;
+        assert_eq!(auth.token().await?, "first");
+        // A token refreshed by another run is picked up without rebuilding `auth`
+        store.save("second", &account)?;
+        assert_eq!(auth.token().await?, "second");
+        Ok(())
+    }
+}
@@ -1,3 +1,6 @@
 pub mod base;
 pub mod finance;
 pub mod utils;
+
+#[cfg(test)]
+mod test_support;
@@ -0,0 +1,47 @@
+//! Candle fixtures shared by the unit tests.
+
+use crate::finance::models::Candle;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+/// Start of the daily fixtures, 2024-01-01 00:00 UTC.
+pub fn fixture_start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+}
+
+/// A bar at `timestamp` with open, high and low equal to `close` and a volume of 1000.
+pub fn flat_candle(timestamp: DateTime<Utc>, close: f64) -> Candle {
+    Candle {
+        timestamp,
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume: 1000.0,
+        adj_close: None,
+    }
+}
+
+/// Flat daily bars from [`fixture_start`], one per entry of `closes`.
+pub fn daily_series(closes: &[f64]) -> Vec<Candle> {
+    closes
+        .iter()
+        .enumerate()
+        .map(|(i, &close)| flat_candle(fixture_start() + Duration::days(i as i64), close))
+        .collect()
+}
+
+/// `count` daily bars from [`fixture_start`] closing at 10, 11, 12 and so on, each opening
+/// half a point below its close with a range of one point either side.
+pub fn daily_candles(count: usize) -> Vec<Candle> {
+    (0..count)
+        .map(|i| {
+            let close = 10.0 + i as f64;
+            Candle {
+                open: close - 0.5,
+                high: close + 1.0,
+                low: close - 1.0,
+                ..flat_candle(fixture_start() + Duration::days(i as i64), close)
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,103 @@
+use crate::finance::models::Candle;
+use chrono::{DateTime, Utc};
+
+/// How [`to_grid`] fills grid timestamps that have no candle of their own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FillStrategy {
+    /// Carry the last known close forward as a flat, zero-volume bar. Grid points before
+    /// the first candle are dropped.
+    #[default]
+    ForwardFill,
+    /// Emit a bar with NaN prices and zero volume
+    Nan,
+    /// Skip the grid point, leaving only timestamps present in both series
+    Drop,
+}
+
+/// Align `candles` onto `grid`, e.g. the timestamps of a benchmark symbol.
+///
+/// Every output candle is stamped with a grid timestamp, in grid order. A grid point with
+/// an exact match takes that candle unchanged; otherwise it is filled according to `fill`.
+/// Forward fill only ever looks at candles at or before the grid point, so no future data
+/// leaks into a bar. Candles that fall between grid points are not emitted but still update
+/// the close carried forward. `grid` must be sorted ascending; `candles` may be in any order.
+pub fn to_grid(candles: &[Candle], grid: &[DateTime<Utc>], fill: FillStrategy) -> Vec<Candle> {
+    let mut sorted = candles.to_vec();
+    sorted.sort_by(Candle::cmp_by_timestamp);
+
+    let mut aligned = Vec::with_capacity(grid.len());
+    let mut next = 0;
+    let mut last = None;
+
+    for &timestamp in grid {
+        let mut exact = None;
+        while let Some(candle) = sorted.get(next).filter(|c| c.timestamp <= timestamp) {
+            last = Some((candle.close, candle.adj_close));
+            if candle.timestamp == timestamp {
+                exact = Some(candle.clone());
+            }
+            next += 1;
+        }
+
+        let filled = match (exact, fill) {
+            (Some(candle), _) => Some(candle),
+            (None, FillStrategy::ForwardFill) => {
+                last.map(|(close, adj_close)| flat(timestamp, close, adj_close))
+            }
+            (None, FillStrategy::Nan) => Some(flat(timestamp, f64::NAN, None)),
+            (None, FillStrategy::Drop) => None,
+        };
+        aligned.extend(filled);
+    }
+
+    aligned
+}
+
+fn flat(timestamp: DateTime<Utc>, price: f64, adj_close: Option<f64>) -> Candle {
+    Candle {
+        timestamp,
+        open: price,
+        high: price,
+        low: price,
+        close: price,
+        volume: 0.0,
+        adj_close,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{fixture_start, flat_candle};
+    use chrono::Duration;
+
+    fn at(day: i64) -> DateTime<Utc> {
+        fixture_start() + Duration::days(day)
+    }
+
+    fn candle(day: i64, close: f64) -> Candle {
+        flat_candle(at(day), close)
+    }
+
+    #[test]
+    fn test_to_grid_forward_fill_has_no_lookahead() {
+        // Out of order, missing day 2, and an off-grid bar on day 4 that must not leak into day 3
+        let candles = vec![candle(3, 13.0), candle(1, 11.0), candle(4, 14.0)];
+        let grid = (0..4).map(at).collect::<Vec<_>>();
+
+        let aligned = to_grid(&candles, &grid, FillStrategy::ForwardFill);
+        let closes = aligned
+            .iter()
+            .map(|c| (c.timestamp, c.close))
+            .collect::<Vec<_>>();
+        assert_eq!(closes, vec![(at(1), 11.0), (at(2), 11.0), (at(3), 13.0)]);
+        assert_eq!(aligned[1].volume, 0.0);
+
+        let dropped = to_grid(&candles, &grid, FillStrategy::Drop);
+        assert_eq!(dropped.len(), 2);
+
+        let nan = to_grid(&candles, &grid, FillStrategy::Nan);
+        assert_eq!(nan.len(), 4);
+        assert!(nan[0].close.is_nan() && nan[2].close.is_nan());
+    }
+}
@@ -0,0 +1,78 @@
+use serde::Deserialize;
+use std::path::Path;
+
+/// Config file looked up in the working directory when `--config` isn't given.
+pub const CONFIG_FILE: &str = "vnquant.toml";
+
+/// CLI defaults read from a `vnquant.toml` file.
+///
+/// A value only replaces the built-in default of the matching flag, so the precedence is
+/// explicit flag > environment variable > config file > built-in default. Intervals use
+/// the CLI spelling, e.g. `one-hour`: `interval` applies to the daily and longer commands,
+/// `intraday_interval` to the intraday fetches.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CliConfig {
+    pub database_url: Option<String>,
+    pub interval: Option<String>,
+    pub intraday_interval: Option<String>,
+    pub concurrency: Option<usize>,
+    pub rate_limit: Option<u32>,
+}
+
+impl CliConfig {
+    /// Parse the config file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read config {}: {}", path.display(), e))?;
+        toml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid config {}: {}", path.display(), e))
+    }
+
+    /// Load `path` if given, else [`CONFIG_FILE`] from the working directory if it exists.
+    ///
+    /// An explicit path that can't be read is an error; a missing default file is not.
+    pub fn discover(path: Option<&Path>) -> anyhow::Result<Self> {
+        match path {
+            Some(path) => Self::load(path),
+            None if Path::new(CONFIG_FILE).is_file() => Self::load(CONFIG_FILE),
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_config() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "database_url = \"sqlite://data.sqlite3\"\ninterval = \"one-day\"\n\
+             intraday_interval = \"one-hour\"\nconcurrency = 8\n",
+        )?;
+
+        let config = CliConfig::discover(Some(&path))?;
+        assert_eq!(
+            config,
+            CliConfig {
+                database_url: Some("sqlite://data.sqlite3".to_string()),
+                interval: Some("one-day".to_string()),
+                intraday_interval: Some("one-hour".to_string()),
+                concurrency: Some(8),
+                rate_limit: None,
+            }
+        );
+
+        std::fs::write(&path, "concurency = 8\n")?;
+        assert!(CliConfig::load(&path).is_err());
+
+        std::fs::remove_file(&path)?;
+        assert!(CliConfig::discover(Some(&path)).is_err());
+        Ok(())
+    }
+}
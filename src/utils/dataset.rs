@@ -0,0 +1,514 @@
+use crate::finance::{
+    db::Database,
+    models::{Candle, Ticker, interval_to_db_str},
+};
+use crate::utils::format::{
+    candle_schema, candles_to_batch, default_parquet_compression, parquet_properties,
+};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use futures::StreamExt;
+use parquet::arrow::ArrowWriter;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, hash_map::Entry};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use tradingview::Interval;
+
+/// Number of candles read from the database before they are routed to partitions.
+const EXPORT_CHUNK_SIZE: usize = 10_000;
+
+/// Name of the manifest written next to the files of an export.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Version of the candle schema written by the exports. Bump it when `candle_schema` changes.
+pub const DATASET_SCHEMA_VERSION: u32 = 1;
+
+/// A Parquet file written by an export, with its row count.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedFile {
+    /// Relative to the export directory in a [`DatasetManifest`], so the directory can be
+    /// moved or shared
+    pub path: PathBuf,
+    pub rows: usize,
+}
+
+/// Sidecar describing an exported dataset, stored as [`MANIFEST_FILE`] in the output directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatasetManifest {
+    pub generated_at: DateTime<Utc>,
+    /// Where the candles were fetched from
+    pub source: String,
+    pub schema_version: u32,
+    /// Latest database migration applied when the export ran, see
+    /// [`Database::schema_version`]; 0 in manifests written before it was recorded
+    #[serde(default)]
+    pub db_schema_version: i64,
+    /// Interval codes as stored in the database, e.g. `1D`
+    pub intervals: Vec<String>,
+    /// Exchanges of the exported tickers, sorted
+    pub exchanges: Vec<String>,
+    /// Number of tickers with at least one exported candle
+    pub ticker_count: usize,
+    /// Timestamp of the earliest exported candle
+    pub start: Option<DateTime<Utc>>,
+    /// Timestamp of the latest exported candle
+    pub end: Option<DateTime<Utc>>,
+    pub files: Vec<ExportedFile>,
+}
+
+impl DatasetManifest {
+    /// Empty manifest for an export of `intervals`, stamped with the current time.
+    pub fn new(intervals: &[Interval]) -> Self {
+        Self {
+            generated_at: Utc::now(),
+            source: "tradingview".to_string(),
+            schema_version: DATASET_SCHEMA_VERSION,
+            db_schema_version: 0,
+            intervals: intervals
+                .iter()
+                .map(|&i| interval_to_db_str(i).into_owned())
+                .collect(),
+            exchanges: Vec::new(),
+            ticker_count: 0,
+            start: None,
+            end: None,
+            files: Vec::new(),
+        }
+    }
+
+    /// Add the files written for one ticker under `out_dir` and widen the date range to
+    /// cover `range`. File paths are stored relative to `out_dir`.
+    fn record(
+        &mut self,
+        out_dir: &Path,
+        ticker: &Ticker,
+        files: Vec<ExportedFile>,
+        range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    ) {
+        if files.is_empty() {
+            return;
+        }
+
+        self.ticker_count += 1;
+        if let Err(pos) = self.exchanges.binary_search(&ticker.exchange) {
+            self.exchanges.insert(pos, ticker.exchange.clone());
+        }
+        if let Some((first, last)) = range {
+            self.start = Some(self.start.map_or(first, |start| start.min(first)));
+            self.end = Some(self.end.map_or(last, |end| end.max(last)));
+        }
+        self.files.extend(files.into_iter().map(|file| {
+            ExportedFile {
+                path: file
+                    .path
+                    .strip_prefix(out_dir)
+                    .map(Path::to_path_buf)
+                    .unwrap_or(file.path),
+                rows: file.rows,
+            }
+        }));
+    }
+
+    /// Write the manifest as pretty-printed JSON to `out_dir/manifest.json`.
+    pub fn write(&self, out_dir: &Path) -> anyhow::Result<()> {
+        std::fs::create_dir_all(out_dir)?;
+        let file = File::create(out_dir.join(MANIFEST_FILE))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// Read a manifest from `path`, either the JSON file itself or the export directory holding it.
+pub fn read_manifest(path: impl AsRef<Path>) -> anyhow::Result<DatasetManifest> {
+    let path = path.as_ref();
+    let path = if path.is_dir() {
+        path.join(MANIFEST_FILE)
+    } else {
+        path.to_path_buf()
+    };
+    Ok(serde_json::from_reader(File::open(path)?)?)
+}
+
+/// Widen `range` to include candles sorted by timestamp.
+fn extend_range(range: &mut Option<(DateTime<Utc>, DateTime<Utc>)>, candles: &[Candle]) {
+    if let (Some(first), Some(last)) = (candles.first(), candles.last()) {
+        let (start, end) = range.get_or_insert((first.timestamp, last.timestamp));
+        *start = (*start).min(first.timestamp);
+        *end = (*end).max(last.timestamp);
+    }
+}
+
+/// Routes candle batches to one Parquet file per partition path, opening writers lazily.
+pub struct PartitionedWriter {
+    writers: HashMap<PathBuf, (ArrowWriter<File>, usize)>,
+}
+
+impl Default for PartitionedWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartitionedWriter {
+    pub fn new() -> Self {
+        Self {
+            writers: HashMap::new(),
+        }
+    }
+
+    /// Append candles to the file at `path`, creating it and its directories on first use.
+    pub fn write(&mut self, path: &Path, candles: &[Candle]) -> anyhow::Result<()> {
+        if candles.is_empty() {
+            return Ok(());
+        }
+
+        let (writer, rows) = match self.writers.entry(path.to_path_buf()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let writer = ArrowWriter::try_new(
+                    File::create(path)?,
+                    candle_schema(),
+                    Some(parquet_properties(default_parquet_compression())),
+                )?;
+                entry.insert((writer, 0))
+            }
+        };
+        writer.write(&candles_to_batch(candles)?)?;
+        *rows += candles.len();
+        Ok(())
+    }
+
+    /// Close every open writer and return the files written, sorted by path.
+    pub fn close_all(&mut self) -> anyhow::Result<Vec<ExportedFile>> {
+        let mut files = Vec::with_capacity(self.writers.len());
+        for (path, (writer, rows)) in self.writers.drain() {
+            writer.close()?;
+            files.push(ExportedFile { path, rows });
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+}
+
+/// Partition directory for one ticker, interval and year, in Hive style:
+/// `interval=1D/year=2024/exchange=HOSE/symbol=VCB/part.parquet`.
+///
+/// Fails when the symbol or exchange is empty, `.` or `..`, or holds a path separator, so a
+/// stored ticker can't write outside `out_dir`.
+pub fn partition_path(
+    out_dir: &Path,
+    ticker: &Ticker,
+    interval: Interval,
+    year: i32,
+) -> anyhow::Result<PathBuf> {
+    Ok(out_dir
+        .join(format!("interval={}", interval_to_db_str(interval)))
+        .join(format!("year={year}"))
+        .join(format!("exchange={}", path_component(&ticker.exchange)?))
+        .join(format!("symbol={}", path_component(&ticker.symbol)?))
+        .join("part.parquet"))
+}
+
+/// Check that `value` stays a single path component inside the export directory.
+///
+/// Rejects empty values, `.` and `..`, and values holding a path separator or NUL.
+fn path_component(value: &str) -> anyhow::Result<&str> {
+    if value.is_empty() || value == "." || value == ".." || value.contains(['/', '\\', '\0']) {
+        return Err(anyhow::anyhow!(
+            "{:?} can't be used in an export path",
+            value
+        ));
+    }
+    Ok(value)
+}
+
+/// Values substituted into an export file name template by [`render_file_template`].
+#[derive(Debug, Clone, Default)]
+pub struct TemplateVars<'a> {
+    pub symbol: Option<&'a str>,
+    pub exchange: Option<&'a str>,
+    pub interval: Option<Interval>,
+    /// Export date, rendered as `YYYY-MM-DD`
+    pub date: Option<NaiveDate>,
+}
+
+/// Placeholders accepted by [`render_file_template`].
+pub const TEMPLATE_PLACEHOLDERS: [&str; 4] = ["symbol", "exchange", "interval", "date"];
+
+/// Fill `{symbol}`, `{exchange}`, `{interval}` and `{date}` in a file name template such as
+/// `{exchange}_{symbol}_{interval}.parquet`.
+///
+/// Intervals use their database code, e.g. `1D`. Fails on unknown placeholders, unbalanced
+/// braces and placeholders with no value for this export, e.g. `{interval}` for tickers.
+pub fn render_file_template(template: &str, vars: &TemplateVars) -> anyhow::Result<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return Err(anyhow::anyhow!("Unmatched '}}' in template {template}"));
+        }
+        rendered.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            return Err(anyhow::anyhow!("Unclosed '{{' in template {template}"));
+        };
+        let name = &rest[start + 1..start + len];
+        let value = match name {
+            "symbol" => vars.symbol.map(str::to_string),
+            "exchange" => vars.exchange.map(str::to_string),
+            "interval" => vars.interval.map(|i| interval_to_db_str(i).into_owned()),
+            "date" => vars.date.map(|d| d.format("%Y-%m-%d").to_string()),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Unknown placeholder {{{name}}} in template {template}, expected one of {}",
+                    TEMPLATE_PLACEHOLDERS.map(|p| format!("{{{p}}}")).join(", ")
+                ));
+            }
+        };
+        let Some(value) = value else {
+            return Err(anyhow::anyhow!(
+                "Placeholder {{{name}}} has no value for this export"
+            ));
+        };
+        rendered.push_str(&value);
+        rest = &rest[start + len + 1..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// Export stored candles partitioned by interval, year, exchange and symbol.
+///
+/// Candles are streamed from the database, so only one chunk per ticker is held in memory.
+/// A [`DatasetManifest`] is written to `out_dir` once all partitions are closed.
+pub async fn export_partitioned_prices(
+    db: &Database,
+    tickers: &[Ticker],
+    intervals: &[Interval],
+    out_dir: &str,
+) -> anyhow::Result<DatasetManifest> {
+    let out_dir = Path::new(out_dir);
+    let mut manifest = DatasetManifest::new(intervals);
+    manifest.db_schema_version = db.schema_version().await?;
+
+    for ticker in tickers {
+        let mut files = Vec::new();
+        let mut range = None;
+
+        for &interval in intervals {
+            let mut writer = PartitionedWriter::new();
+            let mut chunks =
+                std::pin::pin!(db.stream_prices(ticker, interval).chunks(EXPORT_CHUNK_SIZE));
+
+            while let Some(chunk) = chunks.next().await {
+                let chunk = chunk.into_iter().collect::<anyhow::Result<Vec<_>>>()?;
+                extend_range(&mut range, &chunk);
+
+                let mut by_year: BTreeMap<i32, Vec<Candle>> = BTreeMap::new();
+                for candle in chunk {
+                    by_year
+                        .entry(candle.timestamp.year())
+                        .or_default()
+                        .push(candle);
+                }
+                for (year, candles) in by_year {
+                    writer.write(&partition_path(out_dir, ticker, interval, year)?, &candles)?;
+                }
+            }
+
+            files.extend(writer.close_all()?);
+        }
+
+        manifest.record(out_dir, ticker, files, range);
+    }
+
+    manifest.write(out_dir)?;
+    tracing::info!(
+        "Exported {} partition files to {}",
+        manifest.files.len(),
+        out_dir.display()
+    );
+    Ok(manifest)
+}
+
+/// Export the `interval` candles of every stored ticker to `out_dir/{exchange}/{symbol}.parquet`.
+///
+/// Tickers are read page by page and each ticker's candles are streamed into its writer,
+/// so memory use stays bounded. Tickers without candles get no file. A [`DatasetManifest`]
+/// listing the files and their row counts is written to `out_dir` last.
+pub async fn export_all_prices_parquet(
+    db: &Database,
+    interval: Interval,
+    out_dir: &str,
+) -> anyhow::Result<DatasetManifest> {
+    let out_dir = Path::new(out_dir);
+    let mut manifest = DatasetManifest::new(&[interval]);
+    manifest.db_schema_version = db.schema_version().await?;
+    let mut tickers = std::pin::pin!(db.stream_all_tickers());
+
+    while let Some(ticker) = tickers.next().await {
+        let ticker = ticker?;
+        let path = out_dir
+            .join(path_component(&ticker.exchange)?)
+            .join(format!("{}.parquet", path_component(&ticker.symbol)?));
+        let mut writer = PartitionedWriter::new();
+        let mut range = None;
+        let mut chunks = std::pin::pin!(
+            db.stream_prices(&ticker, interval)
+                .chunks(EXPORT_CHUNK_SIZE)
+        );
+
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk.into_iter().collect::<anyhow::Result<Vec<_>>>()?;
+            extend_range(&mut range, &chunk);
+            writer.write(&path, &chunk)?;
+        }
+
+        manifest.record(out_dir, &ticker, writer.close_all()?, range);
+    }
+
+    manifest.write(out_dir)?;
+    tracing::info!(
+        "Exported {} ticker files to {}",
+        manifest.files.len(),
+        out_dir.display()
+    );
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{daily_candles, flat_candle};
+    use chrono::{TimeZone, Utc};
+    use tradingview::MarketSymbol;
+
+    #[test]
+    fn test_render_file_template() -> anyhow::Result<()> {
+        let vars = TemplateVars {
+            symbol: Some("VCB"),
+            exchange: Some("HOSE"),
+            interval: Some(Interval::OneDay),
+            date: NaiveDate::from_ymd_opt(2024, 3, 1),
+        };
+        assert_eq!(
+            render_file_template(
+                "{exchange}/{exchange}_{symbol}_{interval}_{date}.parquet",
+                &vars
+            )?,
+            "HOSE/HOSE_VCB_1D_2024-03-01.parquet"
+        );
+        assert_eq!(render_file_template("tickers.csv", &vars)?, "tickers.csv");
+
+        for template in ["{ticker}.csv", "{symbol.csv", "symbol}.csv", "{}.csv"] {
+            assert!(render_file_template(template, &vars).is_err(), "{template}");
+        }
+        let tickers_only = TemplateVars {
+            exchange: Some("HOSE"),
+            ..Default::default()
+        };
+        assert!(render_file_template("{exchange}_{interval}.csv", &tickers_only).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_partitioned_writer_routes_by_year() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let out_dir = dir.path().join("partitions");
+        let ticker = Ticker {
+            symbol: "VCB".to_string(),
+            exchange: "HOSE".to_string(),
+            ..Default::default()
+        };
+        let candle =
+            |year: i32| flat_candle(Utc.with_ymd_and_hms(year, 6, 1, 0, 0, 0).unwrap(), 1.0);
+
+        let mut writer = PartitionedWriter::new();
+        for year in [2023, 2024, 2024] {
+            writer.write(
+                &partition_path(&out_dir, &ticker, Interval::OneDay, year)?,
+                &[candle(year)],
+            )?;
+        }
+        let files = writer.close_all()?;
+
+        assert_eq!(files.len(), 2);
+        assert!(files[0].path.to_string_lossy().contains("year=2023"));
+        assert_eq!(files[1].rows, 2);
+
+        for symbol in ["../../etc", "A/B", "..", ""] {
+            let ticker = Ticker::new(symbol, "HOSE");
+            assert!(partition_path(&out_dir, &ticker, Interval::OneDay, 2024).is_err());
+        }
+        let ticker = Ticker::new("VCB", "..");
+        assert!(partition_path(&out_dir, &ticker, Interval::OneDay, 2024).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_export_all_prices_parquet() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let out_dir = dir.path().join("export_all");
+        let db = Database::new("sqlite::memory:").await?;
+        let tickers = [Ticker::new("VCB", "HOSE"), Ticker::new("SHS", "HNX")];
+        db.upsert_tickers(&tickers).await?;
+        for (ticker, count) in tickers.iter().zip([3, 2]) {
+            db.upsert_prices(ticker, Interval::OneDay, &daily_candles(count))
+                .await?;
+        }
+
+        let manifest =
+            export_all_prices_parquet(&db, Interval::OneDay, out_dir.to_str().unwrap()).await?;
+        let read_back = read_manifest(&out_dir)?;
+        let files = &manifest.files;
+        let on_disk = files
+            .iter()
+            .all(|f| f.path.is_relative() && out_dir.join(&f.path).exists());
+
+        assert_eq!(files.len(), 2);
+        assert!(on_disk);
+        assert_eq!(read_back, manifest);
+        assert_eq!(manifest.db_schema_version, db.schema_version().await?);
+        assert!(
+            files
+                .iter()
+                .any(|f| f.path == Path::new("HNX/SHS.parquet") && f.rows == 2)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_manifest_round_trips_through_json() -> anyhow::Result<()> {
+        let ticker = Ticker {
+            symbol: "VCB".to_string(),
+            exchange: "HOSE".to_string(),
+            ..Default::default()
+        };
+        let first = Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap();
+        let last = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let mut manifest = DatasetManifest::new(&[Interval::OneDay]);
+        manifest.record(
+            Path::new("/data/export"),
+            &ticker,
+            vec![ExportedFile {
+                path: PathBuf::from("/data/export/HOSE/VCB.parquet"),
+                rows: 42,
+            }],
+            Some((first, last)),
+        );
+
+        let json = serde_json::to_string(&manifest)?;
+        let parsed: DatasetManifest = serde_json::from_str(&json)?;
+
+        assert_eq!(parsed, manifest);
+        assert_eq!(parsed.source, "tradingview");
+        assert_eq!(parsed.intervals, ["1D"]);
+        assert_eq!(parsed.exchanges, ["HOSE"]);
+        assert_eq!((parsed.start, parsed.end), (Some(first), Some(last)));
+        assert_eq!(parsed.files[0].path, Path::new("HOSE/VCB.parquet"));
+        assert_eq!(parsed.files[0].rows, 42);
+        Ok(())
+    }
+}
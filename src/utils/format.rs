@@ -1,13 +1,29 @@
-use crate::finance::models::Ticker;
+use crate::finance::models::{Candle, MlFeatures, Ticker};
 use arrow::array::*;
 use arrow::{
     array::{ArrayRef, Int64Array, RecordBatch, StringArray},
-    datatypes::{DataType, Field, Schema, SchemaRef},
+    datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit},
 };
 use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use std::collections::HashSet;
 use std::fs::File;
 use std::sync::Arc;
 
+/// Compression used by the Parquet exports unless the caller picks another: Zstd at its
+/// default level.
+pub fn default_parquet_compression() -> Compression {
+    Compression::ZSTD(ZstdLevel::default())
+}
+
+/// Writer properties that compress every column with `compression`.
+pub fn parquet_properties(compression: Compression) -> WriterProperties {
+    WriterProperties::builder()
+        .set_compression(compression)
+        .build()
+}
+
 pub fn ticker_schema() -> SchemaRef {
     Arc::new(Schema::new(vec![
         Field::new("symbol", DataType::Utf8, false),
@@ -103,13 +119,15 @@ pub fn to_batch(tickers: Vec<Ticker>) -> arrow::error::Result<RecordBatch> {
 }
 
 /// Export tickers to Parquet file
-pub fn save_parquet(tickers: Vec<Ticker>, path: &str) -> anyhow::Result<()> {
-    use parquet::arrow::ArrowWriter;
-    use std::fs::File;
-
+pub fn save_parquet(
+    tickers: Vec<Ticker>,
+    path: &str,
+    compression: Compression,
+) -> anyhow::Result<()> {
     let batch = to_batch(tickers)?;
     let file = File::create(path)?;
-    let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+    let mut writer =
+        ArrowWriter::try_new(file, batch.schema(), Some(parquet_properties(compression)))?;
 
     writer.write(&batch)?;
     writer.close()?;
@@ -118,10 +136,16 @@ pub fn save_parquet(tickers: Vec<Ticker>, path: &str) -> anyhow::Result<()> {
 }
 
 /// Export tickers in batches to Parquet (for large datasets)
+///
+/// Only `batch_size` tickers are converted to Arrow at a time. Row groups are cut by the
+/// writer every `max_row_group_size` rows of `properties`; use [`parquet_properties`] and
+/// `WriterProperties::into_builder` to tune it. `None` uses the default compression and
+/// the Parquet default row-group size.
 pub fn save_parquet_batched(
     tickers: Vec<Ticker>,
     path: &str,
     batch_size: usize,
+    properties: Option<WriterProperties>,
 ) -> anyhow::Result<()> {
     if tickers.is_empty() {
         return Ok(());
@@ -129,9 +153,11 @@ pub fn save_parquet_batched(
 
     let schema = ticker_schema();
     let file = File::create(path)?;
-    let mut writer = ArrowWriter::try_new(file, schema, None)?;
+    let properties =
+        properties.unwrap_or_else(|| parquet_properties(default_parquet_compression()));
+    let mut writer = ArrowWriter::try_new(file, schema, Some(properties))?;
 
-    for chunk in tickers.chunks(batch_size) {
+    for chunk in tickers.chunks(batch_size.max(1)) {
         let batch = to_batch(chunk.to_vec())?;
         writer.write(&batch)?;
     }
@@ -141,95 +167,886 @@ pub fn save_parquet_batched(
 }
 
 pub fn from_batch(batch: &RecordBatch) -> anyhow::Result<Vec<Ticker>> {
-    let symbols = batch
-        .column(0)
-        .as_any()
-        .downcast_ref::<StringArray>()
-        .unwrap();
-    let exchanges = batch
-        .column(1)
-        .as_any()
-        .downcast_ref::<StringArray>()
-        .unwrap();
-    let descriptions = batch
-        .column(2)
-        .as_any()
-        .downcast_ref::<StringArray>()
-        .unwrap();
-    let currencies = batch
-        .column(3)
-        .as_any()
-        .downcast_ref::<StringArray>()
-        .unwrap();
-    let countries = batch
-        .column(4)
-        .as_any()
-        .downcast_ref::<StringArray>()
-        .unwrap();
-    let market_types = batch
-        .column(5)
-        .as_any()
-        .downcast_ref::<StringArray>()
-        .unwrap();
-    let industries = batch
-        .column(6)
-        .as_any()
-        .downcast_ref::<StringArray>()
-        .unwrap();
-    let sectors = batch
-        .column(7)
-        .as_any()
-        .downcast_ref::<StringArray>()
-        .unwrap();
-    let founded = batch
-        .column(8)
-        .as_any()
-        .downcast_ref::<Int64Array>()
-        .unwrap();
-
-    let mut tickers = Vec::with_capacity(batch.num_rows());
-
-    for i in 0..batch.num_rows() {
-        tickers.push(Ticker {
-            symbol: symbols.value(i).to_string(),
-            exchange: exchanges.value(i).to_string(),
-            description: if descriptions.is_null(i) {
-                None
-            } else {
-                Some(descriptions.value(i).to_string())
-            },
-            currency: if currencies.is_null(i) {
-                None
-            } else {
-                Some(currencies.value(i).to_string())
-            },
-            country: if countries.is_null(i) {
-                None
-            } else {
-                Some(countries.value(i).to_string())
-            },
-            market_type: if market_types.is_null(i) {
-                None
-            } else {
-                Some(market_types.value(i).to_string())
-            },
-            industry: if industries.is_null(i) {
-                None
-            } else {
-                Some(industries.value(i).to_string())
-            },
-            sector: if sectors.is_null(i) {
-                None
-            } else {
-                Some(sectors.value(i).to_string())
-            },
-            founded: if founded.is_null(i) {
-                None
-            } else {
-                Some(founded.value(i))
-            },
-        });
+    (0..batch.num_rows())
+        .map(|row| Ticker::try_from((batch, row)))
+        .collect()
+}
+
+/// Read tickers back from a Parquet file written by [`save_parquet`].
+pub fn load_tickers_parquet(path: &str) -> anyhow::Result<Vec<Ticker>> {
+    let file = File::open(path)?;
+    let reader =
+        parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
+
+    let mut tickers = Vec::new();
+    for batch in reader {
+        tickers.extend(from_batch(&batch?)?);
     }
+    Ok(tickers)
+}
 
+/// Read tickers back from a CSV file with a header row, as written by [`save_csv`].
+pub fn load_tickers_csv(path: &str) -> anyhow::Result<Vec<Ticker>> {
+    let file = File::open(path)?;
+    let reader = arrow::csv::ReaderBuilder::new(ticker_schema())
+        .with_header(true)
+        .build(file)?;
+
+    let mut tickers = Vec::new();
+    for batch in reader {
+        tickers.extend(from_batch(&batch?)?);
+    }
     Ok(tickers)
 }
+
+/// Look up a column by name and downcast it to the expected array type.
+fn typed_column<'a, A: Array + 'static>(
+    batch: &'a RecordBatch,
+    name: &str,
+) -> anyhow::Result<&'a A> {
+    let column = batch
+        .column_by_name(name)
+        .ok_or_else(|| anyhow::anyhow!("Missing column '{}'", name))?;
+    column.as_any().downcast_ref::<A>().ok_or_else(|| {
+        anyhow::anyhow!(
+            "Column '{}' has unexpected type {}",
+            name,
+            column.data_type()
+        )
+    })
+}
+
+fn optional_string(batch: &RecordBatch, name: &str, row: usize) -> anyhow::Result<Option<String>> {
+    let array = typed_column::<StringArray>(batch, name)?;
+    Ok((!array.is_null(row)).then(|| array.value(row).to_string()))
+}
+
+/// Reject a null in a column the schema declares non-nullable, instead of reading the
+/// zero Arrow stores behind it.
+fn ensure_not_null(array: &dyn Array, name: &str, row: usize) -> anyhow::Result<()> {
+    if array.is_null(row) {
+        return Err(anyhow::anyhow!(
+            "Null value in non-nullable column '{}' at row {}",
+            name,
+            row
+        ));
+    }
+    Ok(())
+}
+
+fn check_row(batch: &RecordBatch, row: usize) -> anyhow::Result<()> {
+    if row >= batch.num_rows() {
+        return Err(anyhow::anyhow!(
+            "Row {} out of bounds for batch with {} rows",
+            row,
+            batch.num_rows()
+        ));
+    }
+    Ok(())
+}
+
+impl TryFrom<(&RecordBatch, usize)> for Ticker {
+    type Error = anyhow::Error;
+
+    fn try_from((batch, row): (&RecordBatch, usize)) -> anyhow::Result<Self> {
+        check_row(batch, row)?;
+        let founded = typed_column::<Int64Array>(batch, "founded")?;
+
+        Ok(Ticker {
+            symbol: typed_column::<StringArray>(batch, "symbol")?
+                .value(row)
+                .to_string(),
+            exchange: typed_column::<StringArray>(batch, "exchange")?
+                .value(row)
+                .to_string(),
+            description: optional_string(batch, "description", row)?,
+            currency: optional_string(batch, "currency", row)?,
+            country: optional_string(batch, "country", row)?,
+            market_type: optional_string(batch, "market_type", row)?,
+            industry: optional_string(batch, "industry", row)?,
+            sector: optional_string(batch, "sector", row)?,
+            founded: (!founded.is_null(row)).then(|| founded.value(row)),
+        })
+    }
+}
+
+impl TryFrom<(&RecordBatch, usize)> for Candle {
+    type Error = anyhow::Error;
+
+    fn try_from((batch, row): (&RecordBatch, usize)) -> anyhow::Result<Self> {
+        check_row(batch, row)?;
+        let timestamps = typed_column::<TimestampMillisecondArray>(batch, "timestamp")?;
+        ensure_not_null(timestamps, "timestamp", row)?;
+        let millis = timestamps.value(row);
+        let value = |name: &str| -> anyhow::Result<f64> {
+            let array = typed_column::<Float64Array>(batch, name)?;
+            ensure_not_null(array, name, row)?;
+            Ok(array.value(row))
+        };
+
+        Ok(Candle {
+            timestamp: chrono::DateTime::from_timestamp_millis(millis)
+                .ok_or_else(|| anyhow::anyhow!("Invalid timestamp {} at row {}", millis, row))?,
+            open: value("open")?,
+            high: value("high")?,
+            low: value("low")?,
+            close: value("close")?,
+            volume: value("volume")?,
+            adj_close: match batch.column_by_name("adj_close") {
+                Some(_) => {
+                    let array = typed_column::<Float64Array>(batch, "adj_close")?;
+                    (!array.is_null(row)).then(|| array.value(row))
+                }
+                // Files written before adjusted closes were exported
+                None => None,
+            },
+        })
+    }
+}
+
+/// Convert an Arrow RecordBatch with the candle schema back to candles
+pub fn candles_from_batch(batch: &RecordBatch) -> anyhow::Result<Vec<Candle>> {
+    (0..batch.num_rows())
+        .map(|row| Candle::try_from((batch, row)))
+        .collect()
+}
+
+pub fn candle_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+            false,
+        ),
+        Field::new("open", DataType::Float64, false),
+        Field::new("high", DataType::Float64, false),
+        Field::new("low", DataType::Float64, false),
+        Field::new("close", DataType::Float64, false),
+        Field::new("volume", DataType::Float64, false),
+        Field::new("adj_close", DataType::Float64, true),
+    ]))
+}
+
+/// Convert a slice of candles to Arrow RecordBatch
+pub fn candles_to_batch(candles: &[Candle]) -> arrow::error::Result<RecordBatch> {
+    let timestamps: ArrayRef = Arc::new(
+        TimestampMillisecondArray::from(
+            candles
+                .iter()
+                .map(|c| c.timestamp.timestamp_millis())
+                .collect::<Vec<_>>(),
+        )
+        .with_timezone("UTC"),
+    );
+    let opens: ArrayRef = Arc::new(Float64Array::from(
+        candles.iter().map(|c| c.open).collect::<Vec<_>>(),
+    ));
+    let highs: ArrayRef = Arc::new(Float64Array::from(
+        candles.iter().map(|c| c.high).collect::<Vec<_>>(),
+    ));
+    let lows: ArrayRef = Arc::new(Float64Array::from(
+        candles.iter().map(|c| c.low).collect::<Vec<_>>(),
+    ));
+    let closes: ArrayRef = Arc::new(Float64Array::from(
+        candles.iter().map(|c| c.close).collect::<Vec<_>>(),
+    ));
+    let volumes: ArrayRef = Arc::new(Float64Array::from(
+        candles.iter().map(|c| c.volume).collect::<Vec<_>>(),
+    ));
+    let adj_closes: ArrayRef = Arc::new(Float64Array::from(
+        candles.iter().map(|c| c.adj_close).collect::<Vec<_>>(),
+    ));
+
+    RecordBatch::try_new(
+        candle_schema(),
+        vec![timestamps, opens, highs, lows, closes, volumes, adj_closes],
+    )
+}
+
+/// Export candles to Parquet file
+pub fn save_candles_parquet(
+    candles: &[Candle],
+    path: &str,
+    compression: Compression,
+) -> anyhow::Result<()> {
+    let batch = candles_to_batch(candles)?;
+    let file = File::create(path)?;
+    let mut writer =
+        ArrowWriter::try_new(file, batch.schema(), Some(parquet_properties(compression)))?;
+
+    writer.write(&batch)?;
+    writer.close()?;
+
+    Ok(())
+}
+
+/// Add `candles` to the Parquet file at `path` as a new row group, creating the file if it
+/// doesn't exist yet.
+///
+/// Parquet files can't be extended in place, so the stored row groups are copied one by one
+/// into a temporary file followed by the new row group, which then replaces the original.
+/// Candles whose timestamp is already stored are skipped, so appending an overlapping range
+/// doesn't duplicate rows. The existing file must have the candle schema. The rewritten file
+/// uses [`default_parquet_compression`].
+pub fn append_candles_parquet(path: &str, candles: &[Candle]) -> anyhow::Result<()> {
+    if !std::path::Path::new(path).exists() {
+        return save_candles_parquet(candles, path, default_parquet_compression());
+    }
+    if candles.is_empty() {
+        return Ok(());
+    }
+
+    let builder =
+        parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)?;
+    let schema = builder.schema().clone();
+    if schema.fields() != candle_schema().fields() {
+        return Err(anyhow::anyhow!(
+            "Cannot append candles to {}: the file does not have the candle schema",
+            path
+        ));
+    }
+
+    let tmp = format!("{path}.tmp");
+    let mut writer = ArrowWriter::try_new(
+        File::create(&tmp)?,
+        schema,
+        Some(parquet_properties(default_parquet_compression())),
+    )?;
+    let mut stored = HashSet::new();
+    for row_group in 0..builder.metadata().num_row_groups() {
+        let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(
+            File::open(path)?,
+        )?
+        .with_row_groups(vec![row_group])
+        .build()?;
+        for batch in reader {
+            let batch = batch?;
+            stored.extend(candles_from_batch(&batch)?.into_iter().map(|c| c.timestamp));
+            writer.write(&batch)?;
+        }
+        // Close the row group so each append keeps its own
+        writer.flush()?;
+    }
+
+    let new = candles
+        .iter()
+        .filter(|c| stored.insert(c.timestamp))
+        .cloned()
+        .collect::<Vec<_>>();
+    if new.is_empty() {
+        drop(writer);
+        std::fs::remove_file(&tmp)?;
+        return Ok(());
+    }
+    writer.write(&candles_to_batch(&new)?)?;
+    writer.close()?;
+    std::fs::rename(&tmp, path)?;
+
+    Ok(())
+}
+
+/// Export a stream of candles to Parquet, writing one row group per `batch_size` candles.
+///
+/// Only one batch is held in memory at a time. Returns the number of rows written.
+pub async fn save_candles_parquet_streaming<S>(
+    candles: S,
+    path: &str,
+    batch_size: usize,
+) -> anyhow::Result<usize>
+where
+    S: futures::Stream<Item = anyhow::Result<Candle>>,
+{
+    use futures::StreamExt;
+
+    let file = File::create(path)?;
+    let mut writer = ArrowWriter::try_new(file, candle_schema(), None)?;
+    let mut rows = 0;
+
+    let mut chunks = std::pin::pin!(candles.chunks(batch_size.max(1)));
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk.into_iter().collect::<anyhow::Result<Vec<_>>>()?;
+        writer.write(&candles_to_batch(&chunk)?)?;
+        writer.flush()?;
+        rows += chunk.len();
+    }
+
+    writer.close()?;
+    Ok(rows)
+}
+
+/// Export a RecordBatch to a CSV file with a header row
+pub fn save_batch_csv(batch: &RecordBatch, path: &str) -> anyhow::Result<()> {
+    let file = File::create(path)?;
+    let mut writer = arrow::csv::WriterBuilder::new()
+        .with_header(true)
+        .build(file);
+
+    writer.write(batch)?;
+
+    Ok(())
+}
+
+/// Export tickers to CSV file
+pub fn save_csv(tickers: Vec<Ticker>, path: &str) -> anyhow::Result<()> {
+    save_batch_csv(&to_batch(tickers)?, path)
+}
+
+/// Write a RecordBatch as a JSON array of row objects
+pub fn save_batch_json(batch: &RecordBatch, path: &str) -> anyhow::Result<()> {
+    let file = File::create(path)?;
+    let mut writer = arrow::json::ArrayWriter::new(file);
+
+    writer.write(batch)?;
+    writer.finish()?;
+
+    Ok(())
+}
+
+/// Relabel the UTC `timestamp` column of a candle batch with `tz`.
+///
+/// The stored instants don't change; only the offset they are rendered with in CSV and
+/// JSON output does, e.g. `2024-01-02T07:00:00+07:00` for a UTC-midnight bar in Vietnam.
+pub fn with_output_timezone(
+    batch: &RecordBatch,
+    tz: chrono_tz::Tz,
+) -> arrow::error::Result<RecordBatch> {
+    let target = DataType::Timestamp(TimeUnit::Millisecond, Some(tz.name().into()));
+    let schema = batch.schema();
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    let mut columns = Vec::with_capacity(batch.num_columns());
+
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        if field.name() == "timestamp" {
+            fields.push(Arc::new(
+                field.as_ref().clone().with_data_type(target.clone()),
+            ));
+            columns.push(arrow::compute::cast(column, &target)?);
+        } else {
+            fields.push(field.clone());
+            columns.push(column.clone());
+        }
+    }
+
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+}
+
+fn candles_to_output_batch(
+    candles: &[Candle],
+    tz: Option<chrono_tz::Tz>,
+) -> arrow::error::Result<RecordBatch> {
+    let batch = candles_to_batch(candles)?;
+    match tz {
+        Some(tz) => with_output_timezone(&batch, tz),
+        None => Ok(batch),
+    }
+}
+
+/// Export candles to CSV file, rendering timestamps in `tz` (UTC when `None`)
+pub fn save_candles_csv(
+    candles: &[Candle],
+    path: &str,
+    tz: Option<chrono_tz::Tz>,
+) -> anyhow::Result<()> {
+    save_batch_csv(&candles_to_output_batch(candles, tz)?, path)
+}
+
+/// Export candles to a JSON array, rendering timestamps in `tz` (UTC when `None`)
+pub fn save_candles_json(
+    candles: &[Candle],
+    path: &str,
+    tz: Option<chrono_tz::Tz>,
+) -> anyhow::Result<()> {
+    save_batch_json(&candles_to_output_batch(candles, tz)?, path)
+}
+
+/// Version of the ML feature schema written by [`ml_features_schema`]. Bump it whenever an
+/// optional column is added to `ML_FEATURE_COLUMNS`.
+pub const ML_FEATURES_SCHEMA_VERSION: u32 = 1;
+
+/// Schema metadata key holding the ML feature schema version
+const ML_FEATURES_VERSION_KEY: &str = "vnquant.ml_features.schema_version";
+
+/// Optional indicator columns of the ML feature schema, in column order, with the schema
+/// version that introduced them
+const ML_FEATURE_COLUMNS: [(&str, u32); 6] = [
+    ("rsi", 1),
+    ("mfi", 1),
+    ("sma_20", 1),
+    ("ema_12", 1),
+    ("price_change_pct", 1),
+    ("volatility_pct", 1),
+];
+
+pub fn ml_features_schema() -> SchemaRef {
+    let mut fields = vec![
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+            false,
+        ),
+        Field::new("open", DataType::Float64, false),
+        Field::new("high", DataType::Float64, false),
+        Field::new("low", DataType::Float64, false),
+        Field::new("close", DataType::Float64, false),
+        Field::new("volume", DataType::Float64, false),
+    ];
+    fields.extend(
+        ML_FEATURE_COLUMNS
+            .iter()
+            .map(|(name, _)| Field::new(*name, DataType::Float64, true)),
+    );
+    let metadata = std::collections::HashMap::from([(
+        ML_FEATURES_VERSION_KEY.to_string(),
+        ML_FEATURES_SCHEMA_VERSION.to_string(),
+    )]);
+    Arc::new(Schema::new(fields).with_metadata(metadata))
+}
+
+/// ML feature schema version recorded in `schema`.
+///
+/// Files written before the version was recorded report version 0.
+fn ml_features_schema_version(schema: &Schema) -> anyhow::Result<u32> {
+    schema
+        .metadata()
+        .get(ML_FEATURES_VERSION_KEY)
+        .map(|v| {
+            v.parse()
+                .map_err(|_| anyhow::anyhow!("Invalid ML feature schema version '{}'", v))
+        })
+        .transpose()
+        .map(|version| version.unwrap_or(0))
+}
+
+fn ml_feature_values(features: &MlFeatures) -> [Option<f64>; 6] {
+    [
+        features.rsi,
+        features.mfi,
+        features.sma_20,
+        features.ema_12,
+        features.price_change_pct,
+        features.volatility_pct,
+    ]
+}
+
+/// Convert Vec<MlFeatures> to Arrow RecordBatch. Missing indicators are written as nulls.
+pub fn ml_features_to_batch(features: Vec<MlFeatures>) -> arrow::error::Result<RecordBatch> {
+    let timestamps: ArrayRef = Arc::new(
+        TimestampMillisecondArray::from(
+            features
+                .iter()
+                .map(|f| f.timestamp.timestamp_millis())
+                .collect::<Vec<_>>(),
+        )
+        .with_timezone("UTC"),
+    );
+    let required = |value: fn(&MlFeatures) -> f64| -> ArrayRef {
+        Arc::new(Float64Array::from(
+            features.iter().map(value).collect::<Vec<_>>(),
+        ))
+    };
+
+    let mut columns = vec![
+        timestamps,
+        required(|f| f.open),
+        required(|f| f.high),
+        required(|f| f.low),
+        required(|f| f.close),
+        required(|f| f.volume),
+    ];
+    columns.extend((0..ML_FEATURE_COLUMNS.len()).map(|index| -> ArrayRef {
+        Arc::new(Float64Array::from(
+            features
+                .iter()
+                .map(|f| ml_feature_values(f)[index])
+                .collect::<Vec<_>>(),
+        ))
+    }));
+
+    RecordBatch::try_new(ml_features_schema(), columns)
+}
+
+/// Export ML features to Parquet file
+pub fn save_ml_features_parquet(features: Vec<MlFeatures>, path: &str) -> anyhow::Result<()> {
+    let batch = ml_features_to_batch(features)?;
+    let file = File::create(path)?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+
+    writer.write(&batch)?;
+    writer.close()?;
+
+    Ok(())
+}
+
+impl TryFrom<(&RecordBatch, usize)> for MlFeatures {
+    type Error = anyhow::Error;
+
+    fn try_from((batch, row): (&RecordBatch, usize)) -> anyhow::Result<Self> {
+        let version = ml_features_schema_version(&batch.schema())?;
+        ml_features_row(batch, row, version)
+    }
+}
+
+/// Read one `MlFeatures` row written with schema `version`.
+///
+/// Columns introduced after `version` are filled with `None`, and columns this build does
+/// not know about are ignored, so files written by older and newer releases both load.
+fn ml_features_row(batch: &RecordBatch, row: usize, version: u32) -> anyhow::Result<MlFeatures> {
+    let candle = Candle::try_from((batch, row))?;
+    let optional = |name: &str| -> anyhow::Result<Option<f64>> {
+        let since = ML_FEATURE_COLUMNS
+            .iter()
+            .find_map(|(column, since)| (*column == name).then_some(*since))
+            .unwrap_or(ML_FEATURES_SCHEMA_VERSION);
+        if version < since && batch.column_by_name(name).is_none() {
+            return Ok(None);
+        }
+        let array = typed_column::<Float64Array>(batch, name)?;
+        Ok((!array.is_null(row)).then(|| array.value(row)))
+    };
+
+    Ok(MlFeatures {
+        timestamp: candle.timestamp,
+        open: candle.open,
+        high: candle.high,
+        low: candle.low,
+        close: candle.close,
+        volume: candle.volume,
+        rsi: optional("rsi")?,
+        mfi: optional("mfi")?,
+        sma_20: optional("sma_20")?,
+        ema_12: optional("ema_12")?,
+        price_change_pct: optional("price_change_pct")?,
+        volatility_pct: optional("volatility_pct")?,
+    })
+}
+
+/// Convert an Arrow RecordBatch with the ML feature schema back to `MlFeatures`
+pub fn from_ml_features_batch(batch: &RecordBatch) -> anyhow::Result<Vec<MlFeatures>> {
+    (0..batch.num_rows())
+        .map(|row| MlFeatures::try_from((batch, row)))
+        .collect()
+}
+
+/// Load ML features from a Parquet file written by any version of [`save_ml_features_parquet`].
+///
+/// The schema version stored in the file decides which columns must be present.
+pub fn load_ml_features_parquet(path: &str) -> anyhow::Result<Vec<MlFeatures>> {
+    let file = File::open(path)?;
+    let builder = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(file)?;
+    let version = ml_features_schema_version(builder.schema())?;
+    if version > ML_FEATURES_SCHEMA_VERSION {
+        tracing::warn!(
+            "{} uses ML feature schema v{}, newer than v{}; unknown columns are ignored",
+            path,
+            version,
+            ML_FEATURES_SCHEMA_VERSION
+        );
+    }
+
+    let mut features = Vec::new();
+    for batch in builder.build()? {
+        let batch = batch?;
+        for row in 0..batch.num_rows() {
+            features.push(ml_features_row(&batch, row, version)?);
+        }
+    }
+    Ok(features)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{daily_candles, fixture_start, flat_candle};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_row_conversion_round_trip() -> anyhow::Result<()> {
+        let ticker = Ticker {
+            symbol: "VCB".to_string(),
+            exchange: "HOSE".to_string(),
+            sector: Some("Finance".to_string()),
+            founded: Some(1963),
+            ..Default::default()
+        };
+        let batch = to_batch(vec![Ticker::default(), ticker])?;
+
+        let row = Ticker::try_from((&batch, 1))?;
+        assert_eq!(row.symbol, "VCB");
+        assert_eq!(row.sector.as_deref(), Some("Finance"));
+        assert_eq!(row.description, None);
+        assert_eq!(row.founded, Some(1963));
+        assert!(Ticker::try_from((&batch, 2)).is_err());
+
+        let candle = daily_candles(1).remove(0);
+        let batch = candles_to_batch(std::slice::from_ref(&candle))?;
+        assert!(Candle::try_from((&batch, 0))?.approx_eq(&candle, 0.0));
+
+        // A ticker batch has no candle columns
+        assert!(Candle::try_from((&to_batch(vec![Ticker::default()])?, 0)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_null_candle_value_is_an_error() -> anyhow::Result<()> {
+        // Files from other writers may not enforce the non-nullable columns
+        let schema = Schema::new(
+            candle_schema()
+                .fields()
+                .iter()
+                .map(|field| field.as_ref().clone().with_nullable(true))
+                .collect::<Vec<_>>(),
+        );
+        let value = |close: Option<f64>| Arc::new(Float64Array::from(vec![close])) as ArrayRef;
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(
+                    TimestampMillisecondArray::from(vec![1_704_160_800_000]).with_timezone("UTC"),
+                ),
+                value(Some(1.0)),
+                value(Some(2.0)),
+                value(Some(0.5)),
+                value(None),
+                value(Some(100.0)),
+                value(None),
+            ],
+        )?;
+
+        let err = Candle::try_from((&batch, 0)).unwrap_err().to_string();
+        assert!(err.contains("non-nullable column 'close'"), "{err}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_candles_parquet_export() -> anyhow::Result<()> {
+        let candles = daily_candles(3);
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("candles.parquet");
+        save_candles_parquet(&candles, path.to_str().unwrap(), Compression::UNCOMPRESSED)?;
+
+        let file = File::open(&path)?;
+        let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(file)?
+            .build()?;
+        let rows: usize = reader
+            .map(|batch| batch.map(|b| b.num_rows()))
+            .sum::<Result<usize, _>>()?;
+
+        assert_eq!(rows, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_batched_export_row_group_size() -> anyhow::Result<()> {
+        let tickers = (0..25)
+            .map(|i| Ticker {
+                symbol: format!("T{i}"),
+                exchange: "HOSE".to_string(),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let properties = parquet_properties(Compression::SNAPPY)
+            .into_builder()
+            .set_max_row_group_size(8)
+            .build();
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("row_groups.parquet");
+        let path = path.to_str().unwrap();
+        save_parquet_batched(tickers, path, 10, Some(properties))?;
+
+        let builder = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(
+            File::open(path)?,
+        )?;
+        let sizes = builder
+            .metadata()
+            .row_groups()
+            .iter()
+            .map(|group| group.num_rows())
+            .collect::<Vec<_>>();
+
+        assert_eq!(sizes, [8, 8, 8, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_zstd_export_is_smaller_than_uncompressed() -> anyhow::Result<()> {
+        let candles = (0..5000)
+            .map(|i| flat_candle(fixture_start() + chrono::Duration::minutes(i), 10.5))
+            .collect::<Vec<_>>();
+
+        let size = |name: &str, compression: Compression| -> anyhow::Result<u64> {
+            let dir = tempfile::tempdir()?;
+            let path = dir.path().join(name);
+            save_candles_parquet(&candles, path.to_str().unwrap(), compression)?;
+            let len = std::fs::metadata(&path)?.len();
+            Ok(len)
+        };
+        let plain = size("plain.parquet", Compression::UNCOMPRESSED)?;
+        let zstd = size("zstd.parquet", default_parquet_compression())?;
+
+        assert!(
+            zstd < plain,
+            "zstd {zstd} bytes, uncompressed {plain} bytes"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_candles_parquet() -> anyhow::Result<()> {
+        let candles = daily_candles(5);
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("append_candles.parquet");
+        let path = path.to_str().unwrap();
+        append_candles_parquet(path, &candles[..2])?;
+        append_candles_parquet(path, &candles[2..3])?;
+        append_candles_parquet(path, &candles[3..])?;
+        // Already stored: neither duplicated nor an empty row group
+        append_candles_parquet(path, &candles[1..4])?;
+
+        let builder = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(
+            File::open(path)?,
+        )?;
+        assert_eq!(builder.metadata().num_row_groups(), 3);
+        let mut restored = Vec::new();
+        for batch in builder.build()? {
+            restored.extend(candles_from_batch(&batch?)?);
+        }
+
+        assert_eq!(restored.len(), 5);
+        assert_eq!(restored[4].timestamp, candles[4].timestamp);
+
+        // Files with another schema are left alone
+        let tickers = dir.path().join("append_tickers.parquet");
+        let tickers = tickers.to_str().unwrap();
+        save_parquet(vec![Ticker::default()], tickers, Compression::SNAPPY)?;
+        assert!(append_candles_parquet(tickers, &candles).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_save_candles_parquet_streaming() -> anyhow::Result<()> {
+        let candles = daily_candles(7);
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("streamed.parquet");
+        let path = path.to_str().unwrap();
+        let stream = futures::stream::iter(candles.clone().into_iter().map(Ok));
+        let rows = save_candles_parquet_streaming(stream, path, 3).await?;
+
+        let builder = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(
+            File::open(path)?,
+        )?;
+        assert_eq!(builder.schema().fields(), candle_schema().fields());
+        assert_eq!(builder.metadata().num_row_groups(), 3);
+        let mut restored = Vec::new();
+        for batch in builder.build()? {
+            restored.extend(candles_from_batch(&batch?)?);
+        }
+
+        assert_eq!(rows, 7);
+        assert_eq!(restored.len(), 7);
+        assert_eq!(restored[6].close, candles[6].close);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ml_features_nulls_survive_round_trip() -> anyhow::Result<()> {
+        let features = MlFeatures {
+            timestamp: Utc.with_ymd_and_hms(2024, 1, 2, 2, 0, 0).unwrap(),
+            open: 10.0,
+            high: 11.0,
+            low: 9.0,
+            close: 10.5,
+            volume: 1000.0,
+            rsi: Some(55.0),
+            mfi: None,
+            sma_20: Some(f64::NAN),
+            ema_12: None,
+            price_change_pct: Some(-1.5),
+            volatility_pct: None,
+        };
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("ml_features.parquet");
+        save_ml_features_parquet(vec![features.clone()], path.to_str().unwrap())?;
+
+        let restored = load_ml_features_parquet(path.to_str().unwrap())?;
+
+        assert_eq!(restored.len(), 1);
+        let row = &restored[0];
+        assert_eq!(row.timestamp, features.timestamp);
+        assert_eq!(row.rsi, Some(55.0));
+        assert_eq!(row.mfi, None);
+        assert_eq!(row.ema_12, None);
+        assert_eq!(row.volatility_pct, None);
+        // NaN is a value, not a missing one
+        assert!(row.sma_20.is_some_and(f64::is_nan));
+        assert_eq!(row.price_change_pct, Some(-1.5));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ml_features_schema_evolution() -> anyhow::Result<()> {
+        let candle = flat_candle(Utc.with_ymd_and_hms(2024, 1, 2, 2, 0, 0).unwrap(), 10.5);
+        let base = candles_to_batch(&[candle])?;
+        let extend = |extra: Vec<(&str, ArrayRef)>, metadata: Option<&str>| {
+            let mut fields = base.schema().fields().iter().cloned().collect::<Vec<_>>();
+            let mut columns = base.columns().to_vec();
+            for (name, column) in extra {
+                fields.push(Arc::new(Field::new(name, DataType::Float64, true)));
+                columns.push(column);
+            }
+            let mut schema = Schema::new(fields);
+            if let Some(version) = metadata {
+                schema = schema.with_metadata(std::collections::HashMap::from([(
+                    ML_FEATURES_VERSION_KEY.to_string(),
+                    version.to_string(),
+                )]));
+            }
+            RecordBatch::try_new(Arc::new(schema), columns)
+        };
+        let rsi: ArrayRef = Arc::new(Float64Array::from(vec![Some(40.0)]));
+        let unknown: ArrayRef = Arc::new(Float64Array::from(vec![Some(1.0)]));
+
+        // Unversioned file with a subset of columns plus one this build doesn't know
+        let legacy = extend(vec![("rsi", rsi.clone()), ("unknown", unknown)], None)?;
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("ml_features_legacy.parquet");
+        let mut writer = ArrowWriter::try_new(File::create(&path)?, legacy.schema(), None)?;
+        writer.write(&legacy)?;
+        writer.close()?;
+        let loaded = load_ml_features_parquet(path.to_str().unwrap())?;
+
+        assert_eq!(loaded[0].rsi, Some(40.0));
+        assert_eq!(loaded[0].mfi, None);
+        assert_eq!(loaded[0].volatility_pct, None);
+
+        // A current-version batch must carry every current column
+        let truncated = extend(vec![("rsi", rsi)], Some("1"))?;
+        assert!(from_ml_features_batch(&truncated).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_export_in_local_timezone() -> anyhow::Result<()> {
+        let candle = flat_candle(Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap(), 10.5);
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("candles_tz.csv");
+        let path = path.to_str().unwrap();
+
+        save_candles_csv(
+            std::slice::from_ref(&candle),
+            path,
+            Some(chrono_tz::Asia::Ho_Chi_Minh),
+        )?;
+        let local = std::fs::read_to_string(path)?;
+        save_candles_csv(std::slice::from_ref(&candle), path, None)?;
+        let utc = std::fs::read_to_string(path)?;
+
+        assert!(local.contains("2024-01-02T07:00:00+07:00"), "{local}");
+        assert!(!utc.contains("+07:00"), "{utc}");
+        Ok(())
+    }
+}
@@ -1 +1,7 @@
+pub mod align;
+pub mod config;
+pub mod dataset;
 pub mod format;
+pub mod output;
+#[cfg(feature = "polars")]
+pub mod polars;
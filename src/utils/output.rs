@@ -0,0 +1,325 @@
+use crate::finance::{
+    doctor::{CheckResult, CheckStatus},
+    models::{Candle, HealthStatus, PriceStats, Ticker},
+};
+use serde::Serialize;
+use std::io::Write;
+
+/// Output format shared by every read command of the CLI.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable, column aligned table
+    #[default]
+    Table,
+    /// RFC 4180 CSV with a header row
+    Csv,
+    /// Pretty-printed JSON
+    Json,
+}
+
+/// A record that can be rendered as a row of a table or CSV file.
+///
+/// Missing values are empty cells: CSV leaves the field empty and tables show `N/A`.
+pub trait Tabular: Serialize {
+    fn headers() -> Vec<&'static str>;
+    fn row(&self) -> Vec<String>;
+}
+
+impl Tabular for Ticker {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "symbol",
+            "exchange",
+            "description",
+            "currency",
+            "country",
+            "market_type",
+            "industry",
+            "sector",
+            "founded",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.symbol.clone(),
+            self.exchange.clone(),
+            opt_to_string(&self.description),
+            opt_to_string(&self.currency),
+            opt_to_string(&self.country),
+            opt_to_string(&self.market_type),
+            opt_to_string(&self.industry),
+            opt_to_string(&self.sector),
+            opt_to_string(&self.founded),
+        ]
+    }
+}
+
+impl Tabular for Candle {
+    fn headers() -> Vec<&'static str> {
+        vec!["timestamp", "open", "high", "low", "close", "volume"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.timestamp.to_rfc3339(),
+            self.open.to_string(),
+            self.high.to_string(),
+            self.low.to_string(),
+            self.close.to_string(),
+            self.volume.to_string(),
+        ]
+    }
+}
+
+impl Tabular for CheckResult {
+    fn headers() -> Vec<&'static str> {
+        vec!["check", "status", "detail"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let status = match self.status {
+            CheckStatus::Pass => "pass",
+            CheckStatus::Fail => "fail",
+            CheckStatus::Skip => "skip",
+        };
+        vec![
+            self.name.to_string(),
+            status.to_string(),
+            self.detail.clone(),
+        ]
+    }
+}
+
+impl Tabular for PriceStats {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "count",
+            "first",
+            "last",
+            "min_close",
+            "max_close",
+            "total_volume",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.count.to_string(),
+            opt_to_string(&self.first.map(|t| t.to_rfc3339())),
+            opt_to_string(&self.last.map(|t| t.to_rfc3339())),
+            opt_to_string(&self.min_close),
+            opt_to_string(&self.max_close),
+            self.total_volume.to_string(),
+        ]
+    }
+}
+
+impl Tabular for HealthStatus {
+    fn headers() -> Vec<&'static str> {
+        vec!["healthy", "pool_size", "idle_connections", "missing_tables"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.is_healthy().to_string(),
+            self.pool_size.to_string(),
+            self.idle_connections.to_string(),
+            self.missing_tables.join(","),
+        ]
+    }
+}
+
+fn opt_to_string<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// How a table shows `cell`, with missing values spelled out.
+fn table_cell(cell: String) -> String {
+    if cell.is_empty() {
+        "N/A".to_string()
+    } else {
+        cell
+    }
+}
+
+/// Writes records in the selected [`OutputFormat`] so every command emits the same shape.
+pub struct OutputWriter<W: Write> {
+    format: OutputFormat,
+    out: W,
+}
+
+impl OutputWriter<std::io::Stdout> {
+    pub fn stdout(format: OutputFormat) -> Self {
+        Self::new(format, std::io::stdout())
+    }
+}
+
+impl<W: Write> OutputWriter<W> {
+    pub fn new(format: OutputFormat, out: W) -> Self {
+        Self { format, out }
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// Write a list of records. Tables get aligned columns, CSV gets a header row,
+    /// JSON is emitted as an array.
+    pub fn write_list<T: Tabular>(&mut self, items: &[T]) -> anyhow::Result<()> {
+        match self.format {
+            OutputFormat::Table => {
+                let headers = T::headers();
+                let rows = items
+                    .iter()
+                    .map(|item| item.row().into_iter().map(table_cell).collect::<Vec<_>>())
+                    .collect::<Vec<_>>();
+
+                let mut widths = headers.iter().map(|h| h.len()).collect::<Vec<_>>();
+                for row in &rows {
+                    for (width, cell) in widths.iter_mut().zip(row) {
+                        *width = (*width).max(cell.chars().count());
+                    }
+                }
+
+                let header_line = headers
+                    .iter()
+                    .zip(&widths)
+                    .map(|(h, &w)| format!("{h:<w$}"))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                writeln!(self.out, "{}", header_line.trim_end())?;
+                writeln!(
+                    self.out,
+                    "{}",
+                    "-".repeat(widths.iter().sum::<usize>() + widths.len().saturating_sub(1))
+                )?;
+
+                for row in rows {
+                    let line = row
+                        .iter()
+                        .zip(&widths)
+                        .map(|(cell, &w)| format!("{cell:<w$}"))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    writeln!(self.out, "{}", line.trim_end())?;
+                }
+            }
+            OutputFormat::Csv => {
+                self.write_csv_line(&T::headers())?;
+                for item in items {
+                    self.write_csv_line(&item.row())?;
+                }
+            }
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut self.out, items)?;
+                writeln!(self.out)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write a single record. Tables use a vertical `field: value` layout.
+    pub fn write_one<T: Tabular>(&mut self, item: &T) -> anyhow::Result<()> {
+        match self.format {
+            OutputFormat::Table => {
+                let headers = T::headers();
+                let width = headers.iter().map(|h| h.len()).max().unwrap_or(0);
+                for (header, cell) in headers.iter().zip(item.row().into_iter().map(table_cell)) {
+                    writeln!(
+                        self.out,
+                        "{:<width$} {}",
+                        format!("{header}:"),
+                        cell,
+                        width = width + 1
+                    )?;
+                }
+            }
+            OutputFormat::Csv => {
+                self.write_csv_line(&T::headers())?;
+                self.write_csv_line(&item.row())?;
+            }
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut self.out, item)?;
+                writeln!(self.out)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_csv_line<S: AsRef<str>>(&mut self, fields: &[S]) -> anyhow::Result<()> {
+        let line = fields
+            .iter()
+            .map(|f| csv_escape(f.as_ref()))
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(self.out, "{line}")?;
+        Ok(())
+    }
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_output_escapes_fields() -> anyhow::Result<()> {
+        let ticker = Ticker {
+            symbol: "VCB".to_string(),
+            exchange: "HOSE".to_string(),
+            description: Some("Bank for Foreign Trade, \"Vietcombank\"".to_string()),
+            ..Default::default()
+        };
+
+        let mut buf = Vec::new();
+        OutputWriter::new(OutputFormat::Csv, &mut buf).write_list(&[ticker])?;
+        let output = String::from_utf8(buf)?;
+        let lines = output.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("symbol,exchange,description"));
+        assert!(lines[1].starts_with("VCB,HOSE,\"Bank for Foreign Trade, \"\"Vietcombank\"\"\""));
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_values_are_empty_in_csv() -> anyhow::Result<()> {
+        let ticker = Ticker {
+            symbol: "VCB".to_string(),
+            exchange: "HOSE".to_string(),
+            ..Default::default()
+        };
+
+        let mut buf = Vec::new();
+        OutputWriter::new(OutputFormat::Csv, &mut buf).write_list(std::slice::from_ref(&ticker))?;
+        let csv = String::from_utf8(buf)?;
+        assert_eq!(csv.lines().nth(1), Some("VCB,HOSE,,,,,,,"));
+
+        let mut buf = Vec::new();
+        OutputWriter::new(OutputFormat::Table, &mut buf).write_list(&[ticker])?;
+        let table = String::from_utf8(buf)?;
+        assert!(table.lines().nth(2).unwrap().contains("N/A"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_output_is_array() -> anyhow::Result<()> {
+        let mut buf = Vec::new();
+        OutputWriter::new(OutputFormat::Json, &mut buf)
+            .write_list(&[Ticker::default(), Ticker::default()])?;
+        let value: serde_json::Value = serde_json::from_slice(&buf)?;
+
+        assert_eq!(value.as_array().map(Vec::len), Some(2));
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use crate::finance::models::{Candle, Ticker};
+use crate::utils::format::{candles_to_batch, to_batch};
+use ::polars::prelude::{DataFrame, IpcReader, SerReader};
+use arrow::{ipc::writer::FileWriter, record_batch::RecordBatch};
+use std::io::Cursor;
+
+/// Convert candles to a Polars `DataFrame` with the columns of the candle Arrow schema.
+///
+/// `timestamp` comes out as `Datetime(ms, UTC)`.
+pub fn candles_to_polars(candles: &[Candle]) -> anyhow::Result<DataFrame> {
+    batch_to_polars(&candles_to_batch(candles)?)
+}
+
+/// Convert tickers to a Polars `DataFrame` with the columns of the ticker Arrow schema.
+pub fn tickers_to_polars(tickers: &[Ticker]) -> anyhow::Result<DataFrame> {
+    batch_to_polars(&to_batch(tickers.to_vec())?)
+}
+
+/// Hand a RecordBatch to Polars through an in-memory Arrow IPC file, which keeps column
+/// types (including timestamp time zones) intact without depending on matching Arrow crates.
+fn batch_to_polars(batch: &RecordBatch) -> anyhow::Result<DataFrame> {
+    let mut buffer = Vec::new();
+    {
+        let mut writer = FileWriter::try_new(&mut buffer, &batch.schema())?;
+        writer.write(batch)?;
+        writer.finish()?;
+    }
+    Ok(IpcReader::new(Cursor::new(buffer)).finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::daily_candles;
+    use ::polars::prelude::{DataType, TimeUnit, TimeZone};
+
+    #[test]
+    fn test_candles_and_tickers_to_polars() -> anyhow::Result<()> {
+        let candles = daily_candles(3);
+
+        let df = candles_to_polars(&candles)?;
+        assert_eq!(df.height(), 3);
+        assert_eq!(
+            df.get_column_names_str(),
+            [
+                "timestamp",
+                "open",
+                "high",
+                "low",
+                "close",
+                "volume",
+                "adj_close"
+            ]
+        );
+        assert_eq!(
+            df.column("timestamp")?.dtype(),
+            &DataType::Datetime(TimeUnit::Milliseconds, Some(TimeZone::UTC))
+        );
+        assert_eq!(df.column("close")?.dtype(), &DataType::Float64);
+
+        let tickers = vec![Ticker {
+            symbol: "VCB".to_string(),
+            exchange: "HOSE".to_string(),
+            founded: Some(1963),
+            ..Default::default()
+        }];
+        let df = tickers_to_polars(&tickers)?;
+        assert_eq!(df.height(), 1);
+        assert_eq!(df.get_column_names_str()[..2], ["symbol", "exchange"]);
+        assert_eq!(df.column("symbol")?.dtype(), &DataType::String);
+        assert_eq!(df.column("founded")?.dtype(), &DataType::Int64);
+        Ok(())
+    }
+}